@@ -0,0 +1,109 @@
+//! How many generations each live cell has been alive, and how long ago recently dead cells
+//! died, for coloring cells by age and drawing trails.
+
+use std::collections::HashMap;
+
+/// The age of every live cell, keyed by its coordinates: 0 for a cell born in the latest
+/// generation (or drawn in), 1 for one that survived a generation, and so on. Cells that died
+/// within the last `trail` generations are kept with the generations since they died, 1 for
+/// one that died in the latest generation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ages {
+    ages: HashMap<(i64, i64), u32>,
+    deaths: HashMap<(i64, i64), u32>,
+    trail: u32,
+}
+
+impl Ages {
+    /// Ages for cells that are all newborn, with no deaths yet.
+    pub fn new(live: impl Iterator<Item = (i64, i64)>, trail: u32) -> Self {
+        Self {
+            ages: live.map(|cell| (cell, 0)).collect(),
+            deaths: HashMap::new(),
+            trail,
+        }
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> Option<u32> {
+        self.ages.get(&(x, y)).copied()
+    }
+
+    /// Generations since the cell died, if it did within the trail length.
+    pub fn since_death(&self, x: i64, y: i64) -> Option<u32> {
+        self.deaths.get(&(x, y)).copied()
+    }
+
+    /// The recently dead cells with the generations since they died.
+    pub fn deaths(&self) -> impl Iterator<Item = ((i64, i64), u32)> + '_ {
+        self.deaths.iter().map(|(&cell, &since)| (cell, since))
+    }
+
+    pub fn trail(&self) -> u32 {
+        self.trail
+    }
+
+    /// Changes how many generations dead cells are remembered for, forgetting any that died
+    /// longer ago than that.
+    pub fn set_trail(&mut self, trail: u32) {
+        self.trail = trail;
+        self.deaths.retain(|_, since| *since <= trail);
+    }
+
+    /// Follows a cell being set by hand: a cell brought to life is newborn, one that already
+    /// was alive keeps its age. Erased cells leave no trail.
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        self.deaths.remove(&(x, y));
+        if alive {
+            self.ages.entry((x, y)).or_insert(0);
+        } else {
+            self.ages.remove(&(x, y));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ages.clear();
+        self.deaths.clear();
+    }
+
+    /// Moves on by `generations` to the given live cells. Cells alive before and after grow
+    /// older by that many generations, the rest are newborn, and cells no longer alive start a
+    /// trail as if they had just died. This is exact for a single generation and an
+    /// approximation for a jump over several.
+    pub fn advance(&mut self, live: impl Iterator<Item = (i64, i64)>, generations: u64) {
+        let older = u32::try_from(generations).unwrap_or(u32::MAX);
+        let ages: HashMap<(i64, i64), u32> = live
+            .map(|cell| (cell, self.ages.get(&cell).map_or(0, |age| age.saturating_add(older))))
+            .collect();
+
+        if self.trail > 0 {
+            let trail = self.trail;
+            self.deaths.retain(|cell, since| {
+                *since = since.saturating_add(older);
+                *since <= trail && !ages.contains_key(cell)
+            });
+            for &cell in self.ages.keys() {
+                if !ages.contains_key(&cell) {
+                    self.deaths.insert(cell, 1);
+                }
+            }
+        }
+        self.ages = ages;
+    }
+
+    /// A copy with every cell moved by (dx, dy), keeping only those where `keep` holds.
+    pub fn shifted(&self, dx: i64, dy: i64, keep: impl Fn(i64, i64) -> bool) -> Self {
+        let shift = |cells: &HashMap<(i64, i64), u32>| {
+            cells
+                .iter()
+                .map(|(&(x, y), &n)| ((x + dx, y + dy), n))
+                .filter(|&((x, y), _)| keep(x, y))
+                .collect()
+        };
+        Self { ages: shift(&self.ages), deaths: shift(&self.deaths), trail: self.trail }
+    }
+
+    /// Bytes used by the ages and trails.
+    pub fn memory_size(&self) -> usize {
+        (self.ages.capacity() + self.deaths.capacity()) * std::mem::size_of::<((i64, i64), u32)>()
+    }
+}
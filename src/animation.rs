@@ -0,0 +1,223 @@
+//! Animations of a run as animated GIF or APNG files, looping forever, or as videos. GIF shows
+//! everywhere but holds at most 256 colors a frame, so frames with more have the rest matched to
+//! the nearest; APNG keeps every color. WebM and MP4 videos stay small for long and large runs,
+//! and need the `video` feature (see `video`).
+
+use std::collections::HashMap;
+
+use eframe::egui::{self, Color32};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+    WebM,
+    Mp4,
+}
+
+impl AnimationFormat {
+    pub const ALL: [AnimationFormat; 4] = [AnimationFormat::Gif, AnimationFormat::Apng, AnimationFormat::WebM, AnimationFormat::Mp4];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "GIF",
+            AnimationFormat::Apng => "APNG",
+            AnimationFormat::WebM => "WebM video",
+            AnimationFormat::Mp4 => "MP4 video",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+            AnimationFormat::WebM => "webm",
+            AnimationFormat::Mp4 => "mp4",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "image/gif",
+            AnimationFormat::Apng => "image/apng",
+            AnimationFormat::WebM => "video/webm",
+            AnimationFormat::Mp4 => "video/mp4",
+        }
+    }
+
+    /// Whether the format can be written in this build: videos need the `video` feature, on
+    /// native targets.
+    pub fn is_available(self) -> bool {
+        cfg!(all(feature = "video", not(target_arch = "wasm32"))) || !matches!(self, AnimationFormat::WebM | AnimationFormat::Mp4)
+    }
+
+    /// Guesses the format from a file name, e.g. `gun.gif`.
+    pub fn from_path(path: &str) -> Option<AnimationFormat> {
+        let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        match extension.as_str() {
+            "gif" => Some(AnimationFormat::Gif),
+            "png" | "apng" => Some(AnimationFormat::Apng),
+            "webm" => Some(AnimationFormat::WebM),
+            "mp4" => Some(AnimationFormat::Mp4),
+            _ => None,
+        }
+    }
+
+    /// Encodes `count` frames of the given size, each shown for `delay` milliseconds. The
+    /// frames are made one at a time as they are encoded. Videos can only be saved to a file.
+    pub fn encode(self, [width, height]: [usize; 2], count: usize, frames: impl Iterator<Item = egui::ColorImage>, delay: u16) -> Result<Vec<u8>, String> {
+        match self {
+            AnimationFormat::Gif => encode_gif([width, height], frames, delay),
+            AnimationFormat::Apng => encode_apng([width, height], count, frames, delay),
+            AnimationFormat::WebM | AnimationFormat::Mp4 => Err(format!("A {} can only be saved to a file", self.name())),
+        }
+    }
+
+    /// Encodes the frames like `encode` and writes them to a file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(self, path: &str, size: [usize; 2], count: usize, frames: impl Iterator<Item = egui::ColorImage>, delay: u16) -> Result<(), String> {
+        match self {
+            AnimationFormat::Gif | AnimationFormat::Apng => std::fs::write(path, self.encode(size, count, frames, delay)?).map_err(|e| e.to_string()),
+            #[cfg(feature = "video")]
+            AnimationFormat::WebM | AnimationFormat::Mp4 => crate::video::save(path, self, size, frames, delay),
+            #[cfg(not(feature = "video"))]
+            AnimationFormat::WebM | AnimationFormat::Mp4 => Err(format!("A {} needs the video feature", self.name())),
+        }
+    }
+}
+
+fn encode_apng([width, height]: [usize; 2], count: usize, frames: impl Iterator<Item = egui::ColorImage>, delay: u16) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(count as u32, 0).map_err(|e| e.to_string())?;
+    encoder.set_frame_delay(delay, 1000).map_err(|e| e.to_string())?;
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    for frame in frames {
+        let data: Vec<u8> = frame.pixels.iter().flat_map(|color| [color.r(), color.g(), color.b()]).collect();
+        writer.write_image_data(&data).map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+fn encode_gif([width, height]: [usize; 2], frames: impl Iterator<Item = egui::ColorImage>, delay: u16) -> Result<Vec<u8>, String> {
+    let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(format!("A GIF can't be {width}x{height} pixels"));
+    };
+    let mut bytes = b"GIF89a".to_vec();
+    bytes.extend(width.to_le_bytes());
+    bytes.extend(height.to_le_bytes());
+    bytes.extend([0, 0, 0]); // no global color table, each frame has its own
+    // Loop forever
+    bytes.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+    let centiseconds = (delay / 10).to_le_bytes();
+    for frame in frames {
+        let (palette, indices) = palette(&frame.pixels);
+        let bits = (usize::BITS - (palette.len() - 1).leading_zeros()).max(1) as u8;
+        bytes.extend([0x21, 0xf9, 4, 0, centiseconds[0], centiseconds[1], 0, 0]);
+        bytes.push(0x2c);
+        bytes.extend([0, 0, 0, 0]);
+        bytes.extend(width.to_le_bytes());
+        bytes.extend(height.to_le_bytes());
+        bytes.push(0x80 | (bits - 1));
+        for i in 0..1 << bits {
+            let color = palette.get(i).copied().unwrap_or(Color32::BLACK);
+            bytes.extend([color.r(), color.g(), color.b()]);
+        }
+        let min_code_size = bits.max(2);
+        bytes.push(min_code_size);
+        for block in lzw(&indices, min_code_size).chunks(255) {
+            bytes.push(block.len() as u8);
+            bytes.extend(block);
+        }
+        bytes.push(0);
+    }
+    bytes.push(0x3b);
+    Ok(bytes)
+}
+
+/// Up to 256 colors for the pixels, the first ones found, and each pixel's index into them:
+/// its own color, or the nearest if its color didn't fit.
+fn palette(pixels: &[Color32]) -> (Vec<Color32>, Vec<u8>) {
+    let mut palette = Vec::new();
+    let mut index: HashMap<Color32, u8> = HashMap::new();
+    let indices = pixels
+        .iter()
+        .map(|&color| {
+            if let Some(&i) = index.get(&color) {
+                return i;
+            }
+            let i = if palette.len() < 256 {
+                palette.push(color);
+                palette.len() - 1
+            } else {
+                let distance = |other: &Color32| [0, 1, 2].map(|c| (color[c] as i32 - other[c] as i32).pow(2)).iter().sum::<i32>();
+                (0..palette.len()).min_by_key(|&i| distance(&palette[i])).unwrap()
+            };
+            index.insert(color, i as u8);
+            i as u8
+        })
+        .collect();
+    (palette, indices)
+}
+
+/// Compresses color indices as GIF image data, with codes growing from `min_code_size + 1`
+/// bits up to 12 and the table starting over once it is full.
+fn lzw(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut out = Vec::new();
+    let (mut buffer, mut filled) = (0u32, 0u32);
+    let mut write = |code: u16, size: u32| {
+        buffer |= (code as u32) << filled;
+        filled += size;
+        while filled >= 8 {
+            out.push(buffer as u8);
+            buffer >>= 8;
+            filled -= 8;
+        }
+    };
+
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut size = min_code_size as u32 + 1;
+    let mut next = end + 1;
+    write(clear, size);
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let Some(code) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+        if let Some(&longer) = table.get(&(code, index)) {
+            prefix = Some(longer);
+            continue;
+        }
+        write(code, size);
+        if next < 4096 {
+            table.insert((code, index), next);
+            // Decoders widen the codes once the table reaches the width's limit
+            if next == 1 << size {
+                size += 1;
+            }
+            next += 1;
+        } else {
+            write(clear, size);
+            table.clear();
+            size = min_code_size as u32 + 1;
+            next = end + 1;
+        }
+        prefix = Some(index as u16);
+    }
+    if let Some(code) = prefix {
+        write(code, size);
+        // The decoder adds an entry for the last code too
+        if next < 4096 && next == 1 << size {
+            size += 1;
+        }
+    }
+    write(end, size);
+    write(0, 7); // pushes out the last partial byte
+    out
+}
@@ -0,0 +1,257 @@
+//! Turmites, of which Langton's ant is the best known: agents that walk the grid instead of the
+//! cells following a rule. Each generation an ant looks up its state and the color of its cell
+//! in a table, which says what color to paint the cell, which way to turn and what state to go
+//! into, and then steps forward.
+
+use std::fmt;
+
+/// The direction an ant faces, with y growing downwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Heading {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    pub const ALL: [Heading; 4] = [Heading::North, Heading::East, Heading::South, Heading::West];
+
+    /// The heading after a turn.
+    pub fn turned(self, turn: Turn) -> Heading {
+        Self::ALL[(self as usize + turn as usize) % 4]
+    }
+
+    /// The step (dx, dy) to the cell in front.
+    pub fn offset(self) -> (i64, i64) {
+        match self {
+            Heading::North => (0, -1),
+            Heading::East => (1, 0),
+            Heading::South => (0, 1),
+            Heading::West => (-1, 0),
+        }
+    }
+}
+
+/// A turn relative to the way an ant faces, in quarter turns clockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Turn {
+    None,
+    Right,
+    Back,
+    Left,
+}
+
+impl Turn {
+    pub const ALL: [Turn; 4] = [Turn::None, Turn::Right, Turn::Back, Turn::Left];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Turn::None => "No turn",
+            Turn::Right => "Right",
+            Turn::Back => "U-turn",
+            Turn::Left => "Left",
+        }
+    }
+
+    /// Golly's code for the turn in turmite strings.
+    fn code(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// The letter for the turn in names like `RLR`: N, R, U or L.
+    fn letter(self) -> char {
+        match self {
+            Turn::None => 'N',
+            Turn::Right => 'R',
+            Turn::Back => 'U',
+            Turn::Left => 'L',
+        }
+    }
+}
+
+/// What an ant does on a cell: the color it leaves the cell, how it turns and the state it goes
+/// into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Transition {
+    pub color: u8,
+    pub turn: Turn,
+    pub state: u8,
+}
+
+/// The table of a turmite with `states` internal states walking cells of `colors` colors, with
+/// the transition for state `s` on color `c` at `table[s * colors + c]`. Color 0 is a dead cell,
+/// 1 a live one, and higher colors are higher cell states.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Turmite {
+    pub states: u8,
+    pub colors: u8,
+    pub table: Vec<Transition>,
+}
+
+impl Turmite {
+    pub const MAX_STATES: u8 = 16;
+    pub const MAX_COLORS: u8 = 16;
+
+    /// Well-known turmites in the notation `parse` takes, with their names and what to expect.
+    pub const PRESETS: [(&'static str, &'static str, &'static str); 5] = [
+        ("Langton's ant", "RL", "Chaos for about 10,000 steps, then an endless diagonal highway"),
+        ("LLRR", "LLRR", "Grows symmetrically, one side mirroring the other"),
+        ("RLR", "RLR", "Grows chaotically and never seems to build a highway"),
+        ("Square filler", "LRRRRRLLR", "Fills a growing square"),
+        ("Fibonacci spiral", "{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}", "Two states: grows a spiral"),
+    ];
+
+    /// Langton's ant: turn right on a dead cell, left on a live one, and flip the cell.
+    pub fn langtons_ant() -> Turmite {
+        Turmite::from_turns(&[Turn::Right, Turn::Left])
+    }
+
+    /// A one-state turmite that turns the given way on each color and moves the cell on to the
+    /// next color, like the multi-color generalizations of Langton's ant.
+    pub fn from_turns(turns: &[Turn]) -> Turmite {
+        let colors = turns.len() as u8;
+        let table = (0..colors).zip(turns).map(|(color, &turn)| Transition { color: (color + 1) % colors, turn, state: 0 }).collect();
+        Turmite { states: 1, colors, table }
+    }
+
+    /// The transition for an ant in `state` on a cell of `color`. Colors beyond the table's
+    /// count as 0.
+    pub fn next(&self, state: u8, color: u8) -> Transition {
+        let color = if color < self.colors { color } else { 0 };
+        self.table[state as usize * self.colors as usize + color as usize]
+    }
+
+    /// The same turmite with another number of states or colors, from 1 and 2 up to the maxima.
+    /// New entries leave the cell and state as they are and don't turn, and references to
+    /// states and colors that are gone go to 0.
+    pub fn resized(&self, states: u8, colors: u8) -> Turmite {
+        let (states, colors) = (states.clamp(1, Self::MAX_STATES), colors.clamp(2, Self::MAX_COLORS));
+        let mut table = Vec::with_capacity(states as usize * colors as usize);
+        for state in 0..states {
+            for color in 0..colors {
+                let old = (state < self.states && color < self.colors).then(|| self.next(state, color));
+                let Transition { color: write, turn, state: next } = old.unwrap_or(Transition { color, turn: Turn::None, state });
+                let (write, next) = (if write < colors { write } else { 0 }, if next < states { next } else { 0 });
+                table.push(Transition { color: write, turn, state: next });
+            }
+        }
+        Turmite { states, colors, table }
+    }
+
+    /// Parses a turmite: a string of turns such as `RL` or `LLRR`, one per color, with `N` for
+    /// no turn and `U` for a U-turn, or Golly's turmite notation, which lists each state's
+    /// `{color, turn, state}` for each color, with turns coded 1 for none, 2 for right, 4 for a
+    /// U-turn and 8 for left: `{{{1,2,0},{0,8,0}}}` is Langton's ant.
+    pub fn parse(text: &str) -> Result<Turmite, String> {
+        let text = text.trim();
+        if !text.starts_with('{') {
+            let turns: Option<Vec<Turn>> =
+                text.chars().map(|c| Turn::ALL.into_iter().find(|turn| turn.letter() == c.to_ascii_uppercase())).collect();
+            return match turns {
+                Some(turns) if (2..=Self::MAX_COLORS as usize).contains(&turns.len()) => Ok(Turmite::from_turns(&turns)),
+                _ => Err(format!("Expected 2 to {} of the turns L, R, N and U, or a {{{{{{...}}}}}} table, got \"{text}\"", Self::MAX_COLORS)),
+            };
+        }
+
+        // Numbers at depth 3 make up a transition, transitions at depth 2 a state
+        let mut states: Vec<Vec<[u32; 3]>> = Vec::new();
+        let (mut state, mut numbers) = (Vec::new(), Vec::new());
+        let mut depth = 0;
+        let malformed = || format!("Expected {{{{{{color,turn,state}},...}},...}} with braces around each transition and state: \"{text}\"");
+        for piece in text.split_inclusive(['{', '}', ',']) {
+            let (value, delimiter) = match piece.char_indices().last() {
+                Some((i, c)) if "{},".contains(c) => (piece[..i].trim(), c),
+                _ => return Err(malformed()),
+            };
+            if !value.is_empty() {
+                numbers.push(value.parse().map_err(|_| format!("\"{value}\" is not a number in \"{text}\""))?);
+            }
+            match (delimiter, depth) {
+                ('{', 0..=2) => depth += 1,
+                ('}', 3) => match numbers[..] {
+                    [color, turn, next] => state.push([color, turn, next]),
+                    _ => return Err(format!("Each transition needs a color, a turn and a state: \"{text}\"")),
+                },
+                ('}', 2) if numbers.is_empty() => states.push(std::mem::take(&mut state)),
+                ('}', 1) if numbers.is_empty() => {}
+                (',', 1..=3) => continue,
+                _ => return Err(malformed()),
+            }
+            if delimiter == '}' {
+                numbers.clear();
+                depth -= 1;
+            }
+        }
+        if depth != 0 || states.is_empty() {
+            return Err(malformed());
+        }
+
+        let colors = states[0].len();
+        if states.iter().any(|state| state.len() != colors) {
+            return Err(format!("Every state needs a transition for each color: \"{text}\""));
+        }
+        if states.len() > Self::MAX_STATES as usize || !(2..=Self::MAX_COLORS as usize).contains(&colors) {
+            return Err(format!("Turmites have up to {} states and 2 to {} colors", Self::MAX_STATES, Self::MAX_COLORS));
+        }
+        let mut table = Vec::new();
+        for [color, code, next] in states.iter().flatten().copied() {
+            let Some(turn) = Turn::ALL.into_iter().find(|turn| turn.code() as u32 == code) else {
+                return Err(format!("Unknown turn {code}, expected 1, 2, 4 or 8"));
+            };
+            if color as usize >= colors || next as usize >= states.len() {
+                return Err(format!("{{{color},{code},{next}}} goes to a color or state the table doesn't have"));
+            }
+            table.push(Transition { color: color as u8, turn, state: next as u8 });
+        }
+        Ok(Turmite { states: states.len() as u8, colors: colors as u8, table })
+    }
+}
+
+impl Default for Turmite {
+    fn default() -> Self {
+        Turmite::langtons_ant()
+    }
+}
+
+/// One-state turmites that move each cell on to the next color are written as their turns,
+/// others in Golly's notation.
+impl fmt::Display for Turmite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Turmite::from_turns(&self.table.iter().map(|transition| transition.turn).collect::<Vec<_>>()) {
+            return self.table.iter().try_for_each(|transition| write!(f, "{}", transition.turn.letter()));
+        }
+        let state = |s: &[Transition]| {
+            let transitions: Vec<String> = s.iter().map(|t| format!("{{{},{},{}}}", t.color, t.turn.code(), t.state)).collect();
+            format!("{{{}}}", transitions.join(","))
+        };
+        let states: Vec<String> = self.table.chunks(self.colors as usize).map(state).collect();
+        write!(f, "{{{}}}", states.join(","))
+    }
+}
+
+/// An ant in some state on the cell at (x, y).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ant {
+    pub x: i64,
+    pub y: i64,
+    pub heading: Heading,
+    pub state: u8,
+}
+
+impl Ant {
+    /// An ant in state 0 facing north, as ants are usually started.
+    pub fn new(x: i64, y: i64) -> Self {
+        Self { x, y, heading: Heading::North, state: 0 }
+    }
+
+    /// Turns and changes state according to the turmite's table and the color of the cell under
+    /// the ant: returns the color to paint the cell. The ant still has to move on to the cell
+    /// in front.
+    pub fn turn(&mut self, turmite: &Turmite, color: u8) -> u8 {
+        let transition = turmite.next(self.state, color);
+        self.heading = self.heading.turned(transition.turn);
+        self.state = transition.state;
+        transition.color
+    }
+}
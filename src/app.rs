@@ -0,0 +1,4271 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use eframe::egui::{self, Color32, Pos2, Rect, Vec2};
+use eframe::App;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(not(target_arch = "wasm32"))]
+use once_cell::sync::Lazy;
+
+#[cfg(feature = "serde")]
+use crate::recovery;
+use crate::worker;
+use crate::benchmark::{Backend, Benchmark, Workload};
+use crate::perf::PerfStats;
+use crate::soup::{self, Object, ObjectNames};
+use crate::{camera, formats, rainbow, rule, screenshot, shapes, svg};
+use crate::animation::AnimationFormat;
+use crate::ants::{Turmite, Turn};
+use crate::automaton::Automaton;
+use crate::camera::Camera;
+use crate::catalog::{self, Catalog};
+use crate::cycle::CycleDetector;
+use crate::fetch::{self, Download};
+use crate::formats::Format;
+use crate::history::{History, UndoStack};
+use crate::library::LIBRARY;
+use crate::life3d::{Rule3d, Universe3d};
+use crate::noise::Noise;
+use crate::pattern::Pattern;
+use crate::permalink;
+use crate::rng::Rng;
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+use crate::replay::{Event, Player, Recorder, Replay};
+use crate::rule::{Neighborhood, Rule, Shape};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rulefile::RuleFile;
+use crate::selection::Selection;
+#[cfg(feature = "serde")]
+use crate::session::Session;
+use crate::shapes::{BrushShape, Symmetry};
+use crate::stats::{Changes, PopulationLog};
+use crate::topology::{Edge, Topology};
+use crate::tracking::Tracker;
+use crate::theme::{Appearance, Theme};
+use crate::universe::{Engine, Universe};
+
+const MIN_GRID_LENGTH: usize = 10;
+const MAX_GRID_LENGTH: usize = 1024;
+const RANDOM_FILL_DENSITY: f64 = 0.5; // Starting density of random fills
+const RANDOM_FILL_SIZE: i64 = 64; // Side of the square randomly filled in an infinite universe
+// Shortcuts listed in the help overlay, as (keys, action)
+const SHORTCUTS: [(&str, &str); 23] = [
+    ("Space or P", "Play / pause"),
+    ("S or Tab", "Step one generation"),
+    ("Shift+S", "Step back one generation"),
+    ("+ / -", "Faster / slower"),
+    ("C", "Clear the grid"),
+    ("R", "Random fill with a new seed (rotates the stamp while stamping)"),
+    ("F", "Fit the view to the live cells (mirrors the stamp while stamping)"),
+    ("M", "Show or hide the minimap"),
+    ("F3", "Show or hide the performance overlay"),
+    ("1 to 8", "Draw, erase, line, rectangle, ellipse, fill, select, ant"),
+    ("Ctrl+Z", "Undo"),
+    ("Ctrl+Y or Ctrl+Shift+Z", "Redo"),
+    ("Ctrl+C", "Copy the selection"),
+    ("Ctrl+X", "Cut the selection"),
+    ("Ctrl+V", "Paste a pattern"),
+    ("Ctrl+Shift+1 to 9", "Save the grid to a slot"),
+    ("Ctrl+1 to 9", "Restore the grid from a slot"),
+    ("Delete", "Clear the selection"),
+    ("Arrow keys", "Move the cell cursor, or nudge the selection"),
+    ("Enter, or Space with the cursor shown", "Toggle the cell under the cursor"),
+    ("Esc", "Cancel stamping, pasting, the selection or the cursor"),
+    ("Right or middle drag", "Pan"),
+    ("F1 or H", "Show or hide this help"),
+];
+const MAX_FILL_CELLS: usize = 1 << 20; // Larger fills of an infinite universe are assumed to have leaked out of an enclosure
+const MAX_UNDO: usize = 200; // Edits that can be undone
+const SLOTS: usize = 9; // Quick-save slots, one per number key
+const MAX_ANIMATION_FRAMES: usize = 1000;
+// Memory the timeline of past generations may use
+#[cfg(not(target_arch = "wasm32"))]
+const HISTORY_BYTES: usize = 256 << 20;
+#[cfg(target_arch = "wasm32")]
+const HISTORY_BYTES: usize = 64 << 20;
+const MAX_WARP_GENERATIONS: u64 = 1 << 20;
+const CYCLE_WINDOW: usize = 1024; // Longest period that cycle detection can find
+const MAX_CHART_SAMPLES: usize = 10_000; // Population samples kept before thinning them out
+const STATS_FILE: &str = "stats.csv"; // Where the population samples are exported to
+// Below this many points per cell, cells are drawn as density-shaded blocks instead of one by one
+const LOD_CELL_SIZE: f32 = 2.0;
+// Live cells in the second, third and fourth colors of colored rules; the first is the alive color
+const CELL_COLORS: [Color32; 3] = [Color32::from_rgb(230, 80, 70), Color32::from_rgb(70, 130, 230), Color32::from_rgb(230, 190, 40)];
+const MAX_SPACE_GENERATIONS: u64 = 16; // 3D generations stepped per tick, since they are stepped on the UI thread
+const POLYGON_CELL_SIZE: f32 = 8.0; // Smallest hexagons or triangles drawn one by one rather than as texels or with no outlines
+/// Corners of a cell's hexagon around its center in world coordinates, where rows are one cell
+/// apart: the cells closest to each center, so that they tile the sheared grid.
+const HEXAGON: [Vec2; 6] = [
+    Vec2::new(0.0, -0.625),
+    Vec2::new(0.5, -0.375),
+    Vec2::new(0.5, 0.375),
+    Vec2::new(0.0, 0.625),
+    Vec2::new(-0.5, 0.375),
+    Vec2::new(-0.5, -0.375),
+];
+// Sharp cells when zoomed in, and averaged rather than dropped cells when zoomed far out
+const GRID_TEXTURE_OPTIONS: egui::TextureOptions = egui::TextureOptions {
+    magnification: egui::TextureFilter::Nearest,
+    minification: egui::TextureFilter::Linear,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+static START_TIME: Lazy<Instant> = Lazy::new(Instant::now);
+
+/// The shape of the cells on screen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Lattice {
+    Square,
+    Hexagonal,  // rows sheared by half a cell, see `GameOfLifeApp::grid_to_world`
+    Triangular, // alternating upward and downward triangles, see `rule::is_upward`
+}
+
+/// How the primary mouse button acts on the grid.
+/// Panning is always done with the secondary or middle button.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Draw,      // click or drag to bring cells to life
+    Erase,     // click or drag to kill cells
+    Line,      // drag to draw a straight line
+    Rectangle, // drag out a rectangle
+    Ellipse,   // drag out an ellipse
+    Fill,      // click to fill the dead region around a cell
+    Select,    // drag to select a rectangle
+    Ant,       // click to put down or pick up an ant
+}
+
+impl Tool {
+    const ALL: [Tool; 8] = [Tool::Draw, Tool::Erase, Tool::Line, Tool::Rectangle, Tool::Ellipse, Tool::Fill, Tool::Select, Tool::Ant];
+
+    fn name(self) -> &'static str {
+        match self {
+            Tool::Draw => "Draw",
+            Tool::Erase => "Erase",
+            Tool::Line => "Line",
+            Tool::Rectangle => "Rectangle",
+            Tool::Ellipse => "Ellipse",
+            Tool::Fill => "Fill",
+            Tool::Select => "Select",
+            Tool::Ant => "Ant",
+        }
+    }
+}
+
+/// How the 3D universe is shown.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SpaceView {
+    Slices, // one layer at a time, with the layers either side showing through
+    Orbit,  // the whole cube, turned around by dragging
+}
+
+impl SpaceView {
+    const ALL: [SpaceView; 2] = [SpaceView::Slices, SpaceView::Orbit];
+
+    fn name(self) -> &'static str {
+        match self {
+            SpaceView::Slices => "Slices",
+            SpaceView::Orbit => "Orbit",
+        }
+    }
+}
+
+/// A quick-save slot: a copy of the universe, with its rule, and a name for it.
+#[derive(Clone)]
+struct Slot {
+    name: String,
+    universe: Universe,
+}
+
+/// Objects found in the universe at one generation, or why none could be.
+struct ObjectCensus {
+    generation: u64,
+    rule: Rule,
+    objects: Result<Vec<(Object, usize)>, String>,
+    names: ObjectNames,
+}
+
+/// Actions bound to keys in `handle_shortcut_keys`.
+#[derive(Clone, Copy)]
+enum Shortcut {
+    PlayPause,
+    Step,
+    StepBack,
+    Faster,
+    Slower,
+    Clear,
+    RandomFill,
+    Fit,
+    ToggleMinimap,
+    TogglePerf,
+    ToggleHelp,
+    Tool(Tool),
+}
+
+pub struct GameOfLifeApp {
+    universe: Universe,
+    is_playing: bool, // track if the game is playing, e.g. evolving
+    last_update: f64,
+    update_frequency: f32,
+    history: History, // Recent generations, including the one being shown
+    edits: UndoStack, // Universe before each edit, for undo/redo
+    slots: Vec<Option<Slot>>, // Quick-save slots, to branch experiments from the same start
+    pattern_format: Format, // Format used when exporting
+    pattern_text: String, // Pattern text pasted in by the user for import
+    #[cfg(not(target_arch = "wasm32"))]
+    pattern_path: String, // File used by the pattern open/save actions
+    #[cfg(not(target_arch = "wasm32"))]
+    image_path: String, // File the image of the universe is saved to
+    image_scale: usize, // Pixels per cell in saved images and animations
+    animation: Option<Vec<Universe>>, // Frames of the animation being recorded, if any
+    animation_format: AnimationFormat,
+    animation_delay: u16, // Milliseconds each frame is shown
+    animation_length: u64, // Generations recorded before the animation saves itself
+    #[cfg(not(target_arch = "wasm32"))]
+    animation_path: String, // File animations are saved to
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    session_path: String, // File used by the session open/save actions
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    replay_path: String, // File replays are recorded to and played back from
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    recorder: Option<Recorder>, // Replay being recorded, if any
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    player: Option<Player>, // Replay being played back, if any
+    status: String, // Feedback from the last import/export action
+    tool: Tool, // What dragging on the grid does
+    stroke_last: Option<(i64, i64)>, // Last cell painted by the drag in progress
+    stroke_alive: bool, // Whether the drag in progress brings cells to life or kills them
+    shape_drag: Option<((i64, i64), (i64, i64))>, // Start and end cell of the line/rectangle/ellipse being dragged
+    shape_filled: bool, // Whether rectangles and ellipses are filled in or just outlined
+    symmetry: Symmetry,
+    show_help: bool, // Whether the keyboard shortcut overlay is open
+    cursor: Option<(i64, i64)>, // Cell edited from the keyboard, shown once an arrow key is pressed
+    cursor_moved: bool, // Scroll the view to the cursor on the next frame if it is out of sight
+    brush_shape: BrushShape,
+    brush_radius: i64, // 0 paints single cells
+    selection: Option<Selection>,
+    clipboard: Option<Pattern>, // Last copied or cut selection
+    paste: Option<Pattern>, // Pattern following the cursor until a click places it
+    stamp: Option<(String, Pattern)>, // Catalog pattern placed by clicking the grid, and its name, if any
+    stamp_rotation: u8, // Number of clockwise quarter turns applied to the stamp
+    stamp_flipped: bool, // Whether the stamp is mirrored (applied before rotating)
+    camera: Camera,
+    show_minimap: bool,
+    show_perf: bool, // Whether the performance overlay is shown
+    #[cfg(feature = "profiling")]
+    show_profiler: bool,
+    perf: PerfStats,
+    fit_requested: bool, // Fit the camera to the live cells on the next frame, once the viewport is known
+    step_exponent: u32, // HashLife advances 2^step_exponent generations per tick
+    advance_count: u64, // Generations advanced by the "Advance" button
+    warp: bool, // Tick on every frame instead of every update_frequency seconds
+    warp_generations: u64, // Generations per tick in warp mode
+    warp_unlimited: bool, // Size warp_generations automatically to fill each frame
+    step_started: f64, // When the step in progress was started, to size unlimited warp batches
+    target_generation: u64, // Generation the "Run to" button runs until
+    run_target: Option<u64>, // Generation being run to as fast as possible, while running
+    run_batch: u64, // Generations per step while running to the target, sized to fill each frame
+    threads: usize, // Worker threads for the bit-packed engines
+    worker: worker::Worker, // Steps the universe off the UI thread
+    #[cfg(target_arch = "wasm32")]
+    ctx: egui::Context, // Woken up when the page drives the app through `WebHandle`
+    #[cfg(target_arch = "wasm32")]
+    event_target: Option<String>, // Id of the element the page listens on for the app's events
+    #[cfg(target_arch = "wasm32")]
+    emitted: (u64, usize, bool), // Generation, population and whether it had stabilized, as last told to the page
+    grid_texture: Option<(egui::TextureHandle, Universe)>, // Finite grid drawn one texel per cell, and the universe it shows
+    theme: Theme,
+    appearance: Appearance,
+    dark_mode: bool, // Whether dark visuals are in use, to notice when that changes
+    color_by_age: bool, // Shade live cells by how long they have been alive
+    age_span: u32,      // Age at which cells reach the oldest shade
+    trail_length: u32,  // Generations that dead cells fade out over, 0 for no trails
+    rainbow: bool, // Give live cells colors that newborns inherit, as in Rainbow Life
+    rainbow_brush: Color32, // Color of cells drawn in while rainbow is on
+    rule: Rule,
+    rule_text: String, // Rule being typed in B/S notation
+    rule_error: Option<String>, // Why rule_text can't be used, if it can't
+    automaton: Option<Arc<dyn Automaton>>, // Loaded rule file or automaton handed in, used instead of the rule
+    #[cfg(not(target_arch = "wasm32"))]
+    rule_file_path: String, // File used by the rule file load action
+    draw_state: u8, // State drawn cells get under an automaton with more than two, or color under a colored rule
+    ants: bool, // Turmites walk the grid instead of the rule and automaton
+    turmite: Turmite, // Table the ants follow
+    turmite_text: String, // Turmite being typed in
+    turmite_error: Option<String>, // Why turmite_text can't be used, if it can't
+    noise: Noise, // Randomness added to every generation
+    fill_density: f64, // Fraction of cells a random fill brings to life
+    fill_seed: u64, // Seed of random fills, so that a soup can be made again
+    fill_symmetry: Symmetry, // Symmetry of random fills, for symmetric soups
+    three_d: bool, // Show and step the 3D universe instead of the grid
+    space: Universe3d,
+    space_rule_text: String, // 3D rule being typed in
+    space_rule_error: Option<String>, // Why space_rule_text can't be used, if it can't
+    space_view: SpaceView,
+    space_layer: usize, // Layer shown by the slice view
+    orbit: Vec2, // Yaw and pitch of the orbit view, in radians
+    orbit_zoom: f32, // On-screen size of a voxel in the orbit view, in points
+    population_log: PopulationLog,
+    show_chart: bool,
+    show_benchmark: bool,
+    show_census: bool,
+    census: Option<ObjectCensus>,
+    census_live: bool, // Retake the census whenever the generation changes
+    track_ships: bool,
+    tracker: Tracker,
+    tracking_error: Option<String>, // Why nothing is being tracked, if it isn't
+    show_browser: bool,
+    catalog: Catalog,
+    browser_query: String,
+    save_name: String, // Name and author the selection or pattern is saved to the catalog under
+    save_author: String,
+    show_url_dialog: bool,
+    pattern_url: String, // URL or LifeWiki pattern name typed into the "Load from URL" dialog
+    download: Option<Download>,
+    play_on_load: bool, // Play once the download is in, for links that ask to
+    following: Option<u64>, // Id of the tracked spaceship the camera follows
+    benchmark: Option<Benchmark>, // The last benchmark run, finished or not
+    cycles: CycleDetector,
+    cycle: Option<(u64, bool)>, // Generations after which the universe repeats, and whether that is exactly the period
+    pause_on_cycle: bool,
+    #[cfg(feature = "serde")]
+    recovered: Option<Session>, // Session left behind by a crash, offered for restoring
+    #[cfg(feature = "serde")]
+    last_snapshot: f64, // When the session was last written for crash recovery
+}
+
+impl GameOfLifeApp {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let appearance = cc.storage.and_then(|s| eframe::get_value(s, Appearance::STORAGE_KEY)).unwrap_or(Appearance::System);
+        let dark_mode = appearance.is_dark(cc.integration_info.system_theme);
+        cc.egui_ctx.set_visuals(if dark_mode { egui::Visuals::dark() } else { egui::Visuals::light() });
+        let universe = Universe::new(48, 32);
+        let history = History::new(universe.clone(), HISTORY_BYTES);
+        let center = Pos2::new(universe.width() as f32 / 2.0, universe.height() as f32 / 2.0);
+        let app = Self {
+            universe,
+            is_playing: false,
+            last_update: get_current_time(),
+            update_frequency: 0.5,
+            history,
+            edits: UndoStack::new(MAX_UNDO),
+            slots: vec![None; SLOTS],
+            pattern_format: Format::Rle,
+            pattern_text: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            pattern_path: "pattern.rle".to_string(),
+            #[cfg(not(target_arch = "wasm32"))]
+            image_path: "universe.png".to_string(),
+            image_scale: 4,
+            animation: None,
+            animation_format: AnimationFormat::Gif,
+            animation_delay: 100,
+            animation_length: 100,
+            #[cfg(not(target_arch = "wasm32"))]
+            animation_path: "animation.gif".to_string(),
+            #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+            session_path: "session.ron".to_string(),
+            #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+            replay_path: "replay.ron".to_string(),
+            #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+            recorder: None,
+            #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+            player: None,
+            status: String::new(),
+            tool: Tool::Draw,
+            stroke_last: None,
+            stroke_alive: true,
+            shape_drag: None,
+            shape_filled: false,
+            symmetry: Symmetry::None,
+            show_help: false,
+            cursor: None,
+            cursor_moved: false,
+            brush_shape: BrushShape::Square,
+            brush_radius: 0,
+            selection: None,
+            clipboard: None,
+            paste: None,
+            stamp: None,
+            stamp_rotation: 0,
+            stamp_flipped: false,
+            camera: Camera::new(center, 20.0),
+            show_minimap: true,
+            show_perf: false,
+            #[cfg(feature = "profiling")]
+            show_profiler: false,
+            perf: PerfStats::default(),
+            fit_requested: false,
+            step_exponent: 0,
+            advance_count: 100,
+            warp: false,
+            warp_generations: 10,
+            warp_unlimited: false,
+            step_started: 0.0,
+            target_generation: 1103,
+            run_target: None,
+            run_batch: 1,
+            threads: default_threads(),
+            worker: worker::Worker::new(cc.egui_ctx.clone()),
+            #[cfg(target_arch = "wasm32")]
+            ctx: cc.egui_ctx.clone(),
+            #[cfg(target_arch = "wasm32")]
+            event_target: None,
+            #[cfg(target_arch = "wasm32")]
+            emitted: (0, 0, false),
+            grid_texture: None,
+            color_by_age: false,
+            age_span: 100,
+            trail_length: 0,
+            rainbow: false,
+            rainbow_brush: Color32::from_rgb(255, 0, 0),
+            rule: Rule::CONWAY,
+            rule_text: Rule::CONWAY.to_string(),
+            rule_error: None,
+            automaton: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            rule_file_path: "rule.rule".to_string(),
+            draw_state: 1,
+            ants: false,
+            turmite: Turmite::langtons_ant(),
+            turmite_text: Turmite::langtons_ant().to_string(),
+            noise: Noise::default(),
+            fill_density: RANDOM_FILL_DENSITY,
+            fill_seed: get_current_time().to_bits(),
+            fill_symmetry: Symmetry::None,
+            turmite_error: None,
+            three_d: false,
+            space: Universe3d::new(32),
+            space_rule_text: Rule3d::LIFE_5766.to_string(),
+            space_rule_error: None,
+            space_view: SpaceView::Slices,
+            space_layer: 16,
+            orbit: Vec2::new(0.6, -0.4),
+            orbit_zoom: 8.0,
+            population_log: PopulationLog::new(MAX_CHART_SAMPLES),
+            show_chart: false,
+            show_benchmark: false,
+            show_census: false,
+            census: None,
+            census_live: false,
+            track_ships: false,
+            tracker: Tracker::default(),
+            tracking_error: None,
+            show_browser: false,
+            catalog: Catalog::new(cc.storage.and_then(|s| eframe::get_value(s, Catalog::STORAGE_KEY)).unwrap_or_default()),
+            browser_query: String::new(),
+            save_name: String::new(),
+            save_author: String::new(),
+            show_url_dialog: false,
+            pattern_url: String::new(),
+            download: None,
+            play_on_load: false,
+            following: None,
+            benchmark: None,
+            cycles: CycleDetector::new(CYCLE_WINDOW),
+            cycle: None,
+            pause_on_cycle: false,
+            #[cfg(feature = "serde")]
+            recovered: None,
+            #[cfg(feature = "serde")]
+            last_snapshot: get_current_time(),
+            theme: cc
+                .storage
+                .and_then(|s| eframe::get_value(s, Theme::STORAGE_KEY))
+                .unwrap_or_else(|| Theme::for_dark_mode(dark_mode)),
+            appearance,
+            dark_mode,
+        };
+        // Carry on where the last run left off
+        #[cfg(feature = "serde")]
+        let app = {
+            let mut app = app;
+            let saved = cc.storage.and_then(|s| s.get_string(Session::STORAGE_KEY));
+            if let Some(session) = saved.as_deref().and_then(|text| Session::from_stored(text).ok()) {
+                if let Err(e) = app.apply_session(session) {
+                    app.status = format!("Carried on from the last session, but {e}");
+                }
+            }
+            // A snapshot newer than what eframe saved last means the app didn't exit normally
+            app.recovered = recovery::read().filter(|snapshot| Some(snapshot) != saved.as_ref()).and_then(|text| Session::from_stored(&text).ok());
+            app
+        };
+        app
+    }
+
+    /// Starts with an automaton of another crate's in place of the rule, shown and unloaded
+    /// like a rule file.
+    pub fn with_automaton(mut self, automaton: Arc<dyn Automaton>) -> Self {
+        self.load_automaton(automaton);
+        self
+    }
+
+    /// Starts on a finite grid of the given size, within the sizes the sliders allow.
+    pub fn with_grid_size(mut self, width: usize, height: usize) -> Self {
+        self.universe.set_infinite(false);
+        let clamp = |length: usize| length.clamp(MIN_GRID_LENGTH, MAX_GRID_LENGTH);
+        self.resize_grid(clamp(width), clamp(height));
+        self.fit_requested = true;
+        self
+    }
+
+    /// Starts in an infinite universe, unless the rule brings empty space to life.
+    pub fn with_infinite(mut self) -> Self {
+        self.universe.set_infinite(true);
+        self.reset_history();
+        self.fit_requested = true;
+        self
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.universe.set_topology(topology);
+        self.cancel_step();
+        self
+    }
+
+    /// Starts with a rule, as if typed in; a rule the universe can't follow shows in the status.
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule_text = rule.to_string();
+        self.apply_rule_text();
+        if let Some(err) = &self.rule_error {
+            self.status = format!("Can't start with {rule}: {err}");
+        }
+        self
+    }
+
+    /// Starts with this many seconds between generations, within the slider's range.
+    pub fn with_update_frequency(mut self, seconds: f32) -> Self {
+        self.update_frequency = seconds.clamp(0.1, 2.0);
+        self
+    }
+
+    /// Starts with a pattern file open, as the Open button would open it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_pattern_file(mut self, path: &str) -> Self {
+        self.pattern_path = path.to_string();
+        self.open_pattern();
+        self
+    }
+
+    pub fn with_playing(mut self, playing: bool) -> Self {
+        self.is_playing = playing;
+        self
+    }
+
+    /// Sends the page `generation`, `population` and `stabilized` events, dispatched on the
+    /// element with this id.
+    #[cfg(target_arch = "wasm32")]
+    pub fn with_event_target(mut self, id: &str) -> Self {
+        self.event_target = Some(id.to_string());
+        self
+    }
+
+    /// Tells the page what changed since the last frame: a `generation` event when the
+    /// universe moved to another generation, `population` when the number of live cells
+    /// changed, and `stabilized` when it started repeating.
+    #[cfg(target_arch = "wasm32")]
+    fn emit_events(&mut self) {
+        use wasm_bindgen::JsValue;
+        let Some(target) = &self.event_target else { return };
+        let (generation, population) = (self.universe.generation(), self.universe.population());
+        let stabilized = self.cycle.is_some();
+        let (last_generation, last_population, was_stabilized) = self.emitted;
+        let state = || vec![("generation", JsValue::from_f64(generation as f64)), ("population", JsValue::from_f64(population as f64))];
+        if generation != last_generation {
+            wasm::dispatch(target, "generation", state());
+        }
+        if population != last_population {
+            wasm::dispatch(target, "population", state());
+        }
+        if let Some((period, exact)) = self.cycle.filter(|_| !was_stabilized) {
+            // A period found over steps of several generations may be a multiple of the real one
+            let fields = [("period", JsValue::from_f64(period as f64)), ("exact", JsValue::from_bool(exact))];
+            wasm::dispatch(target, "stabilized", state().into_iter().chain(fields));
+        }
+        self.emitted = (generation, population, stabilized);
+    }
+
+    fn draw_grid(&mut self, ui: &mut egui::Ui) {
+        profile_scope!();
+        let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+        let viewport = response.rect;
+        let stamp = self.oriented_stamp();
+
+        if self.fit_requested {
+            let bounds = self.live_bounds().unwrap_or_else(|| self.default_bounds());
+            self.camera.fit(viewport, bounds, 20.0);
+            self.fit_requested = false;
+        }
+
+        if self.track_ships && !self.three_d {
+            self.tracking_error = self.tracker.update(&self.universe).err();
+            if let Some(id) = self.following {
+                match self.tracker.find(id) {
+                    Some(track) => self.camera.center = self.grid_to_world(Pos2::new(track.center.0 as f32 + 0.5, track.center.1 as f32 + 0.5)),
+                    None => {
+                        self.following = None;
+                        self.status = "Lost the spaceship being followed".to_string();
+                    }
+                }
+            }
+        }
+
+        // Two or more fingers pan and pinch-zoom. Whatever the first finger painted before the
+        // others came down was not meant as an edit, so it is undone.
+        let multi_touch = ui.input(|i| i.multi_touch()).filter(|_| response.hovered() || response.dragged());
+        if multi_touch.is_some() {
+            if self.stroke_last.take().is_some() {
+                self.undo();
+            }
+            self.shape_drag = None;
+        }
+
+        // Clicking or dragging on the minimap moves the camera there instead of editing
+        let minimap = self.show_minimap.then(|| self.minimap_rect(viewport));
+        let on_minimap = |pos: Option<Pos2>| matches!((minimap, pos), (Some(m), Some(p)) if m.contains(p));
+        if let Some(touch) = multi_touch {
+            self.camera.pan(touch.translation_delta);
+            let center = ui.input(|i| i.pointer.hover_pos()).unwrap_or(touch.start_pos);
+            self.camera.zoom_at(viewport, center, touch.zoom_delta);
+        } else if (response.clicked() || response.dragged()) && on_minimap(response.interact_pointer_pos()) {
+            let pos = response.interact_pointer_pos().unwrap();
+            let minimap = minimap.unwrap();
+            self.camera.center = self.minimap_world(viewport).min + (pos - minimap.min) / self.minimap_scale(viewport, minimap);
+        } else if response.dragged_by(egui::PointerButton::Secondary) || response.dragged_by(egui::PointerButton::Middle) {
+            // Drag to pan, which stops following a spaceship
+            self.camera.pan(response.drag_delta());
+            self.following = None;
+        } else if response.dragged() && self.tool == Tool::Select {
+            // Drag out a selection from the cell where the button went down
+            let origin = ui.input(|i| i.pointer.press_origin());
+            if let (Some(origin), Some(pos)) = (origin, response.interact_pointer_pos()) {
+                let selection = Selection::from_corners(self.world_cell(viewport, origin), self.world_cell(viewport, pos));
+                self.selection = match self.universe.is_infinite() {
+                    true => Some(selection),
+                    false => selection.clamped(self.universe.width(), self.universe.height()),
+                };
+            }
+        }
+
+        // Scroll to zoom around the cursor
+        if let Some(hover_pos) = response.hover_pos() {
+            let scroll = ui.input(|i| i.scroll_delta.y);
+            if scroll != 0.0 {
+                self.camera.zoom_at(viewport, hover_pos, (scroll * 0.002).exp());
+            }
+        }
+
+        // Paint while the primary button is held down, joining the cells under the pointer on
+        // consecutive frames with a line so that fast strokes don't leave gaps
+        let painting = response.is_pointer_button_down_on()
+            && ui.input(|i| i.pointer.primary_down())
+            && matches!(self.tool, Tool::Draw | Tool::Erase)
+            && stamp.is_none()
+            && multi_touch.is_none()
+            && !on_minimap(response.interact_pointer_pos());
+        match response.interact_pointer_pos().filter(|_| painting) {
+            Some(pos) => {
+                let cell = self.world_cell(viewport, pos);
+                let from = match self.stroke_last {
+                    Some(last) => last,
+                    None => {
+                        self.record_edit(); // one undo step per stroke
+                        // On touch screens a stroke starting on a live cell erases, so that
+                        // tapping toggles cells
+                        let touch = ui.input(|i| i.any_touches());
+                        self.stroke_alive = self.tool == Tool::Draw && !(touch && self.universe.get(cell.0, cell.1));
+                        cell
+                    }
+                };
+                if self.stroke_last != Some(cell) {
+                    let alive = self.stroke_alive;
+                    let brush = self.brush_shape.offsets(self.brush_radius);
+                    for (cx, cy) in shapes::line(from, cell) {
+                        for &(dx, dy) in &brush {
+                            self.paint(cx + dx, cy + dy, alive);
+                        }
+                    }
+                    self.stroke_last = Some(cell);
+                    self.reset_history();
+                }
+            }
+            None => self.stroke_last = None,
+        }
+
+        // Shapes follow the pointer while the button is down and are drawn when it is released
+        let shaping = response.is_pointer_button_down_on()
+            && ui.input(|i| i.pointer.primary_down())
+            && matches!(self.tool, Tool::Line | Tool::Rectangle | Tool::Ellipse)
+            && stamp.is_none()
+            && multi_touch.is_none();
+        let origin = ui.input(|i| i.pointer.press_origin());
+        match (origin, response.interact_pointer_pos().filter(|_| shaping)) {
+            (Some(origin), Some(pos)) => {
+                self.shape_drag = Some((self.world_cell(viewport, origin), self.world_cell(viewport, pos)));
+            }
+            _ => {
+                if let Some((start, end)) = self.shape_drag.take() {
+                    self.record_edit();
+                    for (x, y) in self.shape_cells(start, end) {
+                        self.paint(x, y, true);
+                    }
+                    self.reset_history();
+                }
+            }
+        }
+
+        // Clicks place the pasted or library pattern, fill, or drop the selection
+        if response.clicked() && !on_minimap(response.interact_pointer_pos()) {
+            if let Some((x, y)) = response.interact_pointer_pos().and_then(|pos| self.cell_at(viewport, pos)) {
+                if let Some(stamp) = &stamp {
+                    // Place the pattern centered on the clicked cell
+                    self.record_edit();
+                    self.stamp_pattern(stamp, x, y);
+                    self.paste = None;
+                    self.reset_history();
+                } else if self.tool == Tool::Fill {
+                    self.fill(x, y);
+                } else if self.tool == Tool::Select {
+                    self.selection = None;
+                } else if self.tool == Tool::Ant {
+                    self.record_edit();
+                    self.set_ants(true);
+                    self.universe.toggle_ant(x, y);
+                    self.reset_history();
+                }
+            }
+        }
+
+        // Define the stroke for the grid lines
+        let grid_line_stroke = egui::Stroke::new(1.0, self.theme.grid_line);
+
+        let visible = self.camera.visible_world(viewport);
+        if self.universe.is_infinite() {
+            // The visible area of an infinite universe may hold millions of cells, so paint the
+            // background once and then only the live cells
+            painter.rect_filled(viewport, 0.0, self.theme.dead);
+            if self.camera.cell_size < LOD_CELL_SIZE {
+                // Too small to draw cell by cell: shade blocks of cells by how many are alive
+                let block = (LOD_CELL_SIZE / self.camera.cell_size).ceil() as i64;
+                for ((bx, by), count) in density_blocks(self.universe.live_cells(), block, self.grid_bounds(visible)) {
+                    let corner = self.grid_to_world(Pos2::new((bx * block) as f32, (by * block) as f32));
+                    let min = self.camera.world_to_screen(viewport, corner);
+                    let rect = Rect::from_min_size(min, Vec2::splat(block as f32 * self.camera.cell_size));
+                    painter.rect_filled(rect, 0.0, density_color(&self.theme, count, block));
+                }
+            } else {
+                let in_view = |x: i64, y: i64| visible.intersects(self.cell_bounds(x, y));
+                for ((x, y), since) in self.universe.recent_deaths() {
+                    if in_view(x, y) {
+                        self.fill_cell(&painter, viewport, x, y, self.theme.trail_color(since, self.trail_length));
+                    }
+                }
+                for (x, y) in self.universe.live_cells() {
+                    if in_view(x, y) {
+                        let style = (self.color_by_age, self.age_span, self.trail_length);
+                        self.fill_cell(&painter, viewport, x, y, cell_color(&self.theme, &self.universe, x, y, style));
+                    }
+                }
+                // Over the live cells, which colored rules color this way
+                for ((x, y), state) in self.universe.higher_states() {
+                    if in_view(x, y) {
+                        self.fill_cell(&painter, viewport, x, y, higher_state_color(&self.theme, &self.universe, state));
+                    }
+                }
+            }
+            if self.lattice() != Lattice::Square {
+                if self.camera.cell_size >= POLYGON_CELL_SIZE {
+                    for (x, y) in self.visible_cells(visible) {
+                        painter.add(egui::Shape::closed_line(self.cell_polygon(viewport, x, y), grid_line_stroke));
+                    }
+                }
+            } else if self.camera.cell_size >= 4.0 {
+                for x in visible.left().floor() as i64..=visible.right().ceil() as i64 {
+                    let sx = self.camera.world_to_screen(viewport, Pos2::new(x as f32, 0.0)).x;
+                    painter.vline(sx, viewport.y_range(), grid_line_stroke);
+                }
+                for y in visible.top().floor() as i64..=visible.bottom().ceil() as i64 {
+                    let sy = self.camera.world_to_screen(viewport, Pos2::new(0.0, y as f32)).y;
+                    painter.hline(viewport.x_range(), sy, grid_line_stroke);
+                }
+            }
+        } else {
+            // The whole grid is one textured quad, with the lines drawn on top only over the
+            // visible cells
+            let (width, height) = (self.universe.width() as i64, self.universe.height() as i64);
+            let texture = self.grid_texture(ui.ctx());
+            let grid = Rect::from_min_max(
+                self.camera.world_to_screen(viewport, Pos2::ZERO),
+                self.camera.world_to_screen(viewport, Pos2::new(width as f32, height as f32)),
+            );
+            let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+            painter.rect_filled(viewport, 0.0, self.theme.background);
+            if self.lattice() != Lattice::Square {
+                // The texture laid out like the cells, so each texel is a parallelogram or
+                // rectangle standing in for a hexagon or triangle until they are big enough to
+                // draw one by one
+                let mut mesh = egui::Mesh::with_texture(texture);
+                for (u, v) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+                    let corner = self.grid_to_world(Pos2::new(u * width as f32, v * height as f32));
+                    let pos = self.camera.world_to_screen(viewport, corner);
+                    mesh.vertices.push(egui::epaint::Vertex { pos, uv: Pos2::new(u, v), color: Color32::WHITE });
+                }
+                mesh.add_triangle(0, 1, 2);
+                mesh.add_triangle(0, 2, 3);
+                painter.add(mesh);
+                if self.camera.cell_size >= POLYGON_CELL_SIZE {
+                    let style = (self.color_by_age, self.age_span, self.trail_length);
+                    for (x, y) in self.visible_cells(visible).into_iter().filter(|&(x, y)| self.universe.contains(x, y)) {
+                        let polygon = self.cell_polygon(viewport, x, y);
+                        let color = cell_color(&self.theme, &self.universe, x, y, style);
+                        painter.add(egui::Shape::convex_polygon(polygon, color, grid_line_stroke));
+                    }
+                }
+            } else {
+                painter.image(texture, grid, uv, Color32::WHITE);
+                if self.camera.cell_size >= 4.0 {
+                    let first_x = (visible.left().floor() as i64).clamp(0, width);
+                    let last_x = (visible.right().ceil() as i64).clamp(0, width);
+                    let first_y = (visible.top().floor() as i64).clamp(0, height);
+                    let last_y = (visible.bottom().ceil() as i64).clamp(0, height);
+                    for x in first_x..=last_x {
+                        let sx = self.camera.world_to_screen(viewport, Pos2::new(x as f32, 0.0)).x;
+                        painter.vline(sx, grid.y_range(), grid_line_stroke);
+                    }
+                    for y in first_y..=last_y {
+                        let sy = self.camera.world_to_screen(viewport, Pos2::new(0.0, y as f32)).y;
+                        painter.hline(grid.x_range(), sy, grid_line_stroke);
+                    }
+                }
+            }
+        }
+
+        // Ants as arrowheads pointing the way they face
+        for ant in self.universe.ants().unwrap_or_default() {
+            let rect = self.cell_rect(viewport, ant.x, ant.y);
+            if !viewport.intersects(rect) {
+                continue;
+            }
+            let (dx, dy) = ant.heading.offset();
+            let (forward, side) = (Vec2::new(dx as f32, dy as f32), Vec2::new(-dy as f32, dx as f32));
+            let half = 0.4 * rect.width();
+            let points = vec![rect.center() + forward * half, rect.center() - (forward + side) * half, rect.center() - (forward - side) * half];
+            painter.add(egui::Shape::convex_polygon(points, Color32::GREEN, egui::Stroke::new(1.0, Color32::BLACK)));
+        }
+
+        if let Some(selection) = self.selection {
+            let (left, top, right, bottom) = (selection.left as f32, selection.top as f32, selection.right as f32, selection.bottom as f32);
+            let corners = [(left, top), (right, top), (right, bottom), (left, bottom)]
+                .map(|(x, y)| self.camera.world_to_screen(viewport, self.grid_to_world(Pos2::new(x, y))));
+            let fill = Color32::from_rgba_unmultiplied(255, 255, 0, 32);
+            painter.add(egui::Shape::convex_polygon(corners.to_vec(), fill, egui::Stroke::new(2.0, Color32::YELLOW)));
+        }
+
+        // Tracked spaceships in boxes labelled with their names
+        if self.track_ships {
+            for track in self.tracker.ships() {
+                let (left, top, right, bottom) = (track.bounds.0 as f32, track.bounds.1 as f32, track.bounds.2 as f32 + 1.0, track.bounds.3 as f32 + 1.0);
+                let corners = [(left, top), (right, top), (right, bottom), (left, bottom)]
+                    .map(|(x, y)| self.camera.world_to_screen(viewport, self.grid_to_world(Pos2::new(x, y))).round());
+                if !corners.iter().any(|&corner| viewport.contains(corner)) {
+                    continue;
+                }
+                let color = if self.following == Some(track.id) { Color32::LIGHT_BLUE } else { Color32::from_rgb(255, 160, 0) };
+                painter.add(egui::Shape::closed_line(corners.to_vec(), egui::Stroke::new(1.5, color)));
+                let label = track.name.map(str::to_string).or_else(|| track.ship.as_ref().map(|ship| ship.code())).unwrap_or_default();
+                painter.text(corners[0] - Vec2::new(0.0, 2.0), egui::Align2::LEFT_BOTTOM, label, egui::FontId::proportional(11.0), color);
+            }
+        }
+
+        if let Some((start, end)) = self.shape_drag {
+            let cells = self.shape_cells(start, end);
+            let shape_color = Color32::from_rgba_unmultiplied(255, 255, 255, 96);
+            for (x, y) in cells.into_iter().filter(|&(x, y)| visible.intersects(self.cell_bounds(x, y))) {
+                self.fill_cell(&painter, viewport, x, y, shape_color);
+            }
+        }
+
+        if let Some((x, y)) = self.cursor {
+            let center = self.grid_to_world(Pos2::new(x as f32 + 0.5, y as f32 + 0.5));
+            if self.cursor_moved && !visible.shrink(1.0).contains(center) {
+                self.camera.center = center;
+                ui.ctx().request_repaint();
+            }
+            self.cursor_moved = false;
+            let cursor_stroke = egui::Stroke::new(2.0, Color32::LIGHT_BLUE);
+            if self.lattice() != Lattice::Square {
+                painter.add(egui::Shape::closed_line(self.cell_polygon(viewport, x, y), cursor_stroke));
+            } else {
+                painter.rect_stroke(self.cell_rect(viewport, x, y).expand(1.0), 0.0, cursor_stroke);
+            }
+        }
+
+        // Show the axes of the editing symmetry
+        if self.symmetry != Symmetry::None {
+            let (sum_x, sum_y) = self.symmetry_center();
+            let center = self.grid_to_world(Pos2::new(sum_x as f32 / 2.0 + 0.5, sum_y as f32 / 2.0 + 0.5));
+            let center = self.camera.world_to_screen(viewport, center);
+            let axis_stroke = egui::Stroke::new(1.0, Color32::from_rgba_unmultiplied(0, 200, 255, 160));
+            if matches!(self.symmetry, Symmetry::MirrorX | Symmetry::FourFold | Symmetry::Dihedral8) {
+                painter.vline(center.x, viewport.y_range(), axis_stroke);
+            }
+            if matches!(self.symmetry, Symmetry::MirrorY | Symmetry::FourFold | Symmetry::Dihedral8) {
+                painter.hline(viewport.x_range(), center.y, axis_stroke);
+            }
+            if self.symmetry == Symmetry::Dihedral8 {
+                let reach = viewport.width() + viewport.height();
+                for diagonal in [Vec2::new(reach, reach), Vec2::new(reach, -reach)] {
+                    painter.line_segment([center - diagonal, center + diagonal], axis_stroke);
+                }
+            }
+            if matches!(self.symmetry, Symmetry::Rotate2 | Symmetry::Rotate4) {
+                painter.circle_stroke(center, 6.0, axis_stroke);
+            }
+        }
+
+        // Outline the cells the brush would paint
+        let brushing = matches!(self.tool, Tool::Draw | Tool::Erase) && stamp.is_none() && self.brush_radius > 0;
+        if let Some(pos) = response.hover_pos().filter(|_| brushing) {
+            let (x, y) = self.world_cell(viewport, pos);
+            let brush_color = Color32::from_rgba_unmultiplied(255, 255, 255, 48);
+            for (dx, dy) in self.brush_shape.offsets(self.brush_radius) {
+                if self.universe.contains(x + dx, y + dy) {
+                    self.fill_cell(&painter, viewport, x + dx, y + dy, brush_color);
+                }
+            }
+        }
+
+        // Draw a translucent preview of the stamp under the cursor
+        if let (Some(stamp), Some((x, y))) = (&stamp, response.hover_pos().and_then(|pos| self.cell_at(viewport, pos))) {
+            let (left, top) = stamp_origin(stamp, x, y);
+            let ghost_color = Color32::from_rgba_unmultiplied(255, 255, 255, 96);
+            for &(px, py) in &stamp.cells {
+                let (cx, cy) = (left + px as i64, top + py as i64);
+                if self.universe.contains(cx, cy) {
+                    self.fill_cell(&painter, viewport, cx, cy, ghost_color);
+                }
+            }
+        }
+
+        if let Some(minimap) = minimap {
+            self.draw_minimap(&painter, viewport, minimap);
+        }
+    }
+
+    /// Texture of the finite grid with one texel per cell. Only the regions where cells changed
+    /// since it was last drawn are re-rasterized and uploaded, so a static grid costs nothing.
+    /// Draws the 3D universe instead of the grid, as the slice view or the orbit view.
+    fn draw_space(&mut self, ui: &mut egui::Ui) {
+        profile_scope!();
+        let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+        let viewport = response.rect;
+        painter.rect_filled(viewport, 0.0, self.theme.background);
+        let size = self.space.size() as f32;
+        let scroll = response.hover_pos().map_or(0.0, |_| ui.input(|i| i.scroll_delta.y));
+        match self.space_view {
+            SpaceView::Slices => {
+                if self.fit_requested {
+                    self.camera.fit(viewport, Rect::from_min_size(Pos2::ZERO, Vec2::splat(size)), 20.0);
+                    self.fit_requested = false;
+                }
+                if response.dragged_by(egui::PointerButton::Secondary) || response.dragged_by(egui::PointerButton::Middle) {
+                    self.camera.pan(response.drag_delta());
+                }
+                if let Some(hover_pos) = response.hover_pos().filter(|_| scroll != 0.0) {
+                    self.camera.zoom_at(viewport, hover_pos, (scroll * 0.002).exp());
+                }
+                // Clicking toggles the voxel on the layer shown
+                if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+                    let world = self.camera.screen_to_world(viewport, pos);
+                    if (0.0..size).contains(&world.x) && (0.0..size).contains(&world.y) {
+                        self.space.toggle(world.x as usize, world.y as usize, self.space_layer);
+                    }
+                }
+                self.draw_slice(&painter, viewport);
+            }
+            SpaceView::Orbit => {
+                if self.fit_requested {
+                    self.orbit_zoom = viewport.size().min_elem() / (size * 1.8);
+                    self.fit_requested = false;
+                }
+                // Drag to turn the cube around, scroll to zoom
+                if response.dragged() {
+                    let delta = response.drag_delta() * 0.01;
+                    self.orbit = Vec2::new(self.orbit.x + delta.x, (self.orbit.y - delta.y).clamp(-1.5, 1.5));
+                }
+                self.orbit_zoom = (self.orbit_zoom * (scroll * 0.002).exp()).clamp(1.0, camera::MAX_CELL_SIZE);
+                self.draw_orbit(&painter, viewport);
+            }
+        }
+    }
+
+    /// Draws the layer being shown through the camera, over the live voxels of the layers
+    /// either side in a faint color so that what moves between layers can be followed.
+    fn draw_slice(&self, painter: &egui::Painter, viewport: Rect) {
+        let size = self.space.size();
+        let cell = |x: usize, y: usize| {
+            let min = self.camera.world_to_screen(viewport, Pos2::new(x as f32, y as f32));
+            Rect::from_min_size(min, Vec2::splat(self.camera.cell_size))
+        };
+        let grid = Rect::from_min_max(cell(0, 0).min, cell(size, size).min);
+        painter.rect_filled(grid, 0.0, self.theme.dead);
+        let z = self.space_layer;
+        for neighbor in [z.checked_sub(1), (z + 1 < size).then_some(z + 1)].into_iter().flatten() {
+            for (x, y) in self.space.layer(neighbor).live_cells() {
+                painter.rect_filled(cell(x, y), 0.0, self.theme.blend(0.3));
+            }
+        }
+        for (x, y) in self.space.layer(z).live_cells() {
+            painter.rect_filled(cell(x, y), 0.0, self.theme.alive);
+        }
+        if self.camera.cell_size >= 4.0 {
+            let stroke = egui::Stroke::new(1.0, self.theme.grid_line);
+            for i in 0..=size {
+                painter.vline(cell(i, 0).min.x, grid.y_range(), stroke);
+                painter.hline(grid.x_range(), cell(0, i).min.y, stroke);
+            }
+        }
+    }
+
+    /// Draws the edges of the cube and its live voxels as squares seen from the orbit angles,
+    /// back to front and darker the further back they are.
+    fn draw_orbit(&self, painter: &egui::Painter, viewport: Rect) {
+        let half = self.space.size() as f32 / 2.0;
+        let (sin_yaw, cos_yaw) = self.orbit.x.sin_cos();
+        let (sin_pitch, cos_pitch) = self.orbit.y.sin_cos();
+        // Screen position and depth, growing away from the viewer, of a point in the cube
+        let project = |point: [f32; 3]| {
+            let [x, y, z] = point.map(|c| c - half);
+            let (x, z) = (x * cos_yaw - z * sin_yaw, x * sin_yaw + z * cos_yaw);
+            let (y, z) = (y * cos_pitch - z * sin_pitch, y * sin_pitch + z * cos_pitch);
+            (viewport.center() + Vec2::new(x, y) * self.orbit_zoom, z)
+        };
+
+        let corner = |i: usize| [i & 1, i >> 1 & 1, i >> 2 & 1].map(|bit| bit as f32 * 2.0 * half);
+        let stroke = egui::Stroke::new(1.0, self.theme.grid_line);
+        for i in 0..8 {
+            for bit in [1, 2, 4].into_iter().filter(|bit| i & bit == 0) {
+                painter.line_segment([project(corner(i)).0, project(corner(i | bit)).0], stroke);
+            }
+        }
+
+        let mut voxels: Vec<(Pos2, f32)> =
+            self.space.live_cells().map(|(x, y, z)| project([x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5])).collect();
+        voxels.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let reach = half * 3f32.sqrt(); // furthest a voxel can be from the middle
+        let outline = if self.orbit_zoom >= 4.0 { egui::Stroke::new(1.0, self.theme.dead) } else { egui::Stroke::NONE };
+        for (pos, depth) in voxels {
+            let near = 0.5 - depth / (2.0 * reach);
+            painter.rect(Rect::from_center_size(pos, Vec2::splat(self.orbit_zoom)), 0.0, self.theme.blend(0.3 + 0.7 * near), outline);
+        }
+    }
+
+    fn grid_texture(&mut self, ctx: &egui::Context) -> egui::TextureId {
+        profile_scope!();
+        // Reads the fields the texture is made from directly, so `grid_texture` can be updated
+        let (universe, theme) = (&self.universe, &self.theme);
+        let style = (self.color_by_age, self.age_span, self.trail_length);
+        let texel = |x, y| cell_color(theme, universe, x, y, style);
+        if let Some((texture, shown)) = &mut self.grid_texture {
+            // Ages and trails change all over with each generation, so then the whole grid is
+            // redrawn
+            let aged = self.universe.tracking().is_some() && shown.generation() != self.universe.generation();
+            if let Some(regions) = self.universe.changed_regions(shown).filter(|_| !aged) {
+                if !regions.is_empty() {
+                    *shown = self.universe.clone();
+                }
+                for [left, top, width, height] in regions {
+                    let mut image = egui::ColorImage::new([width, height], self.theme.dead);
+                    for y in 0..height {
+                        for x in 0..width {
+                            image[(x, y)] = texel((left + x) as i64, (top + y) as i64);
+                        }
+                    }
+                    texture.set_partial([left, top], image, GRID_TEXTURE_OPTIONS);
+                }
+                return texture.id();
+            }
+        }
+
+        // No texture yet, or the grid was resized: rasterize everything
+        let (width, height) = (self.universe.width(), self.universe.height());
+        let mut image = egui::ColorImage::new([width, height], self.theme.dead);
+        for ((x, y), _) in self.universe.recent_deaths() {
+            image[(x as usize, y as usize)] = texel(x, y);
+        }
+        for ((x, y), _) in self.universe.higher_states() {
+            image[(x as usize, y as usize)] = texel(x, y);
+        }
+        for (x, y) in self.universe.live_cells() {
+            image[(x as usize, y as usize)] = texel(x, y);
+        }
+        let texture = ctx.load_texture("grid", image, GRID_TEXTURE_OPTIONS);
+        let id = texture.id();
+        self.grid_texture = Some((texture, self.universe.clone()));
+        id
+    }
+
+    /// Plots the population, births and deaths over the generations. The plot follows new
+    /// samples until it is dragged or zoomed, and a double click makes it follow again.
+    fn draw_chart(&self, ui: &mut egui::Ui) {
+        profile_scope!();
+        let samples = self.population_log.samples();
+        let population: egui_plot::PlotPoints = samples.iter().map(|s| [s.generation as f64, s.population as f64]).collect();
+        let series = |count: fn(&Changes) -> usize| -> egui_plot::PlotPoints {
+            samples
+                .iter()
+                .filter_map(|s| Some([s.generation as f64, count(s.changes.as_ref()?) as f64]))
+                .collect()
+        };
+        let (births, deaths) = (series(|c| c.births), series(|c| c.deaths));
+        egui_plot::Plot::new("population")
+            .legend(egui_plot::Legend::default())
+            .auto_bounds_x()
+            .auto_bounds_y()
+            .include_y(0.0)
+            .x_axis_label("Generation")
+            .label_formatter(|name, value| format!("{name}\ngen {:.0}: {:.0} cells", value.x, value.y))
+            .show(ui, |plot| {
+                plot.line(egui_plot::Line::new(population).name("Population").color(self.theme.alive));
+                plot.line(egui_plot::Line::new(births).name("Births"));
+                plot.line(egui_plot::Line::new(deaths).name("Deaths"));
+            });
+    }
+
+    /// Switches egui between dark and light visuals when the chosen appearance or the system
+    /// preference calls for it. Grid colors still at the defaults for the old visuals follow.
+    fn apply_appearance(&mut self, ctx: &egui::Context, system: Option<eframe::Theme>) {
+        let dark = self.appearance.is_dark(system);
+        if ctx.style().visuals.dark_mode != dark {
+            ctx.set_visuals(if dark { egui::Visuals::dark() } else { egui::Visuals::light() });
+        }
+        if dark != self.dark_mode {
+            if self.theme == Theme::for_dark_mode(self.dark_mode) {
+                self.theme = Theme::for_dark_mode(dark);
+                self.grid_texture = None;
+            }
+            self.dark_mode = dark;
+        }
+    }
+
+    /// The part of the world the minimap covers: the whole grid, or for an infinite universe
+    /// everything that is alive or in view.
+    fn minimap_world(&self, viewport: Rect) -> Rect {
+        if self.universe.is_infinite() {
+            let visible = self.camera.visible_world(viewport);
+            self.live_bounds().map_or(visible, |bounds| bounds.union(visible)).expand(4.0)
+        } else {
+            self.default_bounds()
+        }
+    }
+
+    /// Area in the bottom-right corner of the viewport where the minimap is drawn.
+    fn minimap_rect(&self, viewport: Rect) -> Rect {
+        let max_size = 150.0f32.min(viewport.width() / 3.0).min(viewport.height() / 3.0);
+        let world = self.minimap_world(viewport).size();
+        let size = world * (max_size / world.x.max(world.y));
+        let margin = 8.0;
+        Rect::from_min_size(viewport.max - size - Vec2::splat(margin), size)
+    }
+
+    /// Minimap points per cell.
+    fn minimap_scale(&self, viewport: Rect, minimap: Rect) -> f32 {
+        minimap.width() / self.minimap_world(viewport).width()
+    }
+
+    /// Draws the whole universe scaled into the minimap, with an outline of the visible area.
+    fn draw_minimap(&self, painter: &egui::Painter, viewport: Rect, minimap: Rect) {
+        profile_scope!();
+        let world = self.minimap_world(viewport);
+        let scale = self.minimap_scale(viewport, minimap);
+        painter.rect_filled(minimap, 0.0, Color32::from_black_alpha(220));
+
+        // Blocks of cells at least a couple of points wide, shaded by how many are alive
+        let block = (LOD_CELL_SIZE / scale).ceil().max(1.0) as i64;
+        let dot = Vec2::splat(block as f32 * scale);
+        let clipped = painter.with_clip_rect(minimap);
+        for ((bx, by), count) in density_blocks(self.universe.live_cells(), block, self.grid_bounds(world)) {
+            let corner = self.grid_to_world(Pos2::new((bx * block) as f32, (by * block) as f32));
+            let min = minimap.min + (corner - world.min) * scale;
+            clipped.rect_filled(Rect::from_min_size(min, dot), 0.0, density_color(&self.theme, count, block));
+        }
+
+        let visible = self.camera.visible_world(viewport);
+        let outline = Rect::from_min_max(
+            minimap.min + (visible.min - world.min) * scale,
+            minimap.min + (visible.max - world.min) * scale,
+        );
+        clipped.rect_stroke(outline, 0.0, egui::Stroke::new(1.0, Color32::WHITE));
+        painter.rect_stroke(minimap, 0.0, egui::Stroke::new(1.0, Color32::GRAY));
+    }
+
+    /// Bounding box of all live cells in world coordinates, or `None` if the grid is empty.
+    fn live_bounds(&self) -> Option<Rect> {
+        let mut bounds: Option<Rect> = None;
+        for (x, y) in self.universe.live_cells() {
+            let cell = self.cell_bounds(x, y);
+            bounds = Some(bounds.map_or(cell, |b| b.union(cell)));
+        }
+        bounds
+    }
+
+    /// What to show when there are no live cells: the whole grid, or the area around the origin.
+    fn default_bounds(&self) -> Rect {
+        if self.universe.is_infinite() {
+            Rect::from_center_size(Pos2::ZERO, Vec2::splat(32.0))
+        } else {
+            self.world_bounds(Rect::from_min_size(Pos2::ZERO, Vec2::new(self.universe.width() as f32, self.universe.height() as f32)))
+        }
+    }
+
+    /// The cells covered by the line, rectangle or ellipse tool dragged from `start` to `end`.
+    fn shape_cells(&self, start: (i64, i64), end: (i64, i64)) -> Vec<(i64, i64)> {
+        match self.tool {
+            Tool::Line => shapes::line(start, end),
+            Tool::Rectangle => shapes::rectangle(start, end, self.shape_filled),
+            Tool::Ellipse => shapes::ellipse(start, end, self.shape_filled),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Brings to life the region of dead cells around (x, y), bounded by live cells and the
+    /// edges of a finite grid.
+    fn fill(&mut self, x: i64, y: i64) {
+        let open = |x, y| self.universe.contains(x, y) && !self.universe.get(x, y);
+        let limit = if self.universe.is_infinite() { MAX_FILL_CELLS } else { usize::MAX };
+        match shapes::flood_fill((x, y), limit, open) {
+            Some(cells) => {
+                self.record_edit();
+                for (x, y) in cells {
+                    self.paint(x, y, true);
+                }
+                self.reset_history();
+            }
+            None => self.status = "Fill stopped: the region is not enclosed".to_string(),
+        }
+    }
+
+    /// Coordinates of the cell under a screen position, if it is inside the universe.
+    fn cell_at(&self, viewport: Rect, pos: Pos2) -> Option<(i64, i64)> {
+        let (x, y) = self.world_cell(viewport, pos);
+        self.universe.contains(x, y).then_some((x, y))
+    }
+
+    /// Coordinates of the cell under a screen position, even beyond the edges of the grid.
+    fn world_cell(&self, viewport: Rect, pos: Pos2) -> (i64, i64) {
+        let world = self.camera.screen_to_world(viewport, pos);
+        let row = world.y.floor() as i64;
+        match self.lattice() {
+            Lattice::Square => (world.x.floor() as i64, row),
+            Lattice::Hexagonal => {
+                // The hexagons are the cells of the nearest center, which is in this row or the
+                // ones above and below
+                let candidates = (row - 1..=row + 1).flat_map(|y| {
+                    let x = (world.x + (y as f32 + 0.5) / 2.0 - 0.5).round() as i64;
+                    [(x - 1, y), (x, y), (x + 1, y)]
+                });
+                let nearest = candidates.min_by(|&(ax, ay), &(bx, by)| {
+                    let distance = |x: i64, y: i64| self.grid_to_world(Pos2::new(x as f32 + 0.5, y as f32 + 0.5)).distance_sq(world);
+                    distance(ax, ay).total_cmp(&distance(bx, by))
+                });
+                nearest.unwrap_or_default()
+            }
+            Lattice::Triangular => {
+                // Triangles overlap their neighbors' bounding boxes, so find the one containing
+                // the point among those around it
+                let x = self.world_to_grid(world).x.floor() as i64;
+                let contains = |&(x, y): &(i64, i64)| {
+                    let [a, b, c] = triangle(x, y);
+                    let side = |p: Pos2, q: Pos2| (q - p).x * (world - p).y - (q - p).y * (world - p).x;
+                    let sides = [side(a, b), side(b, c), side(c, a)];
+                    sides.iter().all(|&s| s >= 0.0) || sides.iter().all(|&s| s <= 0.0)
+                };
+                (x - 1..=x + 1).map(|x| (x, row)).find(contains).unwrap_or((x, row))
+            }
+        }
+    }
+
+    /// Screen rectangle of the cell at (x, y) on a square grid.
+    fn cell_rect(&self, viewport: Rect, x: i64, y: i64) -> Rect {
+        let min = self.camera.world_to_screen(viewport, Pos2::new(x as f32, y as f32));
+        Rect::from_min_size(min, Vec2::splat(self.camera.cell_size))
+    }
+
+    /// The shape of the cells, which follows from the rule's neighborhood.
+    fn lattice(&self) -> Lattice {
+        if self.automaton.is_some() || self.ants {
+            return Lattice::Square;
+        }
+        match self.universe.rule().neighborhood.shape {
+            Shape::Hexagonal => Lattice::Hexagonal,
+            Shape::Triangular | Shape::TriangularEdges => Lattice::Triangular,
+            _ => Lattice::Square,
+        }
+    }
+
+    /// World position of a point in grid coordinates. Hexagonal grids are sheared by half a
+    /// cell to the left per row down, which puts the NW and N neighbors of a cell above it and
+    /// the S and SE ones below, as `Shape::Hexagonal` counts them. Triangles overlap their
+    /// neighbors by half, so their columns are half a cell apart.
+    fn grid_to_world(&self, grid: Pos2) -> Pos2 {
+        match self.lattice() {
+            Lattice::Square => grid,
+            Lattice::Hexagonal => Pos2::new(grid.x - grid.y / 2.0, grid.y),
+            Lattice::Triangular => Pos2::new(grid.x / 2.0 + 0.25, grid.y),
+        }
+    }
+
+    fn world_to_grid(&self, world: Pos2) -> Pos2 {
+        match self.lattice() {
+            Lattice::Square => world,
+            Lattice::Hexagonal => Pos2::new(world.x + world.y / 2.0, world.y),
+            Lattice::Triangular => Pos2::new((world.x - 0.25) * 2.0, world.y),
+        }
+    }
+
+    /// World bounding box of a rectangle in grid coordinates, and the other way around.
+    fn world_bounds(&self, grid: Rect) -> Rect {
+        Rect::from_points(&[grid.left_top(), grid.right_top(), grid.left_bottom(), grid.right_bottom()].map(|p| self.grid_to_world(p)))
+    }
+
+    fn grid_bounds(&self, world: Rect) -> Rect {
+        Rect::from_points(&[world.left_top(), world.right_top(), world.left_bottom(), world.right_bottom()].map(|p| self.world_to_grid(p)))
+    }
+
+    /// World bounding box of the cell at (x, y).
+    fn cell_bounds(&self, x: i64, y: i64) -> Rect {
+        let center = self.grid_to_world(Pos2::new(x as f32 + 0.5, y as f32 + 0.5));
+        match self.lattice() {
+            Lattice::Hexagonal => Rect::from_center_size(center, Vec2::new(1.0, 2.0 * HEXAGON[0].y.abs())),
+            _ => Rect::from_center_size(center, Vec2::splat(1.0)),
+        }
+    }
+
+    /// Screen corners of the cell at (x, y).
+    fn cell_polygon(&self, viewport: Rect, x: i64, y: i64) -> Vec<Pos2> {
+        let center = self.grid_to_world(Pos2::new(x as f32 + 0.5, y as f32 + 0.5));
+        let corners = match self.lattice() {
+            Lattice::Square => {
+                let bounds = self.cell_bounds(x, y);
+                vec![bounds.left_top(), bounds.right_top(), bounds.right_bottom(), bounds.left_bottom()]
+            }
+            Lattice::Hexagonal => HEXAGON.iter().map(|&corner| center + corner).collect(),
+            Lattice::Triangular => triangle(x, y).to_vec(),
+        };
+        corners.into_iter().map(|corner| self.camera.world_to_screen(viewport, corner)).collect()
+    }
+
+    /// Fills the cell at (x, y).
+    fn fill_cell(&self, painter: &egui::Painter, viewport: Rect, x: i64, y: i64, color: Color32) {
+        match self.lattice() {
+            Lattice::Square => painter.rect_filled(self.cell_rect(viewport, x, y), 0.0, color),
+            _ => {
+                painter.add(egui::Shape::convex_polygon(self.cell_polygon(viewport, x, y), color, egui::Stroke::NONE));
+            }
+        }
+    }
+
+    /// The cells of a hexagonal or triangular grid in the visible part of the world, with a
+    /// margin of a cell or two.
+    fn visible_cells(&self, visible: Rect) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        for y in visible.top().floor() as i64 - 1..=visible.bottom().ceil() as i64 {
+            let middle = y as f32 + 0.5;
+            let first = self.world_to_grid(Pos2::new(visible.left(), middle)).x.floor() as i64 - 2;
+            let last = self.world_to_grid(Pos2::new(visible.right(), middle)).x.ceil() as i64 + 1;
+            cells.extend((first..=last).map(|x| (x, y)));
+        }
+        cells
+    }
+
+    /// The pattern the next click places: the one being pasted, or else the selected library
+    /// pattern with the current rotation and mirroring applied.
+    fn oriented_stamp(&self) -> Option<Pattern> {
+        if let Some(paste) = &self.paste {
+            return Some(paste.clone());
+        }
+        let mut pattern = self.stamp.as_ref()?.1.clone();
+        if self.stamp_flipped {
+            pattern = pattern.flip_horizontal();
+        }
+        for _ in 0..self.stamp_rotation {
+            pattern = pattern.rotate_clockwise();
+        }
+        Some(pattern)
+    }
+
+    fn handle_stamp_keys(&mut self, ctx: &egui::Context) {
+        if self.stamp.is_none() || ctx.wants_keyboard_input() {
+            return;
+        }
+        ctx.input(|i| {
+            if i.key_pressed(egui::Key::R) {
+                self.stamp_rotation = (self.stamp_rotation + 1) % 4;
+            }
+            if i.key_pressed(egui::Key::F) {
+                self.stamp_flipped = !self.stamp_flipped;
+            }
+            if i.key_pressed(egui::Key::Escape) {
+                self.stamp = None;
+            }
+        });
+    }
+
+    /// Clipboard shortcuts act on the selection; Delete clears it and Esc drops it.
+    fn handle_selection_keys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        for event in ctx.input(|i| i.events.clone()) {
+            match event {
+                egui::Event::Copy => self.copy_selection(ctx),
+                egui::Event::Cut => self.cut_selection(ctx),
+                egui::Event::Paste(text) => self.paste_text(&text),
+                egui::Event::Key { key: egui::Key::Delete | egui::Key::Backspace, pressed: true, .. } => {
+                    self.delete_selection();
+                }
+                egui::Event::Key { key: egui::Key::Escape, pressed: true, .. } => {
+                    self.paste = None;
+                    self.selection = None;
+                }
+                egui::Event::Key { key, pressed: true, .. } if self.selection.is_some() => {
+                    let offset = match key {
+                        egui::Key::ArrowLeft => (-1, 0),
+                        egui::Key::ArrowRight => (1, 0),
+                        egui::Key::ArrowUp => (0, -1),
+                        egui::Key::ArrowDown => (0, 1),
+                        _ => continue,
+                    };
+                    self.transform_selection(Pattern::clone, offset);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Copies the selected cells, both for pasting here and as RLE text for other programs.
+    fn copy_selection(&mut self, ctx: &egui::Context) {
+        let Some(selection) = self.selection else { return };
+        let pattern = self.universe.copy_region(selection);
+        ctx.copy_text(Format::Rle.write(&pattern, self.universe.rule()));
+        self.status = format!("Copied {}x{} selection with {} cells", pattern.width, pattern.height, pattern.cells.len());
+        self.clipboard = Some(pattern);
+    }
+
+    fn cut_selection(&mut self, ctx: &egui::Context) {
+        self.copy_selection(ctx);
+        self.delete_selection();
+    }
+
+    fn delete_selection(&mut self) {
+        let Some(selection) = self.selection else { return };
+        self.record_edit();
+        self.universe.clear_region(selection);
+        self.reset_history();
+    }
+
+    /// Replaces the selected cells with a transformed copy, centered where they were and then
+    /// moved by `(dx, dy)`, and selects the result.
+    fn transform_selection(&mut self, transform: impl Fn(&Pattern) -> Pattern, (dx, dy): (i64, i64)) {
+        let Some(selection) = self.selection else { return };
+        self.record_edit();
+        let pattern = transform(&self.universe.copy_region(selection));
+        self.universe.clear_region(selection);
+
+        let left = selection.left + (selection.width() as i64 - pattern.width as i64) / 2 + dx;
+        let top = selection.top + (selection.height() as i64 - pattern.height as i64) / 2 + dy;
+        self.universe.place(&pattern, left, top);
+        self.selection = Some(Selection {
+            left,
+            top,
+            right: left + pattern.width as i64,
+            bottom: top + pattern.height as i64,
+        });
+        self.reset_history();
+    }
+
+    /// Starts pasting clipboard text in any supported pattern format.
+    fn paste_text(&mut self, text: &str) {
+        match formats::parse(text) {
+            Ok(pattern) if !pattern.is_empty() => self.paste = Some(pattern),
+            Ok(_) => self.status = "Nothing to paste: the pattern is empty".to_string(),
+            Err(err) => self.status = format!("Paste failed: {err}"),
+        }
+    }
+
+    /// Arrow keys move the cell cursor (unless they are nudging a selection) and Enter or Space
+    /// toggles the cell under it, so the grid can be edited without a mouse.
+    fn handle_cursor_keys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (offset, toggle, hide) = ctx.input_mut(|i| {
+            let mut offset = (0, 0);
+            if self.selection.is_none() {
+                let step = if i.modifiers.shift { 10 } else { 1 };
+                for (key, (dx, dy)) in [
+                    (egui::Key::ArrowLeft, (-step, 0)),
+                    (egui::Key::ArrowRight, (step, 0)),
+                    (egui::Key::ArrowUp, (0, -step)),
+                    (egui::Key::ArrowDown, (0, step)),
+                ] {
+                    if i.key_pressed(key) {
+                        offset = (offset.0 + dx, offset.1 + dy);
+                    }
+                }
+            }
+            // While the cursor is shown, Space toggles instead of playing
+            let toggle = self.cursor.is_some()
+                && (i.consume_key(egui::Modifiers::NONE, egui::Key::Enter) || i.consume_key(egui::Modifiers::NONE, egui::Key::Space));
+            (offset, toggle, i.key_pressed(egui::Key::Escape))
+        });
+
+        if offset != (0, 0) {
+            let center = self.world_to_grid(self.camera.center);
+            let (x, y) = self.cursor.unwrap_or((center.x.floor() as i64, center.y.floor() as i64));
+            let (mut x, mut y) = (x + offset.0, y + offset.1);
+            if !self.universe.is_infinite() {
+                x = x.clamp(0, self.universe.width() as i64 - 1);
+                y = y.clamp(0, self.universe.height() as i64 - 1);
+            }
+            self.cursor = Some((x, y));
+            self.cursor_moved = true;
+        }
+        if let Some((x, y)) = self.cursor.filter(|_| toggle) {
+            self.record_edit();
+            self.paint(x, y, !self.universe.get(x, y));
+            self.reset_history();
+        }
+        if hide {
+            self.cursor = None;
+        }
+    }
+
+    /// Playback, view and tool shortcuts. Stamp and selection keys are handled separately.
+    fn handle_shortcut_keys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let stamping = self.stamp.is_some();
+        let shortcuts = ctx.input(|i| {
+            if i.modifiers.command || i.modifiers.alt {
+                return Vec::new();
+            }
+            let mut bindings = vec![
+                (egui::Key::Space, Shortcut::PlayPause),
+                (egui::Key::P, Shortcut::PlayPause),
+                (egui::Key::Tab, Shortcut::Step),
+                (egui::Key::S, if i.modifiers.shift { Shortcut::StepBack } else { Shortcut::Step }),
+                (egui::Key::PlusEquals, Shortcut::Faster),
+                (egui::Key::Minus, Shortcut::Slower),
+                (egui::Key::C, Shortcut::Clear),
+                (egui::Key::M, Shortcut::ToggleMinimap),
+                (egui::Key::F3, Shortcut::TogglePerf),
+                (egui::Key::F1, Shortcut::ToggleHelp),
+                (egui::Key::H, Shortcut::ToggleHelp),
+            ];
+            // R and F belong to the stamp while one is selected
+            if !stamping {
+                bindings.extend([(egui::Key::R, Shortcut::RandomFill), (egui::Key::F, Shortcut::Fit)]);
+            }
+            let number_keys = [egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, egui::Key::Num4, egui::Key::Num5, egui::Key::Num6, egui::Key::Num7, egui::Key::Num8];
+            bindings.extend(number_keys.into_iter().zip(Tool::ALL.map(Shortcut::Tool)));
+            bindings.into_iter().filter(|&(key, _)| i.key_pressed(key)).map(|(_, shortcut)| shortcut).collect()
+        });
+
+        for shortcut in shortcuts {
+            match shortcut {
+                Shortcut::PlayPause => self.is_playing = !self.is_playing,
+                Shortcut::Step => self.advance(1),
+                Shortcut::StepBack => self.step_back(),
+                Shortcut::Faster if self.warp => self.warp_generations = (self.warp_generations * 2).min(MAX_WARP_GENERATIONS),
+                Shortcut::Slower if self.warp => self.warp_generations = (self.warp_generations / 2).max(1),
+                Shortcut::Faster => self.update_frequency = (self.update_frequency / 1.25).max(0.1),
+                Shortcut::Slower => self.update_frequency = (self.update_frequency * 1.25).min(2.0),
+                Shortcut::Clear => {
+                    self.record_edit();
+                    self.clear_grid();
+                    self.is_playing = false;
+                }
+                Shortcut::RandomFill => {
+                    self.new_fill_seed();
+                    self.random_fill();
+                }
+                Shortcut::Fit => self.fit_requested = true,
+                Shortcut::ToggleMinimap => self.show_minimap = !self.show_minimap,
+                Shortcut::TogglePerf => self.show_perf = !self.show_perf,
+                Shortcut::ToggleHelp => self.show_help = !self.show_help,
+                Shortcut::Tool(tool) => self.tool = tool,
+            }
+        }
+    }
+
+    /// Replaces the selection, or without one the grid (or, when infinite, a square around the
+    /// view), with random cells, or in 3D mode the middle of the cube. The same seed, density
+    /// and area always give the same cells. A symmetric fill draws each cell once along with
+    /// its images, and with quarter turns only fills the largest square in the middle.
+    fn random_fill(&mut self) {
+        let mut rng = Rng::new(self.fill_seed);
+        if self.three_d {
+            self.space.randomize(&mut rng, self.space.size() / 2, self.fill_density);
+            return;
+        }
+        self.record_edit();
+        let selection = match self.selection {
+            Some(selection) if !self.universe.is_infinite() => selection.clamped(self.universe.width(), self.universe.height()),
+            selection => selection,
+        };
+        let (left, top, width, height) = match selection {
+            Some(selection) => (selection.left, selection.top, selection.width() as i64, selection.height() as i64),
+            None if self.universe.is_infinite() => {
+                let center = self.world_to_grid(self.camera.center);
+                let (cx, cy) = (center.x as i64, center.y as i64);
+                (cx - RANDOM_FILL_SIZE / 2, cy - RANDOM_FILL_SIZE / 2, RANDOM_FILL_SIZE, RANDOM_FILL_SIZE)
+            }
+            None => (0, 0, self.universe.width() as i64, self.universe.height() as i64),
+        };
+        let (left, top, width, height) = match self.fill_symmetry.turns_quarter() {
+            true => {
+                let side = width.min(height);
+                (left + (width - side) / 2, top + (height - side) / 2, side, side)
+            }
+            false => (left, top, width, height),
+        };
+        let area = Selection { left, top, right: left + width, bottom: top + height };
+        let center = (2 * left + width - 1, 2 * top + height - 1);
+        for y in top..top + height {
+            for x in left..left + width {
+                let images: Vec<(i64, i64)> = self.fill_symmetry.images((x, y), center).into_iter().filter(|&(x, y)| area.contains(x, y)).collect();
+                // Cells whose images come earlier were drawn with them
+                if images.iter().any(|&(ix, iy)| (iy, ix) < (y, x)) {
+                    continue;
+                }
+                let (alive, hue) = (rng.chance(self.fill_density), rainbow::hue(rng.next_f64() as f32));
+                for (x, y) in images {
+                    self.universe.set(x, y, alive);
+                    self.universe.recolor(x, y, hue);
+                }
+            }
+        }
+        self.reset_history();
+    }
+
+    fn new_fill_seed(&mut self) {
+        self.fill_seed = Rng::new(get_current_time().to_bits()).next_u64();
+    }
+
+    /// Offers to restore the session a crash left behind.
+    #[cfg(feature = "serde")]
+    fn draw_recovery(&mut self, ctx: &egui::Context) {
+        let Some(session) = &self.recovered else {
+            return;
+        };
+        let generation = session.universe.generation();
+        let (mut restore, mut discard) = (false, false);
+        egui::Window::new("Recover session").collapsible(false).resizable(false).show(ctx, |ui| {
+            ui.label(format!("The app didn't close normally last time. Restore the grid at generation {generation} from just before?"));
+            ui.horizontal(|ui| {
+                restore = ui.button("Restore").clicked();
+                discard = ui.button("Discard").clicked();
+            });
+        });
+        if restore {
+            if let Some(session) = self.recovered.take() {
+                self.record_edit();
+                self.status = match self.apply_session(session) {
+                    Ok(()) => format!("Restored the session at generation {generation}"),
+                    Err(e) => format!("Restored the session at generation {generation}, but {e}"),
+                };
+            }
+        } else if discard {
+            self.recovered = None;
+        }
+    }
+
+    /// Writes a crash recovery snapshot every so often, unless one left by a crash is still
+    /// waiting to be restored or discarded.
+    #[cfg(feature = "serde")]
+    fn take_snapshot(&mut self) {
+        profile_scope!();
+        let now = get_current_time();
+        if self.recovered.is_some() || now - self.last_snapshot < recovery::INTERVAL {
+            return;
+        }
+        self.last_snapshot = now;
+        if let Ok(text) = self.session().to_stored() {
+            let _ = recovery::write(&text);
+        }
+    }
+
+    fn draw_help(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut self.show_help)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                    for (keys, action) in SHORTCUTS {
+                        ui.strong(keys);
+                        ui.label(action);
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    /// The performance overlay in the top left corner of the grid. `frame_cpu` is how long the
+    /// whole last frame took on the CPU, as eframe measures it.
+    fn draw_perf(&self, ctx: &egui::Context, frame_cpu: Option<f32>) {
+        let now = get_current_time();
+        let millis = |seconds: Option<f64>| seconds.map_or("-".to_string(), |seconds| format!("{:.2} ms", seconds * 1000.0));
+        let megabytes = |bytes: usize| format!("{:.1} MB", bytes as f64 / (1 << 20) as f64);
+        let grid_bytes = if self.three_d { self.space.memory_size() } else { self.universe.memory_size() };
+        let lines = [
+            format!("FPS          {:.0}", self.perf.frames_per_second(now)),
+            format!("Generations  {:.0}/s", self.perf.generations_per_second(now)),
+            format!("Step         {}", millis(self.perf.step_time(now))),
+            format!("Paint        {}", millis(self.perf.paint_time(now))),
+            format!("Frame CPU    {}", millis(frame_cpu.map(f64::from))),
+            format!("Grid         {}", megabytes(grid_bytes)),
+            format!("History      {} ({} kept)", megabytes(self.history.memory_size()), self.history.len()),
+        ];
+        egui::Area::new("performance").anchor(egui::Align2::LEFT_TOP, [8.0, 8.0]).interactable(false).show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).fill(Color32::from_black_alpha(200)).show(ui, |ui| {
+                for line in lines {
+                    ui.label(egui::RichText::new(line).monospace().color(Color32::WHITE));
+                }
+            });
+        });
+    }
+
+    /// The puffin profiler. Scopes are only recorded while it is open, as they cost a little
+    /// even when nothing looks at them.
+    #[cfg(feature = "profiling")]
+    fn draw_profiler(&mut self, ctx: &egui::Context) {
+        puffin::set_scopes_on(self.show_profiler);
+        egui::Window::new("Profiler").open(&mut self.show_profiler).default_size([800.0, 500.0]).show(ctx, puffin_egui::profiler_ui);
+    }
+
+    /// Splits the live cells into objects, classifies each by running it alone on an open plane
+    /// and names those in the library.
+    fn take_census(&mut self) {
+        let rule = self.universe.rule();
+        let objects = match (self.universe.automaton(), self.universe.ants()) {
+            (None, None) => soup::check_rule(rule).map(|()| soup::census(self.universe.live_cells(), rule)),
+            _ => Err("Objects can only be found under a rule, not an automaton or ants".to_string()),
+        };
+        self.census = Some(ObjectCensus { generation: self.universe.generation(), rule, objects, names: ObjectNames::new(rule) });
+    }
+
+    fn draw_census(&mut self, ctx: &egui::Context) {
+        if !self.show_census || self.three_d {
+            return;
+        }
+        let stale = self.census.as_ref().is_none_or(|census| census.generation != self.universe.generation());
+        if self.census_live && stale {
+            self.take_census();
+        }
+        let mut open = self.show_census;
+        let mut copied = None;
+        egui::Window::new("Census").open(&mut open).default_height(360.0).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Take census").clicked() {
+                    self.take_census();
+                }
+                ui.checkbox(&mut self.census_live, "Live").on_hover_text("Take it again every generation, which is slow for big universes");
+            });
+            let Some(census) = &self.census else { return };
+            let objects = match &census.objects {
+                Ok(objects) => objects,
+                Err(e) => {
+                    ui.label(e);
+                    return;
+                }
+            };
+            let total: usize = objects.iter().map(|(_, count)| count).sum();
+            ui.label(format!("Generation {}: {total} objects of {} kinds", census.generation, objects.len()));
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("census").striped(true).show(ui, |ui| {
+                    for heading in ["Count", "Object", "Kind", "Cells", "apgcode"] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+                    for (object, count) in objects {
+                        ui.label(count.to_string());
+                        ui.label(census.names.get(object).map_or_else(|| object.code(), str::to_string)).on_hover_text(object.rle());
+                        ui.label(object.kind.name());
+                        ui.label(object.shape.cells.len().to_string());
+                        match object.apgcode() {
+                            Some(apgcode) => {
+                                let url = soup::catagolue_url(&apgcode, census.rule);
+                                if ui.add(egui::Label::new(&apgcode).sense(egui::Sense::click())).on_hover_text(format!("Click to copy\n{url}")).clicked() {
+                                    ui.ctx().copy_text(apgcode.clone());
+                                    copied = Some(apgcode);
+                                }
+                            }
+                            None => {
+                                ui.label("-");
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+        self.show_census = open;
+        if let Some(apgcode) = copied {
+            self.status = format!("Copied {apgcode}");
+        }
+    }
+
+    /// The spaceships being tracked, each with a button to have the camera follow it.
+    fn draw_tracked_ships(&mut self, ui: &mut egui::Ui) {
+        if let Some(e) = &self.tracking_error {
+            ui.label(e);
+            return;
+        }
+        let ships: Vec<_> = self.tracker.ships().cloned().collect();
+        if ships.is_empty() {
+            ui.label("No spaceships found yet");
+            return;
+        }
+        egui::ScrollArea::vertical().id_source("tracked ships").max_height(120.0).show(ui, |ui| {
+            for ship in ships {
+                ui.horizontal(|ui| {
+                    let (vx, vy) = ship.velocity();
+                    let name = ship.name.map(str::to_string).or_else(|| ship.ship.as_ref().map(|object| object.code())).unwrap_or_default();
+                    ui.label(format!("{name} at ({:.0}, {:.0}), {:.3}c", ship.center.0, ship.center.1, vx.abs().max(vy.abs())));
+                    let following = self.following == Some(ship.id);
+                    if ui.selectable_label(following, "Follow").clicked() {
+                        self.following = if following { None } else { Some(ship.id) };
+                    }
+                });
+            }
+        });
+    }
+
+    /// The benchmark window, which runs the benchmark a slice of each frame while it is open.
+    fn draw_benchmark(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_benchmark;
+        egui::Window::new("Benchmark").open(&mut open).resizable(false).show(ctx, |ui| {
+            let workload = Workload::STANDARD;
+            ui.label(format!(
+                "Runs a {}x{} soup for {} generations on each engine. HashLife runs it without edges, so its population differs.",
+                workload.width, workload.height, workload.generations
+            ));
+            let running = self.benchmark.as_ref().is_some_and(|benchmark| !benchmark.is_done());
+            ui.horizontal(|ui| {
+                if running {
+                    if ui.button("Stop").clicked() {
+                        self.benchmark = None;
+                    }
+                } else if ui.button("Run").clicked() {
+                    self.benchmark = Some(Benchmark::new(workload, Backend::available(default_threads())));
+                }
+            });
+            let Some(benchmark) = &mut self.benchmark else { return };
+            if let Some((backend, progress)) = benchmark.progress() {
+                ui.add(egui::ProgressBar::new(progress as f32).text(backend.name()));
+            }
+            egui::Grid::new("benchmark").striped(true).show(ui, |ui| {
+                for heading in ["Engine", "Generations/s", "Seconds", "Population"] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+                for result in benchmark.results() {
+                    ui.label(result.backend.name());
+                    match &result.timing {
+                        Ok(timing) => {
+                            ui.label(format!("{:.1}", timing.generations_per_second()));
+                            ui.label(format!("{:.3}", timing.seconds));
+                            ui.label(timing.population.to_string());
+                        }
+                        Err(e) => {
+                            ui.colored_label(ui.visuals().error_fg_color, e);
+                            ui.label("");
+                            ui.label("");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+            if ui.button("Copy results").clicked() {
+                ui.ctx().copy_text(benchmark.report());
+            }
+        });
+        self.show_benchmark = open;
+
+        // Run a slice at a time so the window keeps showing progress
+        if let Some(benchmark) = &mut self.benchmark {
+            if self.show_benchmark && !benchmark.is_done() {
+                benchmark.run_for(0.05, get_current_time);
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    fn handle_edit_keys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (undo, redo) = ctx.input(|i| {
+            let command = i.modifiers.command;
+            let undo = command && !i.modifiers.shift && i.key_pressed(egui::Key::Z);
+            let redo = command && (i.key_pressed(egui::Key::Y) || (i.modifiers.shift && i.key_pressed(egui::Key::Z)));
+            (undo, redo)
+        });
+        if undo {
+            self.undo();
+        }
+        if redo {
+            self.redo();
+        }
+    }
+
+    /// Ctrl+Shift and a number key save the grid to that slot, Ctrl and a number key restore it.
+    fn handle_slot_keys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let number_keys = [egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, egui::Key::Num4, egui::Key::Num5, egui::Key::Num6, egui::Key::Num7, egui::Key::Num8, egui::Key::Num9];
+        let pressed = ctx.input(|i| {
+            let slot = number_keys.iter().position(|&key| i.key_pressed(key));
+            slot.filter(|_| i.modifiers.command).map(|slot| (slot, i.modifiers.shift))
+        });
+        match pressed {
+            Some((slot, true)) => self.save_slot(slot),
+            Some((slot, false)) => self.restore_slot(slot),
+            None => {}
+        }
+    }
+
+    /// Copies the universe into a slot, keeping the slot's name if it has one.
+    fn save_slot(&mut self, slot: usize) {
+        let name = match &self.slots[slot] {
+            Some(saved) => saved.name.clone(),
+            None => format!("Generation {}", self.universe.generation()),
+        };
+        self.slots[slot] = Some(Slot { name, universe: self.universe.clone() });
+        self.status = format!("Saved the grid to slot {}", slot + 1);
+    }
+
+    /// Brings back the universe saved in a slot, as an edit that can be undone. The slot keeps
+    /// its copy, so it can be restored again.
+    fn restore_slot(&mut self, slot: usize) {
+        let Some(saved) = self.slots[slot].clone() else {
+            self.status = format!("Slot {} is empty", slot + 1);
+            return;
+        };
+        self.record_edit();
+        self.universe = saved.universe;
+        self.rule = self.universe.rule();
+        self.rule_text = self.rule.to_string();
+        self.rule_error = None;
+        self.ants = self.universe.ants().is_some();
+        self.is_playing = false;
+        self.run_target = None;
+        self.grid_texture = None;
+        self.reset_history();
+        self.status = format!("Restored {} from slot {}", saved.name, slot + 1);
+    }
+
+    /// Remembers the universe before it is edited, so the edit can be undone.
+    fn record_edit(&mut self) {
+        profile_scope!();
+        self.edits.record(self.universe.clone());
+        self.forget_cycle();
+    }
+
+    fn undo(&mut self) {
+        if let Some(previous) = self.edits.undo(self.universe.clone()) {
+            self.universe = previous;
+            self.reset_history();
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(next) = self.edits.redo(self.universe.clone()) {
+            self.universe = next;
+            self.reset_history();
+        }
+    }
+
+    fn update_game_state(&mut self) {
+        let mut generations = if self.universe.engine() == Engine::HashLife { 1 << self.step_exponent } else { 1 };
+        if self.warp {
+            generations *= self.warp_generations;
+        }
+        self.advance(generations);
+    }
+
+    /// Controls for the temperature, random flips and the seed they come from.
+    fn edit_noise(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Temperature:");
+            let slider = egui::Slider::new(&mut self.noise.temperature, 0.0..=0.5).logarithmic(true).smallest_positive(1e-4);
+            ui.add(slider).on_hover_text("Chance that a cell that is alive or next to a live one does the opposite of what the rule says");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Random flips:");
+            let slider = egui::Slider::new(&mut self.noise.flips, 0.0..=0.1).logarithmic(true).smallest_positive(1e-5);
+            let slider = ui.add_enabled(!self.universe.is_infinite(), slider);
+            slider.on_hover_text("Fraction of all cells flipped each generation").on_disabled_hover_text("Only in a finite universe");
+        });
+        if self.noise.is_active() {
+            ui.horizontal(|ui| {
+                ui.label("Seed:");
+                ui.add(egui::DragValue::new(&mut self.noise.seed)).on_hover_text("The same cells with the same seed and settings always run the same way");
+                if ui.button("New seed").clicked() {
+                    self.noise.seed = Rng::new(get_current_time().to_bits()).next_u64();
+                }
+            });
+        }
+    }
+
+    /// Advances the universe by a number of generations as one history entry.
+    fn advance(&mut self, generations: u64) {
+        profile_scope!();
+        // The 3D universe is small enough to step right here, a few generations at a time
+        if self.three_d {
+            let (started, generations) = (get_current_time(), generations.min(MAX_SPACE_GENERATIONS));
+            self.space.advance(generations);
+            let now = get_current_time();
+            self.perf.step(now, generations, now - started);
+            return;
+        }
+        #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+        let generations = self.play_due_events(generations);
+        if generations == 0 {
+            return;
+        }
+
+        // Applied on every step, since restoring a history entry brings back its old settings.
+        // An infinite universe from before a switch to a rule it can't run keeps its old rule.
+        self.universe.set_threads(self.threads);
+        let _ = self.universe.set_rule(self.rule);
+        let _ = self.universe.set_automaton(self.automaton.clone());
+        self.universe.set_ant_mode(self.ants);
+        self.universe.set_turmite(self.turmite.clone());
+        self.universe.set_noise(self.noise);
+
+        // The worker does the stepping and `collect_step` picks up the result; a tick that comes
+        // around while it is still busy is skipped
+        if !self.worker.is_busy() {
+            #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+            if let Some(recorder) = &mut self.recorder {
+                recorder.before_step(&self.universe);
+            }
+            self.step_started = get_current_time();
+            self.worker.submit(self.universe.clone(), generations);
+        }
+    }
+
+    /// Takes the universe advanced by the worker, once it is done, or pauses with why it
+    /// can't be used.
+    fn collect_step(&mut self) {
+        match self.worker.poll() {
+            Some(Ok((next, seconds))) => self.push_generation(next, seconds),
+            Some(Err(e)) => {
+                self.status = e;
+                self.is_playing = false;
+                self.run_target = None;
+            }
+            None => {}
+        }
+    }
+
+    /// Shows the next universe, stepped to in `seconds` of computing.
+    fn push_generation(&mut self, next: Universe, seconds: f64) {
+        profile_scope!();
+        // Aim for steps of roughly one frame when warping or running to a target as fast as
+        // possible
+        let now = get_current_time();
+        let took = now - self.step_started;
+        self.perf.step(now, next.generation().saturating_sub(self.universe.generation()), seconds);
+        if self.warp && self.warp_unlimited {
+            self.warp_generations = frame_sized_batch(self.warp_generations, took);
+        }
+        if self.run_target.is_some() {
+            self.run_batch = frame_sized_batch(self.run_batch, took);
+        }
+
+        self.population_log.record(&next, Some(next.changes_since(&self.universe)));
+
+        // Steps of more than one generation can only tell a multiple of the period
+        let exact = next.generation() == self.universe.generation() + 1;
+        let cycle = self.cycles.observe(next.generation(), next.state_hash()).map(|period| (period, exact));
+        if cycle.is_some() && self.cycle.is_none() && self.pause_on_cycle {
+            self.is_playing = false;
+        }
+        self.cycle = cycle;
+
+        #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+        if let Some(recorder) = &mut self.recorder {
+            recorder.after_step(&next);
+        }
+        self.history.push(next.clone());
+        self.universe = next;
+        self.capture_frame();
+    }
+
+    /// Starts running as fast as possible until the given generation.
+    fn run_to(&mut self, target: u64) {
+        self.is_playing = false;
+        self.run_target = Some(target);
+        self.run_batch = 1;
+    }
+
+    /// Takes the next step toward the run target, never past it, and stops once it is reached.
+    fn run_toward_target(&mut self) {
+        let Some(target) = self.run_target else { return };
+        if self.is_playing {
+            // Playing takes over from the run
+            self.run_target = None;
+            return;
+        }
+        let generation = if self.three_d { self.space.generation() } else { self.universe.generation() };
+        if generation >= target {
+            self.run_target = None;
+            self.status = format!("Reached generation {generation}");
+        } else {
+            self.advance(self.run_batch.min(target - generation));
+        }
+    }
+
+    /// Goes back to the previous generation in the history.
+    fn step_back(&mut self) {
+        if self.history.index() > 0 && !self.three_d {
+            self.cancel_step();
+            self.forget_cycle();
+            self.universe = self.history.seek(self.history.index() - 1).clone();
+        }
+    }
+
+    /// Switches to the rule typed in, if it parses and suits the universe.
+    fn apply_rule_text(&mut self) {
+        let rule = match Rule::parse(&self.rule_text) {
+            Ok(rule) => rule,
+            Err(err) => {
+                self.rule_error = Some(err);
+                return;
+            }
+        };
+        self.rule_error = None;
+        if rule != self.universe.rule() {
+            if let Err(err) = self.universe.set_rule(rule) {
+                self.rule_error = Some(err);
+                return;
+            }
+            self.draw_state = self.draw_state.min(rule.cell_states() - 1);
+            self.cancel_step();
+            self.forget_cycle();
+        }
+        self.rule = rule;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_rule_file(&mut self) {
+        let text = match std::fs::read_to_string(&self.rule_file_path) {
+            Ok(text) => text,
+            Err(e) => {
+                self.status = format!("Failed to open {}: {}", self.rule_file_path, e);
+                return;
+            }
+        };
+        match RuleFile::parse(&text) {
+            Ok(file) => self.load_automaton(Arc::new(file)),
+            Err(e) => self.status = format!("Failed to read {}: {}", self.rule_file_path, e),
+        }
+    }
+
+    /// Runs an automaton in place of the rule, until it is unloaded.
+    fn load_automaton(&mut self, automaton: Arc<dyn Automaton>) {
+        if let Err(e) = self.universe.set_automaton(Some(automaton.clone())) {
+            self.status = e;
+            return;
+        }
+        self.status = format!("Loaded rule {} with {} states", automaton.name(), automaton.states());
+        self.automaton = Some(automaton);
+        self.draw_state = 1;
+        self.cancel_step();
+        self.forget_cycle();
+    }
+
+    fn unload_automaton(&mut self) {
+        let _ = self.universe.set_automaton(None);
+        self.automaton = None;
+        self.cancel_step();
+        self.forget_cycle();
+    }
+
+    /// Lets ants take over from the rule, or goes back to the rule without them.
+    fn set_ants(&mut self, on: bool) {
+        if on != self.ants {
+            self.ants = on;
+            self.universe.set_ant_mode(on);
+            self.cancel_step();
+            self.forget_cycle();
+        }
+    }
+
+    /// Switches to the turmite typed in, if it parses.
+    fn apply_turmite_text(&mut self) {
+        match Turmite::parse(&self.turmite_text) {
+            Ok(turmite) => self.set_turmite(turmite),
+            Err(err) => self.turmite_error = Some(err),
+        }
+    }
+
+    fn set_turmite(&mut self, turmite: Turmite) {
+        self.turmite_error = None;
+        if turmite != self.turmite {
+            self.universe.set_turmite(turmite.clone());
+            self.draw_state = self.draw_state.min(turmite.colors - 1);
+            self.turmite = turmite;
+            self.cancel_step();
+            self.forget_cycle();
+        }
+    }
+
+    /// The turmite's presets, notation and table, one row per state and one column per color,
+    /// each entry being the color to paint, the turn and the next state.
+    fn edit_turmite(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Turmite:");
+            let edit = egui::TextEdit::singleline(&mut self.turmite_text).desired_width(140.0);
+            let hover = "Turns for each color, e.g. RL for Langton's ant or LLRR, with N for no turn and U for a U-turn, or a table in Golly's notation, e.g. {{{1,2,0},{0,8,0}}}";
+            if ui.add(edit).on_hover_text(hover).changed() {
+                self.apply_turmite_text();
+            }
+            let mut preset = None;
+            let name = Turmite::PRESETS.iter().find(|(_, text, _)| Turmite::parse(text).ok().as_ref() == Some(&self.turmite));
+            egui::ComboBox::from_id_source("turmite")
+                .selected_text(name.map_or("Custom", |(name, _, _)| name))
+                .show_ui(ui, |ui| {
+                    for (name, text, description) in Turmite::PRESETS {
+                        let selected = Turmite::parse(text).ok().as_ref() == Some(&self.turmite);
+                        if ui.selectable_label(selected, name).on_hover_text(description).clicked() {
+                            preset = Some(text);
+                        }
+                    }
+                });
+            if let Some(text) = preset {
+                self.turmite_text = text.to_string();
+                self.apply_turmite_text();
+            }
+        });
+        if let Some(error) = &self.turmite_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        let mut turmite = self.turmite.clone();
+        ui.horizontal(|ui| {
+            let (mut states, mut colors) = (turmite.states, turmite.colors);
+            ui.add(egui::DragValue::new(&mut states).clamp_range(1..=Turmite::MAX_STATES).prefix("States: "));
+            ui.add(egui::DragValue::new(&mut colors).clamp_range(2..=Turmite::MAX_COLORS).prefix("Colors: "));
+            if (states, colors) != (turmite.states, turmite.colors) {
+                turmite = turmite.resized(states, colors);
+            }
+        });
+        egui::Grid::new("turmite_table").striped(true).show(ui, |ui| {
+            ui.label("");
+            for color in 0..turmite.colors {
+                ui.label(format!("On color {color}"));
+            }
+            ui.end_row();
+            let (states, colors) = (turmite.states, turmite.colors);
+            for (state, row) in turmite.table.chunks_mut(colors as usize).enumerate() {
+                ui.label(format!("State {state}"));
+                for (color, transition) in row.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut transition.color).clamp_range(0..=colors - 1)).on_hover_text("Color to paint");
+                        egui::ComboBox::from_id_source(("turn", state, color))
+                            .width(70.0)
+                            .selected_text(transition.turn.name())
+                            .show_ui(ui, |ui| {
+                                for turn in Turn::ALL {
+                                    ui.selectable_value(&mut transition.turn, turn, turn.name());
+                                }
+                            });
+                        ui.add(egui::DragValue::new(&mut transition.state).clamp_range(0..=states - 1)).on_hover_text("Next state");
+                    });
+                }
+                ui.end_row();
+            }
+        });
+        if turmite != self.turmite {
+            self.turmite_text = turmite.to_string();
+            self.set_turmite(turmite);
+        }
+    }
+
+    /// Starts cycle detection over, since the cells no longer follow from the generations seen.
+    fn apply_space_rule_text(&mut self) {
+        match Rule3d::parse(&self.space_rule_text) {
+            Ok(rule) => {
+                self.space.set_rule(rule);
+                self.space_rule_error = None;
+            }
+            Err(err) => self.space_rule_error = Some(err),
+        }
+    }
+
+    /// The 3D rule, the size and edges of the cube, and how it is shown.
+    fn edit_space(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("3D rule:");
+            let edit = egui::TextEdit::singleline(&mut self.space_rule_text).desired_width(100.0).hint_text("5766");
+            let hover = "Bays' notation: the fewest and most of the 26 neighbors a live voxel survives with, then the fewest and most a dead one is born with, e.g. 5766 or 4555, with commas between numbers above 9, e.g. 13,26,13,14";
+            if ui.add(edit).on_hover_text(hover).changed() {
+                self.apply_space_rule_text();
+            }
+            let mut preset = None;
+            egui::ComboBox::from_id_source("3d rule preset")
+                .selected_text(self.space.rule().name())
+                .show_ui(ui, |ui| {
+                    for (name, rule, description) in Rule3d::PRESETS {
+                        if ui.selectable_label(rule == self.space.rule(), name).on_hover_text(description).clicked() {
+                            preset = Some(rule);
+                        }
+                    }
+                });
+            if let Some(rule) = preset {
+                self.space_rule_text = rule.to_string();
+                self.apply_space_rule_text();
+            }
+        });
+        if let Some((_, _, description)) = Rule3d::PRESETS.iter().find(|(_, rule, _)| *rule == self.space.rule()) {
+            ui.weak(*description);
+        }
+        if let Some(error) = &self.space_rule_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.horizontal(|ui| {
+            ui.label("Cube size:");
+            let mut size = self.space.size();
+            ui.add(egui::Slider::new(&mut size, Universe3d::MIN_SIZE..=Universe3d::MAX_SIZE));
+            if size != self.space.size() {
+                // Stay on the same layer as the voxels move to the middle of the new cube
+                let shift = (size / 2) as isize - (self.space.size() / 2) as isize;
+                self.space_layer = (self.space_layer as isize + shift).clamp(0, size as isize - 1) as usize;
+                self.space = self.space.resized(size);
+                self.fit_requested = true;
+            }
+        });
+        let mut wrap = self.space.wraps();
+        if ui.checkbox(&mut wrap, "Wrap around").on_hover_text("Opposite faces of the cube meet, instead of all beyond them being dead").changed() {
+            self.space.set_wrap(wrap);
+        }
+        ui.horizontal(|ui| {
+            ui.label("View:");
+            for view in SpaceView::ALL {
+                if ui.selectable_value(&mut self.space_view, view, view.name()).clicked() {
+                    self.fit_requested = true;
+                }
+            }
+        });
+        if self.space_view == SpaceView::Slices {
+            ui.horizontal(|ui| {
+                ui.label("Layer:");
+                ui.add(egui::Slider::new(&mut self.space_layer, 0..=self.space.size() - 1));
+            });
+        } else {
+            ui.label("Drag to turn the cube around");
+        }
+    }
+
+    /// Controls for how often the grid steps while playing, and for warping through generations.
+    fn edit_speed(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Update frequency (s):");
+            ui.add_enabled(!self.warp, egui::Slider::new(&mut self.update_frequency, 0.1..=2.0));
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.warp, "Warp").on_hover_text("Step on every frame");
+            if self.warp {
+                ui.checkbox(&mut self.warp_unlimited, "As fast as possible");
+            }
+        });
+        if self.warp {
+            ui.horizontal(|ui| {
+                ui.label("Generations per frame:");
+                let slider = egui::Slider::new(&mut self.warp_generations, 1..=MAX_WARP_GENERATIONS).logarithmic(true);
+                ui.add_enabled(!self.warp_unlimited, slider);
+            });
+        }
+    }
+
+    /// The rule as text, from the presets or by neighborhood, or a rule file loaded in its place.
+    fn edit_rule(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Rule:");
+            let edit = egui::TextEdit::singleline(&mut self.rule_text).desired_width(100.0).hint_text("B3/S23");
+            if ui.add(edit).on_hover_text("Birth and survival neighbor counts, e.g. B36/S23, Hensel letters to pick arrangements of neighbors, e.g. B2-a/S12, a number of states for rules with decay, e.g. B2/S345/C4, and V, H, L or LE for the von Neumann, hexagonal, triangular or edge-only triangular neighborhood, e.g. B1/S1V, B2/S34H or B4/S345L. Wider neighborhoods and Larger than Life rules are written as R2,C0,S6-9,B7-8,NC or R5,C0,M1,S34..58,B34..45,NM, Margolus block rules as MS,D followed by the 16 blocks, e.g. MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15, one-dimensional elementary rules as W and their number, e.g. W30, growing down from the top row, and colored rules with Immigration or QuadLife after them, or alone for Conway's Life").changed() {
+                self.apply_rule_text();
+            }
+            let mut preset = None;
+            egui::ComboBox::from_id_source("rule preset")
+                .selected_text(self.rule.name())
+                .show_ui(ui, |ui| {
+                    for (name, rule, description) in Rule::PRESETS {
+                        if ui.selectable_label(rule == self.rule, name).on_hover_text(description).clicked() {
+                            preset = Some(rule);
+                        }
+                    }
+                });
+            if let Some(rule) = preset {
+                self.rule_text = rule.to_string();
+                self.apply_rule_text();
+            }
+        });
+        if let Some((_, _, description)) = Rule::PRESETS.iter().find(|(_, rule, _)| *rule == self.rule) {
+            ui.weak(*description);
+        }
+        if self.rule.larger_than_life.is_none() && self.rule.margolus.is_none() && self.rule.elementary.is_none() {
+            ui.horizontal(|ui| {
+                ui.label("Neighborhood:");
+                let mut chosen = None;
+                egui::ComboBox::from_id_source("neighborhood")
+                    .selected_text(self.rule.neighborhood.name())
+                    .show_ui(ui, |ui| {
+                        for (name, neighborhood) in Neighborhood::PRESETS {
+                            if ui.selectable_label(neighborhood == self.rule.neighborhood, name).clicked() {
+                                chosen = Some(neighborhood);
+                            }
+                        }
+                    });
+                if let Some(neighborhood) = chosen {
+                    self.rule_text = self.rule.with_neighborhood(neighborhood).to_string();
+                    self.apply_rule_text();
+                }
+            });
+        }
+        if let Some(error) = &self.rule_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.horizontal(|ui| match self.automaton.as_deref() {
+            Some(automaton) => {
+                ui.label(format!("Rule: {} ({} states)", automaton.name(), automaton.states()));
+                if ui.button("Unload").on_hover_text("Go back to the rule above").clicked() {
+                    self.unload_automaton();
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            None => {
+                ui.text_edit_singleline(&mut self.rule_file_path);
+                if ui.button("Load rule").on_hover_text("Golly .rule file with a @TABLE or @TREE").clicked() {
+                    self.load_rule_file();
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            None => {}
+        });
+    }
+
+    /// The state or color drawn cells get, when there is more than one.
+    fn edit_draw_state(&mut self, ui: &mut egui::Ui) {
+        let states = self.universe.states();
+        if states > 2 && (self.ants || self.automaton.is_some()) {
+            ui.horizontal(|ui| {
+                ui.label("Draw state:");
+                ui.add(egui::DragValue::new(&mut self.draw_state).clamp_range(1..=states - 1));
+            });
+        } else if self.rule.colors > 1 {
+            ui.horizontal(|ui| {
+                ui.label("Draw color:");
+                for color in 1..=self.rule.colors {
+                    let swatch = egui::RichText::new("⏹").color(live_color(&self.theme, color)).size(18.0);
+                    ui.selectable_value(&mut self.draw_state, color, swatch).on_hover_text(format!("Color {color}"));
+                }
+            });
+        }
+    }
+
+    /// Controls for unbounded universes, the engine and how it runs, and the benchmark.
+    fn edit_engine(&mut self, ui: &mut egui::Ui) {
+        let births_from_nothing = self.universe.births_from_nothing();
+        let mut infinite = self.universe.is_infinite();
+        let checkbox = egui::Checkbox::new(&mut infinite, "Infinite universe");
+        let checkbox = ui.add_enabled(!births_from_nothing, checkbox);
+        if checkbox.on_disabled_hover_text("The rule brings empty space to life").changed() {
+            self.universe.set_infinite(infinite);
+            self.reset_history();
+        }
+        ui.horizontal(|ui| {
+            ui.label("Engine:");
+            let mut engine = self.universe.engine();
+            egui::ComboBox::from_id_source("engine")
+                .selected_text(engine.name())
+                .show_ui(ui, |ui| {
+                    let rule = self.universe.rule();
+                    let has_automaton = self.universe.automaton().is_some();
+                    let usable = |e: &Engine| {
+                        let automaton = *e != Engine::HashLife || !has_automaton;
+                        e.is_available() && (e.supports_finite() || !births_from_nothing) && e.supports_rule(rule) && automaton
+                    };
+                    for option in Engine::ALL.into_iter().filter(usable) {
+                        ui.selectable_value(&mut engine, option, option.name());
+                    }
+                });
+            if engine != self.universe.engine() {
+                match self.universe.set_engine(engine) {
+                    Ok(()) => self.reset_history(),
+                    Err(e) => self.status = e,
+                }
+            }
+            if ui.button("Benchmark").on_hover_text("Time every engine on a standard soup").clicked() {
+                self.show_benchmark = !self.show_benchmark;
+            }
+        });
+        if self.universe.engine() == Engine::HashLife {
+            ui.horizontal(|ui| {
+                ui.label("Generations per tick: 2^");
+                ui.add(egui::Slider::new(&mut self.step_exponent, 0..=30));
+            });
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if matches!(self.universe.engine(), Engine::BitPacked | Engine::Simd) {
+            ui.horizontal(|ui| {
+                ui.label("Threads:");
+                ui.add(egui::Slider::new(&mut self.threads, 1..=default_threads().max(2)));
+            });
+        }
+    }
+
+    /// The size of a finite grid and how its edges are glued together.
+    fn edit_topology(&mut self, ui: &mut egui::Ui) {
+        let infinite = self.universe.is_infinite();
+        if !infinite {
+            let mut width = self.universe.width();
+            let mut height = self.universe.height();
+            ui.horizontal(|ui| {
+                ui.label("Grid Width:");
+                let max = MAX_GRID_LENGTH.max(width);
+                ui.add(egui::Slider::new(&mut width, MIN_GRID_LENGTH..=max).logarithmic(true));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Grid Height:");
+                let max = MAX_GRID_LENGTH.max(height);
+                ui.add(egui::Slider::new(&mut height, MIN_GRID_LENGTH..=max).logarithmic(true));
+            });
+            if (width, height) != (self.universe.width(), self.universe.height()) {
+                self.resize_grid(width, height);
+                self.is_playing = false;
+            }
+        }
+        ui.add_enabled_ui(!infinite, |ui| ui.collapsing("Topology", |ui| {
+            let mut topology = self.universe.topology();
+            egui::ComboBox::from_label("Preset")
+                .selected_text(topology.name())
+                .show_ui(ui, |ui| {
+                    for (name, preset) in Topology::PRESETS {
+                        ui.selectable_value(&mut topology, preset, name);
+                    }
+                });
+            edge_combo(ui, "Left/right edges", &mut topology.horizontal);
+            edge_combo(ui, "Top/bottom edges", &mut topology.vertical);
+            if topology != self.universe.topology() {
+                self.universe.set_topology(topology);
+                self.cancel_step();
+            }
+        }));
+    }
+
+    /// Timeline of the retained generations; playing on from a past one drops the ones after it.
+    fn draw_timeline(&mut self, ui: &mut egui::Ui) {
+        if !self.history.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label("Timeline:");
+                let mut index = self.history.index();
+                let history = &self.history;
+                let slider = egui::Slider::new(&mut index, 0..=history.len() - 1)
+                    .custom_formatter(|i, _| format!("gen {}", history.get(i as usize).generation()));
+                ui.add(slider).on_hover_text(format!(
+                    "{} generations kept ({:.1} MB)",
+                    history.len(),
+                    history.memory_size() as f64 / (1 << 20) as f64
+                ));
+                if index != self.history.index() {
+                    self.universe = self.history.seek(index).clone();
+                    self.cancel_step();
+                    self.forget_cycle();
+                }
+            });
+        }
+    }
+
+    /// Random fills: their density, symmetry and seed.
+    fn edit_fill(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let area = if self.selection.is_some() && !self.three_d { "the selection" } else { "the grid" };
+            if ui.button("Randomize").on_hover_text(format!("Fill {area} with random cells from the seed")).clicked() {
+                self.random_fill();
+            }
+            ui.add(egui::Slider::new(&mut self.fill_density, 0.0..=1.0).text("density"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Symmetry:");
+            egui::ComboBox::from_id_source("fill_symmetry")
+                .selected_text(self.fill_symmetry.group())
+                .show_ui(ui, |ui| {
+                    for symmetry in Symmetry::ALL {
+                        let label = format!("{} ({})", symmetry.group(), symmetry.name());
+                        ui.selectable_value(&mut self.fill_symmetry, symmetry, label);
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Seed:");
+            ui.add(egui::DragValue::new(&mut self.fill_seed)).on_hover_text("The same seed and density fill the same area with the same cells");
+            if ui.button("New seed").clicked() {
+                self.new_fill_seed();
+            }
+        });
+    }
+
+    /// Stepping while paused, and running as fast as possible to a generation.
+    fn edit_stepping(&mut self, ui: &mut egui::Ui) {
+        ui.add_enabled_ui(!self.is_playing, |ui| {
+            ui.horizontal(|ui| {
+                let step_back = egui::Button::new("Step back");
+                if ui.add_enabled(self.history.index() > 0 && !self.three_d, step_back).on_hover_text("Go back one generation").clicked() {
+                    self.step_back();
+                }
+                if ui.button("Step").on_hover_text("Advance one generation").clicked() {
+                    self.advance(1);
+                }
+                if ui.button("Advance").clicked() {
+                    self.advance(self.advance_count);
+                }
+                ui.add(egui::DragValue::new(&mut self.advance_count).clamp_range(1..=u32::MAX).suffix(" generations"));
+            });
+        });
+        ui.horizontal(|ui| {
+            if self.run_target.is_some() {
+                if ui.button("Stop").clicked() {
+                    self.run_target = None;
+                }
+            } else {
+                let run = egui::Button::new("Run to");
+                let generation = if self.three_d { self.space.generation() } else { self.universe.generation() };
+                let ahead = self.target_generation > generation;
+                if ui.add_enabled(ahead, run).on_hover_text("Advance as fast as possible and stop exactly at this generation").clicked() {
+                    self.run_to(self.target_generation);
+                }
+            }
+            ui.add(egui::DragValue::new(&mut self.target_generation).prefix("generation "));
+        });
+    }
+
+    /// The generation and population, where the pattern is heading, and the chart, statistics
+    /// export, census and spaceship tracking.
+    fn draw_stats(&mut self, ui: &mut egui::Ui) {
+        if self.three_d {
+            ui.label(format!("Generation: {}   Population: {}", self.space.generation(), self.space.population()));
+        } else {
+            ui.horizontal(|ui| {
+                ui.label(format!("Generation: {}   Population: {}", self.universe.generation(), self.universe.population()));
+                ui.checkbox(&mut self.show_chart, "Chart");
+                if ui.button("Export CSV").on_hover_text("Save the population, births, deaths and bounding box of each generation for spreadsheets").clicked() {
+                    self.save_stats();
+                }
+                if ui.button("Census").on_hover_text("Count the still lifes, oscillators and spaceships on the grid").clicked() {
+                    self.show_census = !self.show_census;
+                }
+            });
+            let colors = self.universe.color_populations();
+            if !colors.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label("By color:");
+                    for (color, count) in (1..).zip(colors) {
+                        ui.colored_label(live_color(&self.theme, color), format!("⏹ {count}"));
+                    }
+                });
+            }
+            if ui.checkbox(&mut self.track_ships, "Track spaceships").on_hover_text("Follow objects from one generation to the next and box the ones that move").changed() {
+                self.tracker.clear();
+                self.tracking_error = None;
+                self.following = None;
+            }
+            if self.track_ships {
+                self.draw_tracked_ships(ui);
+            }
+            let last = self.population_log.samples().last().filter(|s| s.generation == self.universe.generation());
+            if let Some(changes) = last.and_then(|s| s.changes) {
+                ui.label(format!("Last step: {} born, {} died", changes.births, changes.deaths));
+            }
+            ui.horizontal(|ui| {
+                match self.cycle {
+                    Some(_) if self.universe.population() == 0 => ui.label("Died out"),
+                    Some((1, _)) => ui.label("Still life"),
+                    Some((period, true)) => ui.label(format!("Cycling with period {period}")),
+                    Some((period, false)) => ui.label(format!("Repeats every {period} generations")),
+                    None => ui.label("No cycle found yet"),
+                };
+                ui.checkbox(&mut self.pause_on_cycle, "Pause on cycle");
+            });
+        }
+    }
+
+    /// The editing tools with their options, and the selection and clipboard.
+    fn edit_tools(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Tool:");
+            for tool in Tool::ALL {
+                ui.selectable_value(&mut self.tool, tool, tool.name());
+            }
+        });
+        ui.label("Pan with the right or middle button");
+        if matches!(self.tool, Tool::Rectangle | Tool::Ellipse) {
+            ui.checkbox(&mut self.shape_filled, "Filled");
+        }
+        ui.horizontal(|ui| {
+            ui.label("Symmetry:");
+            egui::ComboBox::from_id_source("symmetry")
+                .selected_text(self.symmetry.name())
+                .show_ui(ui, |ui| {
+                    for symmetry in Symmetry::ALL {
+                        ui.selectable_value(&mut self.symmetry, symmetry, symmetry.name());
+                    }
+                });
+        });
+        if matches!(self.tool, Tool::Draw | Tool::Erase) {
+            ui.horizontal(|ui| {
+                ui.label("Brush:");
+                egui::ComboBox::from_id_source("brush_shape")
+                    .selected_text(self.brush_shape.name())
+                    .show_ui(ui, |ui| {
+                        for shape in BrushShape::ALL {
+                            ui.selectable_value(&mut self.brush_shape, shape, shape.name());
+                        }
+                    });
+                ui.add(egui::Slider::new(&mut self.brush_radius, 0..=32).text("radius"));
+            });
+        }
+        ui.horizontal(|ui| {
+            let selected = self.selection.is_some();
+            if ui.add_enabled(selected, egui::Button::new("Cut")).on_hover_text("Ctrl+X").clicked() {
+                self.cut_selection(ui.ctx());
+            }
+            if ui.add_enabled(selected, egui::Button::new("Copy")).on_hover_text("Ctrl+C").clicked() {
+                self.copy_selection(ui.ctx());
+            }
+            if ui.add_enabled(self.clipboard.is_some(), egui::Button::new("Paste")).on_hover_text("Ctrl+V").clicked() {
+                self.paste = self.clipboard.clone();
+            }
+            if ui.add_enabled(selected, egui::Button::new("Delete")).on_hover_text("Del").clicked() {
+                self.delete_selection();
+            }
+            if self.paste.is_some() {
+                ui.label("Click to place, Esc to cancel");
+            }
+        });
+        if self.selection.is_some() {
+            ui.horizontal(|ui| {
+                if ui.button("Rotate").on_hover_text("Rotate the selection 90° clockwise").clicked() {
+                    self.transform_selection(Pattern::rotate_clockwise, (0, 0));
+                }
+                if ui.button("Flip H").on_hover_text("Mirror the selection left-to-right").clicked() {
+                    self.transform_selection(Pattern::flip_horizontal, (0, 0));
+                }
+                if ui.button("Flip V").on_hover_text("Mirror the selection top-to-bottom").clicked() {
+                    self.transform_selection(Pattern::flip_vertical, (0, 0));
+                }
+                ui.label("Arrow keys to nudge");
+            });
+        }
+    }
+
+    /// The stamp from the pattern browser, and pattern, session, image, animation and replay files.
+    fn edit_files(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!("Stamp: {}", self.stamp.as_ref().map_or("None", |(name, _)| name.as_str())));
+            if ui.button("Browse patterns").on_hover_text("The library and your saved patterns, with thumbnails and search").clicked() {
+                self.show_browser = !self.show_browser;
+            }
+            if self.stamp.is_some() {
+                if ui.button("Rotate").on_hover_text("Rotate 90° (R)").clicked() {
+                    self.stamp_rotation = (self.stamp_rotation + 1) % 4;
+                }
+                if ui.button("Mirror").on_hover_text("Mirror (F)").clicked() {
+                    self.stamp_flipped = !self.stamp_flipped;
+                }
+                ui.label("Esc to cancel");
+            }
+        });
+
+        // Pattern import/export
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("pattern_format")
+                .selected_text(self.pattern_format.name())
+                .show_ui(ui, |ui| {
+                    for format in Format::ALL {
+                        ui.selectable_value(&mut self.pattern_format, format, format.name());
+                    }
+                });
+            if ui.button("Copy").clicked() {
+                self.export_pattern(ui.ctx());
+            }
+            if ui.button("Copy as text").on_hover_text("Rows of . and O for forums and commit messages").clicked() {
+                self.copy_pattern_art(ui.ctx());
+            }
+            if ui.button("Share").on_hover_text("Copy a link with the whole pattern and rule in it").clicked() {
+                self.share(ui.ctx());
+            }
+            if ui.button("Load from URL").on_hover_text("Download an RLE file, open a shared link, or a pattern from LifeWiki by name").clicked() {
+                self.show_url_dialog = true;
+            }
+            if ui.button("Copy apgcode").on_hover_text("Catagolue's name for the selection, or the whole pattern if nothing is selected").clicked() {
+                self.copy_apgcode(ui.ctx());
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.text_edit_singleline(&mut self.pattern_path);
+                if ui.button("Open").clicked() {
+                    self.open_pattern();
+                }
+                if ui.button("Save").clicked() {
+                    self.save_pattern();
+                }
+            }
+        });
+        #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+        ui.horizontal(|ui| {
+            ui.label("Session:");
+            ui.text_edit_singleline(&mut self.session_path);
+            if ui.button("Open").on_hover_text("Bring back a saved grid with its rule, generation, speed and view").clicked() {
+                self.open_session();
+            }
+            if ui.button("Save").on_hover_text("Save the grid with its rule, generation, speed and view").clicked() {
+                self.save_session();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Image:");
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.text_edit_singleline(&mut self.image_path);
+            ui.add(egui::DragValue::new(&mut self.image_scale).clamp_range(1..=32).suffix(" px per cell"));
+            if ui.button("Save image").on_hover_text("The whole universe, not just the view, without the grid lines").clicked() {
+                self.save_image();
+            }
+            if ui.button("Save SVG").on_hover_text("The whole universe as vector art, crisp at any size").clicked() {
+                self.save_svg();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Animation:");
+            egui::ComboBox::from_id_source("animation_format")
+                .selected_text(self.animation_format.name())
+                .show_ui(ui, |ui| {
+                    for format in AnimationFormat::ALL.into_iter().filter(|format| format.is_available()) {
+                        ui.selectable_value(&mut self.animation_format, format, format.name());
+                    }
+                });
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.text_edit_singleline(&mut self.animation_path);
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.animation_length).clamp_range(1..=10_000).suffix(" generations"));
+            ui.add(egui::DragValue::new(&mut self.animation_delay).clamp_range(10..=5000).suffix(" ms per frame"));
+            match &self.animation {
+                Some(frames) => {
+                    let recorded = self.universe.generation().saturating_sub(frames[0].generation());
+                    if ui.button("Stop and save").on_hover_text(format!("{recorded} generations recorded")).clicked() {
+                        self.save_animation();
+                    }
+                }
+                None => {
+                    let hover = "Record a frame for each step until the generations are done, drawn at the image scale";
+                    if ui.button("Record").on_hover_text(hover).clicked() {
+                        self.animation = Some(vec![self.universe.clone()]);
+                        self.status = "Recording an animation".to_string();
+                    }
+                }
+            }
+        });
+        #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+        ui.horizontal(|ui| {
+            ui.label("Replay:");
+            ui.text_edit_singleline(&mut self.replay_path);
+            let recorded = self.recorder.as_ref().map(Recorder::generations);
+            let playing_to = self.player.as_ref().map(Player::end);
+            match (recorded, playing_to) {
+                (Some(generations), _) => {
+                    if ui.button("Stop and save").on_hover_text(format!("{generations} generations recorded")).clicked() {
+                        self.stop_recording();
+                    }
+                }
+                (None, Some(end)) => {
+                    if ui.button("Stop").on_hover_text(format!("Playing back to generation {end}")).clicked() {
+                        self.player = None;
+                    }
+                }
+                (None, None) => {
+                    let hover = "Record the grid, and every rule change and edit from the next step on, to play back exactly";
+                    if ui.button("Record").on_hover_text(hover).clicked() {
+                        self.recorder = Some(Recorder::new());
+                        self.status = "Recording a replay".to_string();
+                    }
+                    if ui.button("Play back").clicked() {
+                        self.play_replay();
+                    }
+                }
+            }
+        });
+    }
+
+    /// Saving the grid into the numbered slots and restoring it from them.
+    fn edit_slots(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("slots").show(ui, |ui| {
+            for slot in 0..SLOTS {
+                ui.label(format!("{}", slot + 1));
+                match &mut self.slots[slot] {
+                    Some(saved) => {
+                        ui.text_edit_singleline(&mut saved.name);
+                        ui.weak(format!("{} cells", saved.universe.population()));
+                    }
+                    None => {
+                        ui.weak("Empty");
+                        ui.label("");
+                    }
+                }
+                if ui.button("Save").on_hover_text(format!("Ctrl+Shift+{}", slot + 1)).clicked() {
+                    self.save_slot(slot);
+                }
+                let restore = ui.add_enabled(self.slots[slot].is_some(), egui::Button::new("Restore"));
+                if restore.on_hover_text(format!("Ctrl+{}", slot + 1)).clicked() {
+                    self.restore_slot(slot);
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    /// The appearance, the palette and its colors, and coloring by age, rainbow colors and trails.
+    fn edit_colors(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("Appearance")
+            .selected_text(self.appearance.name())
+            .show_ui(ui, |ui| {
+                for appearance in Appearance::ALL {
+                    ui.selectable_value(&mut self.appearance, appearance, appearance.name());
+                }
+            });
+        let theme = self.theme;
+        let default = Theme::for_dark_mode(self.dark_mode);
+        egui::ComboBox::from_label("Palette")
+            .selected_text(if theme == default { "Default" } else { theme.name() })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.theme, default, "Default");
+                for (name, preset) in Theme::PRESETS {
+                    ui.selectable_value(&mut self.theme, preset, name);
+                }
+            });
+        egui::Grid::new("theme").num_columns(2).show(ui, |ui| {
+            for (label, color) in [
+                ("Alive", &mut self.theme.alive),
+                ("Dead", &mut self.theme.dead),
+                ("Grid lines", &mut self.theme.grid_line),
+                ("Background", &mut self.theme.background),
+                ("Newborn", &mut self.theme.newborn),
+                ("Old", &mut self.theme.old),
+            ] {
+                ui.label(label);
+                ui.color_edit_button_srgba(color);
+                ui.end_row();
+            }
+        });
+        let (color_by_age, age_span) = (self.color_by_age, self.age_span);
+        ui.checkbox(&mut self.color_by_age, "Color by age")
+            .on_hover_text("Shade live cells from the newborn to the old color by how long they have been alive");
+        ui.add_enabled_ui(self.color_by_age, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Oldest shade at:");
+                ui.add(egui::DragValue::new(&mut self.age_span).clamp_range(1..=100_000).suffix(" gen"));
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.rainbow, "Rainbow")
+                .on_hover_text("Live cells carry colors, and newborns get the average color of their parents. Random fills use every color, and drawn cells get the brush color");
+            ui.add_enabled_ui(self.rainbow, |ui| {
+                ui.label("Brush:");
+                ui.color_edit_button_srgba(&mut self.rainbow_brush);
+            });
+        });
+        let trail_length = self.trail_length;
+        ui.horizontal(|ui| {
+            ui.label("Trails:");
+            ui.add(egui::Slider::new(&mut self.trail_length, 0..=64).suffix(" gen"))
+                .on_hover_text("Cells that died this many generations ago or less fade out instead of vanishing");
+        });
+        let settings = (self.color_by_age, self.age_span, self.trail_length);
+        if self.theme != theme || settings != (color_by_age, age_span, trail_length) {
+            // The texture has the old colors baked in
+            self.grid_texture = None;
+        }
+    }
+
+    fn forget_cycle(&mut self) {
+        self.cycles.clear();
+        self.cycle = None;
+    }
+
+    /// Drops any step still running in the background, since it started from a universe that
+    /// has since been edited.
+    fn cancel_step(&mut self) {
+        self.worker.cancel();
+    }
+
+    fn clear_grid(&mut self) {
+        if self.three_d {
+            self.space.clear();
+            return;
+        }
+        self.cancel_step();
+        self.universe.clear();
+    }
+
+    /// Reallocates the grid at a new size, keeping the existing cells centered.
+    fn resize_grid(&mut self, width: usize, height: usize) {
+        let (resized, (shift_x, shift_y)) = self.universe.resized(width, height);
+        self.universe = resized;
+
+        // Keep the camera on the same cells, and drop history snapshots of the old size
+        self.camera.center += self.grid_to_world(Pos2::new(shift_x as f32, shift_y as f32)).to_vec2();
+        self.reset_history();
+    }
+
+    fn reset_history(&mut self) {
+        self.cancel_step();
+        self.forget_cycle();
+        self.history.reset(self.universe.clone());
+    }
+
+    /// Sets the pattern's live cells with its center at (x, y), clipping anything outside the grid.
+    fn stamp_pattern(&mut self, pattern: &Pattern, x: i64, y: i64) {
+        let (left, top) = stamp_origin(pattern, x, y);
+        for &(px, py) in &pattern.cells {
+            self.paint(left + px as i64, top + py as i64, true);
+        }
+    }
+
+    /// Sets a cell and its images under the editing symmetry, skipping any outside the grid.
+    fn paint(&mut self, x: i64, y: i64, alive: bool) {
+        for (x, y) in self.symmetry.images((x, y), self.symmetry_center()) {
+            if self.universe.contains(x, y) {
+                match self.universe.automaton() {
+                    _ if alive && self.ants => self.universe.set_state(x, y, self.draw_state),
+                    Some(_) if alive => self.universe.set_state(x, y, self.draw_state),
+                    None if alive && self.rule.colors > 1 => self.universe.set_state(x, y, self.draw_state),
+                    _ => self.universe.set(x, y, alive),
+                }
+            }
+        }
+    }
+
+    /// Doubled center of symmetry: the middle of a finite grid, or cell (0, 0) when infinite.
+    fn symmetry_center(&self) -> (i64, i64) {
+        match self.universe.is_infinite() {
+            true => (0, 0),
+            false => (self.universe.width() as i64 - 1, self.universe.height() as i64 - 1),
+        }
+    }
+
+    fn current_pattern(&self) -> Pattern {
+        self.universe.to_pattern()
+    }
+
+    fn export_pattern(&mut self, ctx: &egui::Context) {
+        let pattern = self.current_pattern();
+        if pattern.is_empty() {
+            self.status = "Nothing to export: the grid is empty".to_string();
+            return;
+        }
+        ctx.copy_text(self.pattern_format.write(&pattern, self.universe.rule()));
+        self.status = format!(
+            "Copied {}x{} pattern to clipboard as {}",
+            pattern.width, pattern.height, self.pattern_format.name()
+        );
+    }
+
+    /// A universe drawn over the area at `scale` pixels per cell, as the grid shows it.
+    fn picture(&self, universe: &Universe, area: PictureArea, scale: usize) -> egui::ColorImage {
+        draw_picture(&self.theme, universe, area, scale, (self.color_by_age, self.age_span, self.trail_length))
+    }
+
+    /// Saves a picture of the whole universe, not just the view, as a PNG file, or downloads it
+    /// on the web.
+    fn save_image(&mut self) {
+        let picture = picture_area([&self.universe]).ok_or_else(|| "the universe is empty".to_string()).and_then(|area| {
+            let image = self.picture(&self.universe, area, fit_scale(self.image_scale, area)?);
+            Ok((screenshot::encode(&image)?, image.size))
+        });
+        let (png, [width, height]) = match picture {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                self.status = format!("Failed to make an image: {e}");
+                return;
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let (saved, name) = (std::fs::write(&self.image_path, png).map_err(|e| e.to_string()), &self.image_path);
+        #[cfg(target_arch = "wasm32")]
+        let (saved, name) = (screenshot::download("universe.png", "image/png", &png), "universe.png");
+        self.status = match saved {
+            Ok(()) => format!("Saved a {width}x{height} image to {name}"),
+            Err(e) => format!("Failed to save {name}: {e}"),
+        };
+    }
+
+    /// Saves the whole universe as an SVG, next to the image file with the extension changed,
+    /// or downloads it on the web.
+    fn save_svg(&mut self) {
+        let Some(area) = picture_area([&self.universe]) else {
+            self.status = "Failed to make an image: the universe is empty".to_string();
+            return;
+        };
+        let style = (self.color_by_age, self.age_span, self.trail_length);
+        let cells = drawn_cells(&self.universe).into_iter().map(|(x, y)| ((x, y), cell_color(&self.theme, &self.universe, x, y, style)));
+        let svg = svg::write(cells, area, self.image_scale, self.theme.dead);
+        #[cfg(not(target_arch = "wasm32"))]
+        let (saved, name) = {
+            let path = std::path::Path::new(&self.image_path).with_extension("svg");
+            (std::fs::write(&path, svg).map_err(|e| e.to_string()), path.display().to_string())
+        };
+        #[cfg(target_arch = "wasm32")]
+        let (saved, name) = (screenshot::download("universe.svg", "image/svg+xml", svg.as_bytes()), "universe.svg");
+        self.status = match saved {
+            Ok(()) => format!("Saved {}x{} cells as SVG to {name}", area.2, area.3),
+            Err(e) => format!("Failed to save {name}: {e}"),
+        };
+    }
+
+    /// Saves the population samples as CSV to `STATS_FILE`, or downloads them on the web. Long
+    /// runs have been thinned out to `MAX_CHART_SAMPLES`.
+    fn save_stats(&mut self) {
+        let csv = self.population_log.to_csv();
+        #[cfg(not(target_arch = "wasm32"))]
+        let saved = std::fs::write(STATS_FILE, csv).map_err(|e| e.to_string());
+        #[cfg(target_arch = "wasm32")]
+        let saved = screenshot::download(STATS_FILE, "text/csv", csv.as_bytes());
+        self.status = match saved {
+            Ok(()) => format!("Saved {} generations of stats to {STATS_FILE}", self.population_log.samples().len()),
+            Err(e) => format!("Failed to save {STATS_FILE}: {e}"),
+        };
+    }
+
+    /// Adds the universe as it now is to the animation being recorded, and saves the animation
+    /// once it covers `animation_length` generations or has `MAX_ANIMATION_FRAMES` frames.
+    fn capture_frame(&mut self) {
+        profile_scope!();
+        let Some(frames) = &mut self.animation else { return };
+        frames.push(self.universe.clone());
+        let recorded = self.universe.generation().saturating_sub(frames[0].generation());
+        if recorded >= self.animation_length || frames.len() >= MAX_ANIMATION_FRAMES {
+            self.save_animation();
+        }
+    }
+
+    /// Stops recording the animation and saves it to a file, or downloads it on the web. Every
+    /// frame shows the same area, enough for all of them.
+    fn save_animation(&mut self) {
+        let Some(frames) = self.animation.take() else { return };
+        #[cfg(not(target_arch = "wasm32"))]
+        let format = AnimationFormat::from_path(&self.animation_path).unwrap_or(self.animation_format);
+        #[cfg(target_arch = "wasm32")]
+        let format = self.animation_format;
+        #[cfg(not(target_arch = "wasm32"))]
+        let name = self.animation_path.clone();
+        #[cfg(target_arch = "wasm32")]
+        let name = format!("animation.{}", format.extension());
+        let saved = picture_area(&frames).ok_or_else(|| "the universe stayed empty".to_string()).and_then(|area| {
+            let scale = fit_scale(self.image_scale, area)?;
+            let pictures = frames.iter().map(|universe| self.picture(universe, area, scale));
+            self.write_animation(&name, format, [area.2 * scale, area.3 * scale], frames.len(), pictures)
+        });
+        self.status = match saved {
+            Ok(()) => format!("Saved {} frames to {} as {}", frames.len(), name, format.name()),
+            Err(e) => format!("Failed to save {name}: {e}"),
+        };
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_animation(&self, path: &str, format: AnimationFormat, size: [usize; 2], count: usize, frames: impl Iterator<Item = egui::ColorImage>) -> Result<(), String> {
+        format.save(path, size, count, frames, self.animation_delay)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write_animation(&self, name: &str, format: AnimationFormat, size: [usize; 2], count: usize, frames: impl Iterator<Item = egui::ColorImage>) -> Result<(), String> {
+        screenshot::download(name, format.mime_type(), &format.encode(size, count, frames, self.animation_delay)?)
+    }
+
+    /// Copies the pattern, cropped to its live cells, as rows of `.` and `O` to share as text.
+    fn copy_pattern_art(&mut self, ctx: &egui::Context) {
+        let pattern = self.current_pattern();
+        if pattern.is_empty() {
+            self.status = "Nothing to copy: the grid is empty".to_string();
+            return;
+        }
+        ctx.copy_text(formats::plaintext::write_art(&pattern));
+        self.status = format!("Copied {}x{} pattern to clipboard as text; Ctrl+V pastes text like it back", pattern.width, pattern.height);
+    }
+
+    /// The pattern browser: the catalog as thumbnails, narrowed by the search box. Clicking one
+    /// makes it the stamp, for the next click on the grid to place.
+    fn draw_browser(&mut self, ctx: &egui::Context) {
+        if !self.show_browser {
+            return;
+        }
+        let mut open = self.show_browser;
+        let (mut picked, mut removed) = (None, None);
+        egui::Window::new("Patterns").open(&mut open).default_size([420.0, 480.0]).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Search:");
+                ui.text_edit_singleline(&mut self.browser_query).on_hover_text("Words in the name, author or category");
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.save_name).hint_text("Name").desired_width(120.0));
+                ui.add(egui::TextEdit::singleline(&mut self.save_author).hint_text("Author").desired_width(100.0));
+                let save = ui.add_enabled(!self.save_name.trim().is_empty(), egui::Button::new("Save"));
+                if save.on_hover_text("Add the selection, or the whole pattern if nothing is selected, to your patterns").clicked() {
+                    self.save_to_catalog();
+                }
+            });
+            ui.separator();
+            let matching: Vec<usize> = (0..self.catalog.entries().len()).filter(|&i| self.catalog.entries()[i].matches(&self.browser_query)).collect();
+            if matching.is_empty() {
+                ui.label("No patterns match");
+            }
+            let size = Vec2::splat(catalog::THUMBNAIL_SIZE as f32);
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for i in matching {
+                        let texture = self.catalog.thumbnail(ui.ctx(), i, self.theme.alive, self.theme.dead);
+                        let entry = &self.catalog.entries()[i];
+                        ui.vertical(|ui| {
+                            ui.set_width(size.x + 16.0);
+                            let thumbnail = ui.add(egui::ImageButton::new(egui::load::SizedTexture::new(texture.id(), size)));
+                            let mut details = entry.category.clone();
+                            if !entry.author.is_empty() {
+                                details.push_str(&format!("\nby {}", entry.author));
+                            }
+                            if thumbnail.on_hover_text(details).clicked() {
+                                picked = Some(i);
+                            }
+                            ui.add(egui::Label::new(&entry.name).truncate(true));
+                            if entry.category == catalog::SAVED && ui.small_button("Delete").clicked() {
+                                removed = Some(i);
+                            }
+                        });
+                    }
+                });
+            });
+        });
+        self.show_browser = open;
+
+        if let Some(i) = picked {
+            let entry = &self.catalog.entries()[i];
+            match entry.pattern() {
+                Ok(pattern) => {
+                    self.status = format!("Click the grid to place {}", entry.name);
+                    self.stamp = Some((entry.name.clone(), pattern));
+                    (self.stamp_rotation, self.stamp_flipped) = (0, false);
+                    self.paste = None;
+                }
+                Err(e) => self.status = format!("Failed to read {}: {e}", entry.name),
+            }
+        }
+        if let Some(i) = removed {
+            self.status = format!("Deleted {}", self.catalog.entries()[i].name);
+            self.catalog.remove(i);
+        }
+    }
+
+    /// Adds the selection, or the whole pattern, to the catalog under the name typed for it.
+    fn save_to_catalog(&mut self) {
+        let pattern = match self.selection {
+            Some(selection) => self.universe.copy_region(selection),
+            None => self.current_pattern(),
+        };
+        if pattern.is_empty() {
+            self.status = "Nothing to save: there are no live cells".to_string();
+            return;
+        }
+        let entry = catalog::Entry::saved(&self.save_name, &self.save_author, &pattern, self.universe.rule());
+        self.status = format!("Saved {} to your patterns", entry.name);
+        self.catalog.save(entry);
+        self.save_name.clear();
+    }
+
+    /// Loads a pattern from a shared link, or from the library by name, ignoring case, spaces
+    /// and punctuation, or else starts downloading it as the "Load from URL" dialog would.
+    fn load_named_pattern(&mut self, text: &str, ctx: &egui::Context) {
+        if let Some(shared) = permalink::decode(text) {
+            match shared {
+                Ok((pattern, rule)) => self.load_shared(pattern, rule),
+                Err(e) => self.status = e,
+            }
+            return;
+        }
+        let key = |name: &str| name.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect::<String>();
+        match LIBRARY.iter().find(|entry| key(entry.name) == key(text)) {
+            Some(entry) => {
+                self.status = match self.load_pattern(entry.pattern()) {
+                    Ok(()) => format!("Loaded {}", entry.name),
+                    Err(e) => e,
+                };
+            }
+            None => self.download = Some(Download::start(fetch::pattern_url(text), ctx.clone())),
+        }
+    }
+
+    /// Puts a shared board on the grid under the rule it was shared with.
+    fn load_shared(&mut self, pattern: Pattern, rule: Rule) {
+        self.rule_text = rule.to_string();
+        self.apply_rule_text();
+        if let Err(e) = self.load_pattern(pattern) {
+            self.status = e;
+            return;
+        }
+        self.status = match &self.rule_error {
+            Some(e) => format!("Loaded the shared pattern, but can't run {rule}: {e}"),
+            None => format!("Loaded the shared pattern under {rule}"),
+        };
+    }
+
+    /// Copies a link with the whole pattern and the rule in its fragment. On the web it is a
+    /// link to this page, which also shows it in the address bar; elsewhere there is no page to
+    /// link to, so only the fragment is copied, for the web app's address or Load from URL.
+    fn share(&mut self, ctx: &egui::Context) {
+        let pattern = self.current_pattern();
+        if pattern.is_empty() {
+            self.status = "Nothing to share: the grid is empty".to_string();
+            return;
+        }
+        let fragment = permalink::encode(&pattern, self.universe.rule());
+        #[cfg(target_arch = "wasm32")]
+        let link = match web_sys::window().map(|window| window.location()) {
+            Some(location) => {
+                let _ = location.set_hash(&fragment);
+                location.href().unwrap_or(fragment)
+            }
+            None => fragment,
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let link = fragment;
+        self.status = format!("Copied a link to the {}x{} pattern, {} characters long", pattern.width, pattern.height, link.len());
+        ctx.copy_text(link);
+    }
+
+    /// The "Load from URL" dialog, which places the pattern on the grid once it has downloaded.
+    fn draw_url_dialog(&mut self, ctx: &egui::Context) {
+        if let Some(result) = self.download.as_ref().and_then(|download| download.poll()) {
+            let url = self.download.take().map(|download| download.url).unwrap_or_default();
+            let play = std::mem::take(&mut self.play_on_load);
+            let loaded = result.and_then(|text| {
+                let format = Format::from_path(&url).unwrap_or_else(|| Format::detect(&text));
+                self.load_text(format, &text)
+            });
+            match loaded {
+                Ok(()) => {
+                    self.status = format!("{url}: {}", self.status);
+                    self.show_url_dialog = false;
+                    self.is_playing = play;
+                }
+                Err(e) => self.status = format!("Failed to load {url}: {e}"),
+            }
+        }
+        if !self.show_url_dialog {
+            return;
+        }
+        let mut open = self.show_url_dialog;
+        egui::Window::new("Load from URL").open(&mut open).resizable(false).show(ctx, |ui| {
+            ui.label("The URL of an RLE file, a shared link, or the name of a LifeWiki pattern such as \"Gosper glider gun\"");
+            let edit = ui.add(egui::TextEdit::singleline(&mut self.pattern_url).desired_width(320.0));
+            let entered = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.horizontal(|ui| {
+                let ready = self.download.is_none() && !self.pattern_url.trim().is_empty();
+                if ui.add_enabled(ready, egui::Button::new("Load")).clicked() || (entered && ready) {
+                    self.load_named_pattern(&self.pattern_url.clone(), ui.ctx());
+                }
+                if let Some(download) = &self.download {
+                    ui.spinner();
+                    ui.label(format!("Downloading {}", download.url));
+                }
+            });
+        });
+        self.show_url_dialog = open;
+    }
+
+    /// Copies the apgcode of the selected cells, or of every live cell, taken as one object.
+    fn copy_apgcode(&mut self, ctx: &egui::Context) {
+        let cells: Vec<(i64, i64)> = match self.selection {
+            Some(selection) => {
+                let pattern = self.universe.copy_region(selection);
+                pattern.cells.iter().map(|&(x, y)| (x as i64, y as i64)).collect()
+            }
+            None => self.universe.live_cells().collect(),
+        };
+        let rule = self.universe.rule();
+        let object = match (self.universe.automaton(), self.universe.ants()) {
+            (None, None) => soup::check_rule(rule).map(|()| Object::classify(&cells, rule)),
+            _ => Err("apgcodes are only for rules, not automata or ants".to_string()),
+        };
+        self.status = match object.map(|object| object.apgcode()) {
+            Ok(Some(apgcode)) => {
+                ctx.copy_text(apgcode.clone());
+                format!("Copied {apgcode}, at {}", soup::catagolue_url(&apgcode, rule))
+            }
+            Ok(None) => "No apgcode: the pattern doesn't repeat within 64 generations".to_string(),
+            Err(e) => e,
+        };
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_pattern(&mut self) {
+        // The file extension wins over the format picker, so "glider.cells" is always plaintext
+        let format = Format::from_path(&self.pattern_path).unwrap_or(self.pattern_format);
+        let pattern = self.current_pattern();
+        self.status = match std::fs::write(&self.pattern_path, format.write(&pattern, self.universe.rule())) {
+            Ok(()) => format!("Saved pattern to {} as {}", self.pattern_path, format.name()),
+            Err(e) => format!("Failed to save {}: {}", self.pattern_path, e),
+        };
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_pattern(&mut self) {
+        let text = match std::fs::read_to_string(&self.pattern_path) {
+            Ok(text) => text,
+            Err(e) => {
+                self.status = format!("Failed to open {}: {}", self.pattern_path, e);
+                return;
+            }
+        };
+        let format = Format::from_path(&self.pattern_path).unwrap_or_else(|| Format::detect(&text));
+        if let Err(e) = self.load_text(format, &text) {
+            self.status = format!("Failed to read {}: {}", self.pattern_path, e);
+        }
+    }
+
+    /// The universe and the settings saved with it.
+    #[cfg(feature = "serde")]
+    fn session(&self) -> Session {
+        Session {
+            universe: self.universe.clone(),
+            engine: self.universe.engine().name().to_string(),
+            update_frequency: self.update_frequency,
+            warp: self.warp,
+            warp_generations: self.warp_generations,
+            warp_unlimited: self.warp_unlimited,
+            step_exponent: self.step_exponent,
+            camera_center: self.camera.center,
+            cell_size: self.camera.cell_size,
+            noise: self.noise,
+            color_by_age: self.color_by_age,
+            age_span: self.age_span,
+            trail_length: self.trail_length,
+        }
+    }
+
+    /// Replaces the universe and the settings saved with it by those of a session. The rule
+    /// file, ants and 3D mode are left behind, as sessions don't keep them. Fails, with
+    /// everything else applied, if the session's engine can't be used.
+    #[cfg(feature = "serde")]
+    fn apply_session(&mut self, session: Session) -> Result<(), String> {
+        self.universe = session.universe;
+        let engine = match Engine::ALL.into_iter().find(|engine| engine.name() == session.engine && engine.is_available()) {
+            Some(engine) => self.universe.set_engine(engine),
+            None => Err(format!("{} isn't available in this build", session.engine)),
+        };
+        self.rule = self.universe.rule();
+        self.rule_text = self.rule.to_string();
+        self.rule_error = None;
+        self.automaton = None;
+        self.ants = false;
+        self.three_d = false;
+        self.draw_state = 1;
+        self.update_frequency = session.update_frequency;
+        self.warp = session.warp;
+        self.warp_generations = session.warp_generations;
+        self.warp_unlimited = session.warp_unlimited;
+        self.step_exponent = session.step_exponent;
+        self.camera = Camera::new(session.camera_center, session.cell_size);
+        self.noise = session.noise;
+        self.color_by_age = session.color_by_age;
+        self.age_span = session.age_span;
+        self.trail_length = session.trail_length;
+        self.is_playing = false;
+        self.run_target = None;
+        self.selection = None;
+        self.grid_texture = None;
+        self.reset_history();
+        engine.map_err(|e| format!("it runs on {} instead: {e}", self.universe.engine().name()))
+    }
+
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    fn save_session(&mut self) {
+        self.status = match self.session().to_text().and_then(|text| std::fs::write(&self.session_path, text).map_err(|e| e.to_string())) {
+            Ok(()) => format!("Saved session to {}", self.session_path),
+            Err(e) => format!("Failed to save {}: {}", self.session_path, e),
+        };
+    }
+
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    fn open_session(&mut self) {
+        let session = match std::fs::read_to_string(&self.session_path).map_err(|e| e.to_string()).and_then(|text| Session::parse(&text)) {
+            Ok(session) => session,
+            Err(e) => {
+                self.status = format!("Failed to open {}: {}", self.session_path, e);
+                return;
+            }
+        };
+        self.record_edit();
+        let applied = self.apply_session(session);
+        self.status = format!("Opened session {} at generation {}", self.session_path, self.universe.generation());
+        if let Err(e) = applied {
+            self.status.push_str(&format!(", but {e}"));
+        }
+    }
+
+    /// Stops recording the replay and writes it to the replay file.
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else { return };
+        let generations = recorder.generations();
+        let replay = recorder.finish();
+        if replay.events.is_empty() {
+            self.status = "Nothing was recorded, as the universe never stepped".to_string();
+            return;
+        }
+        self.status = match replay.to_text().and_then(|text| std::fs::write(&self.replay_path, text).map_err(|e| e.to_string())) {
+            Ok(()) => format!("Saved a replay of {generations} generations to {}", self.replay_path),
+            Err(e) => format!("Failed to save {}: {}", self.replay_path, e),
+        };
+    }
+
+    /// Loads the replay file and plays it back from the universe it starts with.
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    fn play_replay(&mut self) {
+        let replay = match std::fs::read_to_string(&self.replay_path).map_err(|e| e.to_string()).and_then(|text| Replay::parse(&text)) {
+            Ok(replay) => replay,
+            Err(e) => {
+                self.status = format!("Failed to open {}: {}", self.replay_path, e);
+                return;
+            }
+        };
+        self.record_edit();
+        self.recorder = None;
+        self.three_d = false;
+        let mut player = Player::new(replay);
+        if let Some(event) = player.next_due(u64::MAX) {
+            self.apply_replay_event(event);
+        }
+        self.status = format!("Playing back {} to generation {}", self.replay_path, player.end());
+        self.player = Some(player);
+        self.is_playing = true;
+    }
+
+    /// Applies the events of the replay being played back that are due by now, and returns how
+    /// many of `generations` can be stepped before the next one. Playback ends with the replay.
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    fn play_due_events(&mut self, generations: u64) -> u64 {
+        // Events wait for a step in progress, which started before them
+        if self.worker.is_busy() {
+            return generations;
+        }
+        let Some(mut player) = self.player.take() else { return generations };
+        while let Some(event) = player.next_due(self.universe.generation()) {
+            self.apply_replay_event(event);
+        }
+        let generation = self.universe.generation();
+        if player.is_finished(generation) {
+            self.is_playing = false;
+            self.run_target = None;
+            self.status = format!("Replay finished at generation {generation}");
+            return 0;
+        }
+        let left = player.generations_left(generation);
+        self.player = Some(player);
+        generations.min(left)
+    }
+
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    fn apply_replay_event(&mut self, event: Event) {
+        match event {
+            Event::Start(universe) => {
+                self.universe = *universe;
+                self.rule = self.universe.rule();
+                self.rule_text = self.rule.to_string();
+                self.rule_error = None;
+                self.automaton = None;
+                self.ants = false;
+                self.noise = Noise::default();
+                self.grid_texture = None;
+                self.fit_requested = true;
+                self.reset_history();
+            }
+            Event::Rule(rule) => {
+                self.rule = rule;
+                self.rule_text = rule.to_string();
+                let _ = self.universe.set_rule(rule);
+            }
+            Event::Noise(noise) => self.noise = noise,
+            Event::Cells(cells) => {
+                for (x, y, state) in cells {
+                    self.universe.set_state(x, y, state);
+                }
+            }
+        }
+    }
+
+    fn import_pattern_text(&mut self) {
+        let text = std::mem::take(&mut self.pattern_text);
+        if let Err(e) = self.load_text(Format::detect(&text), &text) {
+            self.status = format!("Failed to read pattern: {}", e);
+        }
+        self.pattern_text = text;
+    }
+
+    /// Loads pattern text in a format, under the rule the text names if it names one.
+    fn load_text(&mut self, format: Format, text: &str) -> Result<(), String> {
+        let pattern = format.parse(text)?;
+        let rule = format.rule(text);
+        if let Some(Ok(rule)) = rule {
+            self.rule_text = rule.to_string();
+            self.apply_rule_text();
+        }
+        self.load_pattern(pattern)?;
+        match (rule, &self.rule_error) {
+            (Some(Ok(rule)), None) => self.status.push_str(&format!(" under {rule}")),
+            (Some(Ok(rule)), Some(e)) => self.status.push_str(&format!(", but can't run {rule}: {e}")),
+            (Some(Err(e)), _) => self.status.push_str(&format!(", but not its rule: {e}")),
+            (None, _) => {}
+        }
+        Ok(())
+    }
+
+    /// Replaces the grid contents with the pattern, centered, growing the grid if it doesn't fit.
+    /// Patterns larger than the largest grid go into an unbounded universe instead, which
+    /// fails under rules that bring empty space to life.
+    fn load_pattern(&mut self, pattern: Pattern) -> Result<(), String> {
+        let too_large = pattern.width.max(pattern.height) > MAX_GRID_LENGTH && !self.universe.is_infinite();
+        if too_large && self.universe.births_from_nothing() {
+            return Err(format!(
+                "The {}x{} pattern is larger than the largest grid, {MAX_GRID_LENGTH}x{MAX_GRID_LENGTH}, and the rule brings empty space to life, so it can't go in an unbounded universe",
+                pattern.width, pattern.height
+            ));
+        }
+        self.record_edit();
+        if too_large {
+            self.universe.set_infinite(true);
+        }
+        if self.universe.is_infinite() {
+            self.clear_grid();
+            self.universe.place(&pattern, -(pattern.width as i64 / 2), -(pattern.height as i64 / 2));
+        } else {
+            let width = self.universe.width().max(pattern.width);
+            let height = self.universe.height().max(pattern.height);
+            if (width, height) != (self.universe.width(), self.universe.height()) {
+                self.resize_grid(width, height);
+            }
+            self.clear_grid();
+
+            let left = (width - pattern.width) / 2;
+            let top = (height - pattern.height) / 2;
+            self.universe.place(&pattern, left as i64, top as i64);
+        }
+
+        self.is_playing = false;
+        self.reset_history();
+        self.fit_requested = true;
+        self.status = format!("Loaded {}x{} pattern with {} cells", pattern.width, pattern.height, pattern.cells.len());
+        if too_large {
+            self.status.push_str(" into an unbounded universe, as it's larger than the largest grid");
+        }
+        Ok(())
+    }
+}
+
+impl App for GameOfLifeApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        #[cfg(feature = "profiling")]
+        puffin::GlobalProfiler::lock().new_frame();
+        profile_scope!();
+        self.apply_appearance(ctx, frame.info().system_theme);
+        self.handle_stamp_keys(ctx);
+        self.handle_edit_keys(ctx);
+        self.handle_selection_keys(ctx);
+        self.handle_cursor_keys(ctx);
+        self.handle_shortcut_keys(ctx);
+        self.handle_slot_keys(ctx);
+        self.collect_step();
+        // Ages and trails are only tracked while they are shown
+        let tracking = (self.color_by_age || self.trail_length > 0).then_some(self.trail_length);
+        if self.universe.tracking() != tracking {
+            self.universe.set_tracking(tracking);
+            self.grid_texture = None;
+        }
+        let brush = self.rainbow.then_some([self.rainbow_brush.r(), self.rainbow_brush.g(), self.rainbow_brush.b()]);
+        if self.universe.rainbow_brush() != brush {
+            self.universe.set_rainbow(brush);
+            self.grid_texture = None;
+        }
+
+        egui::SidePanel::right("controls").show(ctx, |ui| {
+            profile_scope!("controls");
+            ui.horizontal(|ui| {
+                ui.heading("Conway's Game of Life");
+                if ui.button("?").on_hover_text("Keyboard shortcuts (F1)").clicked() {
+                    self.show_help = !self.show_help;
+                }
+            });
+
+            // Use vertical layout for sliders
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.show_minimap, "Show minimap");
+                    ui.checkbox(&mut self.show_perf, "Performance").on_hover_text("Frame rate, step and paint times, and memory (F3)");
+                    #[cfg(feature = "profiling")]
+                    ui.checkbox(&mut self.show_profiler, "Profiler").on_hover_text("Flamegraph of the stepping, history and drawing scopes");
+                    if ui.button("Fit").on_hover_text("Zoom to fit all live cells").clicked() {
+                        self.fit_requested = true;
+                    }
+                });
+                self.edit_speed(ui);
+                self.edit_rule(ui);
+                let mut ants = self.ants;
+                let checkbox = ui.checkbox(&mut ants, "Ants");
+                if checkbox.on_hover_text("Ants walk the grid instead of the rule, such as Langton's ant, which turns right on dead cells and left on live ones and flips them. Put them down with the Ant tool").changed() {
+                    self.set_ants(ants);
+                    if ants {
+                        self.tool = Tool::Ant;
+                    }
+                }
+                if self.ants {
+                    self.edit_turmite(ui);
+                }
+                let checkbox = ui.checkbox(&mut self.three_d, "3D");
+                if checkbox.on_hover_text("Life on a cube of voxels, each with the 26 around it as neighbors, shown instead of the grid and played with the same controls").changed() {
+                    self.run_target = None;
+                    self.fit_requested = true;
+                }
+                if self.three_d {
+                    self.edit_space(ui);
+                }
+                self.edit_draw_state(ui);
+                if !self.three_d {
+                    self.edit_noise(ui);
+                }
+                self.edit_engine(ui);
+                self.edit_topology(ui);
+                self.draw_timeline(ui);
+            });
+
+            // Play, Pause, and Clear buttons
+            ui.horizontal(|ui| {
+                if ui.button("Play").clicked() {
+                    self.is_playing = true;
+                }
+                if ui.button("Pause").clicked() {
+                    self.is_playing = false;
+                }
+                if ui.button("Clear").clicked() {
+                    self.record_edit();
+                    self.clear_grid();
+                    self.is_playing = false;
+                }
+            });
+            self.edit_fill(ui);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(self.edits.can_undo(), egui::Button::new("Undo")).on_hover_text("Ctrl+Z").clicked() {
+                    self.undo();
+                }
+                if ui.add_enabled(self.edits.can_redo(), egui::Button::new("Redo")).on_hover_text("Ctrl+Y").clicked() {
+                    self.redo();
+                }
+            });
+            self.edit_stepping(ui);
+            self.draw_stats(ui);
+
+            self.edit_tools(ui);
+
+            self.edit_files(ui);
+            ui.collapsing("Slots", |ui| self.edit_slots(ui));
+            ui.collapsing("Paste pattern", |ui| {
+                ui.add(egui::TextEdit::multiline(&mut self.pattern_text).hint_text("RLE, plaintext, Life 1.06 or macrocell"));
+                if ui.button("Load").clicked() {
+                    self.import_pattern_text();
+                }
+            });
+            ui.collapsing("Colors", |ui| self.edit_colors(ui));
+            if !self.status.is_empty() {
+                ui.label(&self.status);
+            }
+
+            let now = get_current_time();
+            // Step if the game is playing and the update frequency time has passed, or on every frame when warping
+            if self.is_playing && (self.warp || (now - self.last_update) >= self.update_frequency as f64) {
+                self.update_game_state();
+                self.last_update = now; // Reset the timer
+            }
+            self.run_toward_target();
+        });
+
+        self.population_log.record(&self.universe, None);
+        #[cfg(target_arch = "wasm32")]
+        self.emit_events();
+        if self.show_chart {
+            egui::TopBottomPanel::bottom("chart").resizable(true).default_height(160.0).show(ctx, |ui| {
+                self.draw_chart(ui);
+            });
+        }
+
+        let paint_started = get_current_time();
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.three_d {
+                self.draw_space(ui);
+            } else {
+                self.draw_grid(ui);
+            }
+        });
+        self.perf.frame(paint_started, get_current_time() - paint_started);
+        if self.show_perf {
+            self.draw_perf(ctx, frame.info().cpu_usage);
+        }
+        self.draw_help(ctx);
+        self.draw_benchmark(ctx);
+        self.draw_census(ctx);
+        self.draw_url_dialog(ctx);
+        self.draw_browser(ctx);
+        #[cfg(feature = "profiling")]
+        self.draw_profiler(ctx);
+        #[cfg(feature = "serde")]
+        {
+            self.draw_recovery(ctx);
+            self.take_snapshot();
+        }
+
+        // egui repaints by itself on input, so only schedule the next tick while playing or
+        // running to a target. The worker also wakes the UI when a step is done.
+        if (self.is_playing && self.warp) || self.run_target.is_some() {
+            ctx.request_repaint();
+        } else if self.is_playing {
+            let until_tick = self.last_update + self.update_frequency as f64 - get_current_time();
+            ctx.request_repaint_after(Duration::from_secs_f64(until_tick.max(0.0)));
+        }
+    }
+
+    // Lets `WebHandle` reach the app through the web runner
+    #[cfg(target_arch = "wasm32")]
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(&mut *self)
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Theme::STORAGE_KEY, &self.theme);
+        eframe::set_value(storage, Appearance::STORAGE_KEY, &self.appearance);
+        eframe::set_value(storage, Catalog::STORAGE_KEY, &self.catalog.saved());
+        // The recovery snapshot is kept the same as the saved session, so that it only differs
+        // after a crash
+        #[cfg(feature = "serde")]
+        if let Ok(text) = self.session().to_stored() {
+            if self.recovered.is_none() {
+                let _ = recovery::write(&text);
+            }
+            storage.set_string(Session::STORAGE_KEY, text);
+        }
+    }
+}
+
+
+/// World corners of the triangle of the cell at (x, y), which points up or down like
+/// `rule::is_upward` and overlaps its left and right neighbors by half.
+fn triangle(x: i64, y: i64) -> [Pos2; 3] {
+    let (left, top) = (x as f32 / 2.0, y as f32);
+    match rule::is_upward(x, y) {
+        true => [Pos2::new(left + 0.5, top), Pos2::new(left + 1.0, top + 1.0), Pos2::new(left, top + 1.0)],
+        false => [Pos2::new(left, top), Pos2::new(left + 1.0, top), Pos2::new(left + 0.5, top + 1.0)],
+    }
+}
+
+/// Counts the live cells inside `area` in each `block` x `block` square, keyed by the square's
+/// coordinates in blocks.
+fn density_blocks(cells: impl Iterator<Item = (i64, i64)>, block: i64, area: Rect) -> HashMap<(i64, i64), u32> {
+    let mut counts = HashMap::new();
+    for (x, y) in cells {
+        if area.intersects(Rect::from_min_size(Pos2::new(x as f32, y as f32), Vec2::splat(1.0))) {
+            *counts.entry((x.div_euclid(block), y.div_euclid(block))).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Color of the cell at (x, y) given how cells are shaded: by age or not, the age span and the
+/// trail length. Cells with a Rainbow Life color always show that.
+fn cell_color(theme: &Theme, universe: &Universe, x: i64, y: i64, (color_by_age, age_span, trail_length): (bool, u32, u32)) -> Color32 {
+    match universe.state(x, y) {
+        0 => universe.since_death(x, y).map_or(theme.dead, |since| theme.trail_color(since, trail_length)),
+        1 => match universe.rainbow_color(x, y) {
+            Some([r, g, b]) => Color32::from_rgb(r, g, b),
+            None => theme.cell_color(universe.age(x, y).filter(|_| color_by_age), age_span),
+        },
+        state => higher_state_color(theme, universe, state),
+    }
+}
+
+/// Color of a cell in a state above 1: the automaton's color for the state if it has one, one
+/// of the cell colors under a colored rule, otherwise a shade between the old and dead colors.
+fn higher_state_color(theme: &Theme, universe: &Universe, state: u8) -> Color32 {
+    match universe.automaton().filter(|_| universe.ants().is_none()) {
+        Some(automaton) => automaton.color(state).map_or_else(|| theme.decay_color(state, automaton.states()), |[r, g, b]| Color32::from_rgb(r, g, b)),
+        None if universe.ants().is_none() && universe.rule().colors > 1 => live_color(theme, state),
+        None => theme.decay_color(state, universe.states()),
+    }
+}
+
+/// Color of live cells of the given color under a colored rule, the first being the alive color.
+fn live_color(theme: &Theme, color: u8) -> Color32 {
+    match color {
+        0 | 1 => theme.alive,
+        color => CELL_COLORS[(color as usize - 2) % CELL_COLORS.len()],
+    }
+}
+
+/// Where pictures are drawn, as (left, top, width, height) in cells.
+pub(crate) type PictureArea = (i64, i64, usize, usize);
+
+/// The cells of a universe that aren't drawn as plain dead space.
+fn drawn_cells(universe: &Universe) -> Vec<(i64, i64)> {
+    let mut cells: Vec<(i64, i64)> = universe.live_cells().collect();
+    cells.extend(universe.higher_states().map(|(cell, _)| cell));
+    cells.extend(universe.recent_deaths().map(|(cell, _)| cell));
+    cells
+}
+
+/// The area pictures of the universes show: all of the first one's grid if it is finite, or
+/// the box around every cell drawn in any of them if infinite, which is `None` when all are
+/// empty.
+pub(crate) fn picture_area(universes: impl IntoIterator<Item = impl std::borrow::Borrow<Universe>>) -> Option<PictureArea> {
+    let mut universes = universes.into_iter().peekable();
+    let first = universes.peek()?.borrow();
+    if !first.is_infinite() {
+        return Some((0, 0, first.width(), first.height()));
+    }
+    let bounds = universes.flat_map(|universe| drawn_cells(universe.borrow())).fold(None, |bounds, (x, y)| match bounds {
+        None => Some((x, y, x, y)),
+        Some((left, top, right, bottom)) => Some((x.min(left), y.min(top), x.max(right), y.max(bottom))),
+    });
+    let (left, top, right, bottom) = bounds?;
+    Some((left, top, (right - left + 1) as usize, (bottom - top + 1) as usize))
+}
+
+/// Pixels per cell for pictures of the area: `scale`, or fewer if the pictures would be too
+/// large.
+pub(crate) fn fit_scale(scale: usize, (_, _, width, height): PictureArea) -> Result<usize, String> {
+    let fits = (screenshot::MAX_PIXELS as f64 / (width as f64 * height as f64)).sqrt() as usize;
+    match scale.min(fits) {
+        0 => Err(format!("{width}x{height} cells is too large for an image")),
+        scale => Ok(scale),
+    }
+}
+
+/// A universe drawn over the area at `scale` pixels per cell, in the theme's colors with the
+/// given age and trail style.
+pub(crate) fn draw_picture(theme: &Theme, universe: &Universe, (left, top, width, height): PictureArea, scale: usize, style: (bool, u32, u32)) -> egui::ColorImage {
+    let mut image = egui::ColorImage::new([width * scale, height * scale], theme.dead);
+    for (x, y) in drawn_cells(universe) {
+        let (column, row) = (x - left, y - top);
+        if !(0..width as i64).contains(&column) || !(0..height as i64).contains(&row) {
+            continue;
+        }
+        let color = cell_color(theme, universe, x, y, style);
+        for py in row as usize * scale..(row as usize + 1) * scale {
+            image.pixels[py * width * scale + column as usize * scale..][..scale].fill(color);
+        }
+    }
+    image
+}
+
+/// The alive color shaded toward the dead one by the fraction of a block's cells that are alive,
+/// never fully dead so that a lone cell stays visible.
+fn density_color(theme: &Theme, count: u32, block: i64) -> Color32 {
+    let density = count as f32 / (block * block) as f32;
+    theme.blend(0.35 + 0.65 * density.sqrt())
+}
+
+/// The next size for a batch of generations that took `took` seconds, doubled or halved to
+/// bring it closer to one frame.
+fn frame_sized_batch(batch: u64, took: f64) -> u64 {
+    if took < 0.008 {
+        (batch * 2).min(MAX_WARP_GENERATIONS)
+    } else if took > 0.03 {
+        (batch / 2).max(1)
+    } else {
+        batch
+    }
+}
+
+/// One thread per core on native targets; the web build always runs on a single thread.
+fn default_threads() -> usize {
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::thread::available_parallelism().map_or(1, |n| n.get());
+    #[cfg(target_arch = "wasm32")]
+    1
+}
+
+fn edge_combo(ui: &mut egui::Ui, label: &str, edge: &mut Edge) {
+    egui::ComboBox::from_label(label)
+        .selected_text(edge.name())
+        .show_ui(ui, |ui| {
+            for option in Edge::ALL {
+                ui.selectable_value(edge, option, option.name());
+            }
+        });
+}
+
+/// Top-left corner that puts the pattern's center on (x, y).
+fn stamp_origin(pattern: &Pattern, x: i64, y: i64) -> (i64, i64) {
+    (x - (pattern.width / 2) as i64, y - (pattern.height / 2) as i64)
+}
+
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use wasm_bindgen::prelude::*;
+    use eframe::egui;
+    use super::GameOfLifeApp; // Import our app
+    use crate::formats::Format;
+    use crate::permalink;
+    use crate::rule::Rule;
+    use crate::topology::Topology;
+    
+    #[wasm_bindgen]
+    pub struct WebHandle {
+        runner: eframe::WebRunner,
+    }
+
+    #[wasm_bindgen]
+    impl WebHandle {
+        // Installs a panic hook, then returns
+        #[allow(clippy::new_without_default)]
+        #[wasm_bindgen(constructor)]
+        pub fn new() -> Self {
+            // Redirect [`log`] message to `console.log` and friends:
+            eframe::WebLogger::init(log::LevelFilter::Debug).ok();
+
+            Self {
+                runner: eframe::WebRunner::new(),
+            }
+        }
+
+        /// Call this once from JavaScript to start the app. The app's events are dispatched on
+        /// the canvas, and the page's query string sets it up.
+        #[wasm_bindgen]
+        pub async fn start(&self, canvas_id: &str) -> Result<(), wasm_bindgen::JsValue> {
+            let target = canvas_id.to_string();
+            let location = web_sys::window().map(|window| window.location());
+            let query = location.as_ref().and_then(|location| location.search().ok()).unwrap_or_default();
+            let fragment = location.as_ref().and_then(|location| location.hash().ok()).unwrap_or_default();
+            self.runner
+                .start(
+                    canvas_id,
+                    eframe::WebOptions::default(),
+                    Box::new(move |cc| {
+                        let mut app = configure(GameOfLifeApp::new(cc).with_event_target(&target), &query, &cc.egui_ctx);
+                        // A shared board in the fragment takes the place of any pattern and rule in the query
+                        if let Some(shared) = permalink::decode(&fragment) {
+                            match shared {
+                                Ok((pattern, rule)) => app.load_shared(pattern, rule),
+                                Err(e) => app.status = e,
+                            }
+                        }
+                        Box::new(app)
+                    }),
+                )
+                .await?;
+            save_when_hidden(&self.runner)
+        }
+
+        /// Replaces the grid with a pattern given as RLE, or in another format the app reads.
+        pub fn load_rle(&self, text: &str) -> Result<(), JsValue> {
+            self.with_app(|app| {
+                app.load_text(Format::detect(text), text)?;
+                Ok(())
+            })
+        }
+
+        pub fn play(&self) -> Result<(), JsValue> {
+            self.with_app(|app| {
+                app.is_playing = true;
+                Ok(())
+            })
+        }
+
+        pub fn pause(&self) -> Result<(), JsValue> {
+            self.with_app(|app| {
+                app.is_playing = false;
+                Ok(())
+            })
+        }
+
+        /// Advances `generations` generations at once, as one step that can be undone.
+        pub fn step(&self, generations: u32) -> Result<(), JsValue> {
+            self.with_app(|app| {
+                app.advance(generations as u64);
+                Ok(())
+            })
+        }
+
+        /// Switches to a rule, in B/S or another notation the rule box takes.
+        pub fn set_rule(&self, rule: &str) -> Result<(), JsValue> {
+            self.with_app(|app| {
+                app.rule_text = rule.to_string();
+                app.apply_rule_text();
+                app.rule_error.clone().map_or(Ok(()), Err)
+            })
+        }
+
+        /// Plays this many generations a second, within the range of the speed slider.
+        pub fn set_speed(&self, generations_per_second: f32) -> Result<(), JsValue> {
+            self.with_app(|app| {
+                app.update_frequency = (1.0 / generations_per_second.max(f32::MIN_POSITIVE)).clamp(0.1, 2.0);
+                Ok(())
+            })
+        }
+
+        /// Kills every cell and pauses, like the Clear button.
+        pub fn clear(&self) -> Result<(), JsValue> {
+            self.with_app(|app| {
+                app.record_edit();
+                app.clear_grid();
+                app.is_playing = false;
+                Ok(())
+            })
+        }
+
+        /// Runs `change` on the app once it has started, then redraws. Errors come back to
+        /// JavaScript as strings.
+        fn with_app(&self, change: impl FnOnce(&mut GameOfLifeApp) -> Result<(), String>) -> Result<(), JsValue> {
+            let mut app = self.runner.app_mut::<GameOfLifeApp>().ok_or("The app isn't running")?;
+            let result = change(&mut app);
+            app.ctx.request_repaint();
+            result.map_err(JsValue::from)
+        }
+    }
+
+    /// Local storage under the keys eframe keeps the app's state in.
+    struct LocalStorage;
+
+    impl eframe::Storage for LocalStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            web_sys::window()?.local_storage().ok()??.get_item(key).ok()?
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            let storage = web_sys::window().and_then(|window| window.local_storage().ok().flatten());
+            if storage.is_none_or(|storage| storage.set_item(key, &value).is_err()) {
+                log::warn!("Couldn't save {key}: local storage is full or unavailable");
+            }
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    /// Saves the grid, rule and settings to local storage whenever the page is hidden or left,
+    /// so that a reload carries on from there. eframe only saves every 30 seconds, as the
+    /// unload listener it adds never fires.
+    fn save_when_hidden(runner: &eframe::WebRunner) -> Result<(), JsValue> {
+        use wasm_bindgen::JsCast;
+        let window = web_sys::window().ok_or("No window")?;
+        let document = window.document().ok_or("No document")?;
+        let runner = runner.clone();
+        let save = Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
+            let hidden = web_sys::window().and_then(|window| window.document()).is_none_or(|document| document.hidden());
+            if event.type_() == "pagehide" || hidden {
+                if let Some(mut app) = runner.app_mut::<GameOfLifeApp>() {
+                    eframe::App::save(&mut *app, &mut LocalStorage);
+                }
+            }
+        });
+        window.add_event_listener_with_callback("pagehide", save.as_ref().unchecked_ref())?;
+        document.add_event_listener_with_callback("visibilitychange", save.as_ref().unchecked_ref())?;
+        save.forget(); // Kept for as long as the page lives
+        Ok(())
+    }
+
+    /// Sets the app up from a query string such as `?rule=B36/S23&size=128&pattern=glider&play`:
+    /// `size` as a side or `WIDTHxHEIGHT`, `infinite`, `boundary`, `rule`, `speed` in generations
+    /// a second, `pattern` as a library or LifeWiki name or the URL of an RLE file, and `play`.
+    /// Values that can't be used are reported in the status line.
+    fn configure(mut app: GameOfLifeApp, query: &str, ctx: &egui::Context) -> GameOfLifeApp {
+        let Ok(params) = web_sys::UrlSearchParams::new_with_str(query) else { return app };
+        let flag = |name: &str| params.get(name).is_some_and(|value| !matches!(value.as_str(), "0" | "false" | "no"));
+        let mut problems = Vec::new();
+        if let Some(size) = params.get("size") {
+            let parse = |text: &str| text.trim().parse::<usize>().ok().filter(|&length| length > 0);
+            let lengths = match size.split_once(['x', 'X']) {
+                Some((width, height)) => parse(width).zip(parse(height)),
+                None => parse(&size).map(|side| (side, side)),
+            };
+            match lengths {
+                Some((width, height)) => app = app.with_grid_size(width, height),
+                None => problems.push(format!("{size} isn't a size such as 128 or 200x150")),
+            }
+        }
+        if flag("infinite") {
+            app = app.with_infinite();
+        }
+        if let Some(boundary) = params.get("boundary") {
+            match Topology::parse(&boundary) {
+                Ok(topology) => app = app.with_topology(topology),
+                Err(e) => problems.push(e),
+            }
+        }
+        if let Some(rule) = params.get("rule") {
+            match Rule::parse(&rule) {
+                Ok(rule) => app = app.with_rule(rule),
+                Err(e) => problems.push(e),
+            }
+        }
+        if let Some(speed) = params.get("speed") {
+            match speed.parse::<f32>() {
+                Ok(speed) if speed > 0.0 => app = app.with_update_frequency(1.0 / speed),
+                _ => problems.push(format!("{speed} isn't a speed in generations a second")),
+            }
+        }
+        if let Some(pattern) = params.get("pattern") {
+            app.load_named_pattern(&pattern, ctx);
+        }
+        app = app.with_playing(flag("play"));
+        app.play_on_load = app.download.is_some() && flag("play");
+        if !problems.is_empty() {
+            app.status = format!("Ignored part of the link: {}", problems.join("; "));
+        }
+        app
+    }
+
+    /// Dispatches a `CustomEvent` named `name` on the element with id `target`, with the fields
+    /// as its `detail`. Events the page can't receive are dropped.
+    pub fn dispatch<'a>(target: &str, name: &str, fields: impl IntoIterator<Item = (&'a str, JsValue)>) {
+        let Some(element) = web_sys::window().and_then(|window| window.document()).and_then(|document| document.get_element_by_id(target)) else {
+            return;
+        };
+        let detail = js_sys::Object::new();
+        for (key, value) in fields {
+            let _ = js_sys::Reflect::set(&detail, &JsValue::from_str(key), &value);
+        }
+        let init = web_sys::CustomEventInit::new();
+        init.set_detail(&detail);
+        if let Ok(event) = web_sys::CustomEvent::new_with_event_init_dict(name, &init) {
+            let _ = element.dispatch_event(&event);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn get_current_time() -> f64 {
+    web_sys::window().unwrap().performance().unwrap().now() / 1000.0
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_current_time() -> f64 {
+    START_TIME.elapsed().as_secs_f64()
+}
\ No newline at end of file
@@ -0,0 +1,28 @@
+//! Automata that run in place of the rule, such as rule files, and that other crates can define
+//! for themselves: hand one to `Universe::set_automaton` or `GameOfLifeApp::with_automaton`.
+
+/// A cellular automaton on the square grid in which each cell looks at its 8 nearest neighbors.
+/// State 0 is empty space; an infinite universe only visits cells that aren't dead and their
+/// neighbors, so there empty space must stay empty.
+pub trait Automaton: Send + Sync {
+    /// The name shown for the automaton.
+    fn name(&self) -> String;
+
+    /// The number of cell states, dead included.
+    fn states(&self) -> u8;
+
+    /// The next state of a cell in state `center` whose neighbors are in the given states, in
+    /// the order N, NE, E, SE, S, SW, W, NW of `rule::NEIGHBORS`. Called once per distinct
+    /// neighborhood in a generation, so it needn't be fast.
+    fn next(&self, center: u8, neighbors: [u8; 8]) -> u8;
+
+    /// The color of a state, or `None` for the theme's.
+    fn color(&self, _state: u8) -> Option<[u8; 3]> {
+        None
+    }
+
+    /// Whether empty space comes to life, which an unbounded universe can't hold.
+    fn births_from_nothing(&self) -> bool {
+        self.next(0, [0; 8]) != 0
+    }
+}
@@ -0,0 +1,234 @@
+//! A standard workload run on each engine in turn, timing how many generations a second each
+//! manages: `game_of_life bench`, or the Benchmark window in the app. The workload runs a slice
+//! at a time so that the app keeps drawing in between.
+
+#[cfg(not(target_arch = "wasm32"))]
+use clap::Args;
+
+use crate::rng::Rng;
+use crate::universe::{Engine, Universe};
+
+/// Times every engine on a random soup.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Soup size in cells
+    #[arg(long, value_name = "WIDTHxHEIGHT", default_value = "256x256", value_parser = crate::cli::parse_size)]
+    size: (usize, usize),
+    /// Generations each engine runs
+    #[arg(long, value_name = "N", default_value_t = Workload::STANDARD.generations)]
+    generations: u64,
+    /// Seed for the soup
+    #[arg(long, default_value_t = Workload::STANDARD.seed)]
+    seed: u64,
+    /// Threads for the parallel runs of the bit-packed engines; one per core if not given
+    #[arg(long)]
+    threads: Option<usize>,
+}
+
+/// Runs the benchmark as the `bench` subcommand asks, printing each engine's result as it
+/// finishes.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn bench(args: BenchArgs) -> Result<String, String> {
+    let BenchArgs { size: (width, height), generations, seed, threads } = args;
+    let threads = threads.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let workload = Workload { width, height, generations, seed, ..Workload::STANDARD };
+    let mut benchmark = Benchmark::new(workload, Backend::available(threads));
+    let start = std::time::Instant::now();
+    let mut shown = 0;
+    while !benchmark.run_for(0.5, || start.elapsed().as_secs_f64()) || shown < benchmark.results().len() {
+        for result in &benchmark.results()[shown..] {
+            match &result.timing {
+                Ok(timing) => eprintln!("{}: {:.1} gen/s", result.backend.name(), timing.generations_per_second()),
+                Err(e) => eprintln!("{}: {e}", result.backend.name()),
+            }
+        }
+        shown = benchmark.results().len();
+    }
+    print!("{}", benchmark.report());
+    Ok(format!("Benchmarked {} engines", benchmark.results().len()))
+}
+
+/// A random soup on a plane with dead edges, run for some generations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Workload {
+    pub width: usize,
+    pub height: usize,
+    pub generations: u64,
+    pub density: f64,
+    pub seed: u64,
+}
+
+impl Workload {
+    pub const STANDARD: Workload = Workload { width: 256, height: 256, generations: 10_000, density: 0.5, seed: 1 };
+
+    /// The soup the workload starts from.
+    pub fn soup(&self) -> Universe {
+        let mut universe = Universe::new(self.width, self.height);
+        let mut rng = Rng::new(self.seed);
+        for y in 0..self.height as i64 {
+            for x in 0..self.width as i64 {
+                if rng.chance(self.density) {
+                    universe.set(x, y, true);
+                }
+            }
+        }
+        universe
+    }
+}
+
+/// An engine on a number of threads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Backend {
+    pub engine: Engine,
+    pub threads: usize,
+}
+
+impl Backend {
+    pub fn name(self) -> String {
+        match self.threads {
+            1 => self.engine.name().to_string(),
+            threads => format!("{} on {threads} threads", self.engine.name()),
+        }
+    }
+
+    /// Every engine that runs in this build, and the bit-packed ones again on `threads` threads
+    /// when there are more than one.
+    pub fn available(threads: usize) -> Vec<Backend> {
+        let mut backends = Vec::new();
+        for engine in Engine::ALL.into_iter().filter(|engine| engine.is_available()) {
+            backends.push(Backend { engine, threads: 1 });
+            if threads > 1 && matches!(engine, Engine::BitPacked | Engine::Simd) {
+                backends.push(Backend { engine, threads });
+            }
+        }
+        backends
+    }
+
+    /// The workload's soup set up for this backend. HashLife only runs unbounded universes, so
+    /// its soup has no edges and ends up with a different population from the rest.
+    fn universe(self, workload: &Workload) -> Result<Universe, String> {
+        let mut universe = workload.soup();
+        universe.set_engine(self.engine)?;
+        universe.set_threads(self.threads);
+        Ok(universe)
+    }
+}
+
+/// How a backend did on the workload, or why it couldn't run it.
+#[derive(Clone, Debug)]
+pub struct Measurement {
+    pub backend: Backend,
+    pub timing: Result<Timing, String>,
+}
+
+/// How long a backend took over the workload.
+#[derive(Clone, Copy, Debug)]
+pub struct Timing {
+    pub generations: u64,
+    pub seconds: f64,
+    pub population: usize, // at the end, which the finite engines should all agree on
+}
+
+impl Timing {
+    pub fn generations_per_second(&self) -> f64 {
+        self.generations as f64 / self.seconds.max(1e-9)
+    }
+}
+
+/// The workload part way through the backends.
+pub struct Benchmark {
+    workload: Workload,
+    pending: Vec<Backend>, // still to run, the next one last
+    running: Option<Running>,
+    results: Vec<Measurement>,
+}
+
+struct Running {
+    backend: Backend,
+    universe: Universe,
+    seconds: f64, // spent advancing so far, not counting setup
+    batch: u64,   // generations to advance between looks at the clock
+}
+
+impl Benchmark {
+    pub fn new(workload: Workload, backends: Vec<Backend>) -> Self {
+        let mut pending = backends;
+        pending.reverse();
+        Self { workload, pending, running: None, results: Vec::new() }
+    }
+
+    pub fn workload(&self) -> &Workload {
+        &self.workload
+    }
+
+    pub fn results(&self) -> &[Measurement] {
+        &self.results
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.running.is_none() && self.pending.is_empty()
+    }
+
+    /// The backend being run and the fraction of the workload it got through.
+    pub fn progress(&self) -> Option<(Backend, f64)> {
+        let running = self.running.as_ref()?;
+        Some((running.backend, running.universe.generation() as f64 / self.workload.generations.max(1) as f64))
+    }
+
+    /// Runs for about `seconds` by `clock`, which counts seconds from any start. Generations go
+    /// in batches that double while they are quick, so looking at the clock costs little.
+    /// Returns whether every backend has finished.
+    pub fn run_for(&mut self, seconds: f64, clock: impl Fn() -> f64) -> bool {
+        let until = clock() + seconds;
+        while clock() < until {
+            let Some(running) = &mut self.running else {
+                let Some(backend) = self.pending.pop() else { return true };
+                let universe = match backend.universe(&self.workload) {
+                    Ok(universe) => universe,
+                    Err(e) => {
+                        self.results.push(Measurement { backend, timing: Err(e) });
+                        continue;
+                    }
+                };
+                self.running = Some(Running { backend, universe, seconds: 0.0, batch: 1 });
+                continue;
+            };
+            let left = self.workload.generations - running.universe.generation();
+            let batch = running.batch.min(left);
+            let started = clock();
+            running.universe.advance(batch);
+            let took = clock() - started;
+            running.seconds += took;
+            if took < seconds / 8.0 {
+                running.batch *= 2;
+            }
+            if batch == left {
+                let timing = Timing { generations: self.workload.generations, seconds: running.seconds, population: running.universe.population() };
+                self.results.push(Measurement { backend: running.backend, timing: Ok(timing) });
+                self.running = None;
+            }
+        }
+        self.is_done()
+    }
+
+    /// The results as a plain-text table.
+    pub fn report(&self) -> String {
+        let workload = &self.workload;
+        let mut report = format!("{}x{} soup at density {}, {} generations\n", workload.width, workload.height, workload.density, workload.generations);
+        let width = self.results.iter().map(|result| result.backend.name().len()).max().unwrap_or(0);
+        for result in &self.results {
+            let name = result.backend.name();
+            match &result.timing {
+                Ok(timing) => report.push_str(&format!(
+                    "{name:width$}  {:>12.1} gen/s  {:>8.3} s  population {}\n",
+                    timing.generations_per_second(),
+                    timing.seconds,
+                    timing.population
+                )),
+                Err(e) => report.push_str(&format!("{name:width$}  {e}\n")),
+            }
+        }
+        report
+    }
+}
@@ -0,0 +1,419 @@
+//! Dense cell storage packed 64 cells to a word, with a word-parallel stepping routine.
+
+use crate::rule::{self, LargerThanLife, Margolus, Neighborhood, Rule, Shape};
+use crate::simd;
+use crate::stats::Changes;
+use crate::topology::{Edge, Topology};
+
+/// A `width` x `height` grid stored row by row, one bit per cell: cell (x, y) is bit `x % 64`
+/// of word `x / 64` of row `y`.
+#[derive(Clone, PartialEq, Hash)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(64);
+        Self {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.words[y * self.words_per_row + x / 64] & (1 << (x % 64)) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        let word = &mut self.words[y * self.words_per_row + x / 64];
+        if alive {
+            *word |= 1 << (x % 64);
+        } else {
+            *word &= !(1 << (x % 64));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn population(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Bytes used by the cells.
+    pub fn memory_size(&self) -> usize {
+        self.words.len() * std::mem::size_of::<u64>()
+    }
+
+    pub fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// The words of a row. Bits past the right edge must be left clear.
+    pub fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// Iterates over the live cells in row-major order, skipping empty words.
+    pub fn live_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height).flat_map(move |y| {
+            self.row(y).iter().enumerate().filter(|(_, &w)| w != 0).flat_map(move |(k, &word)| {
+                (0..64).filter(move |bit| word & (1 << bit) != 0).map(move |bit| (k * 64 + bit, y))
+            })
+        })
+    }
+
+    /// Rectangles `[x, y, width, height]` covering every cell that differs from `other`, a grid
+    /// of the same size: for each band of `band_rows` rows with changes, the smallest rectangle
+    /// around them.
+    pub fn changed_regions(&self, other: &BitGrid, band_rows: usize) -> Vec<[usize; 4]> {
+        let mut regions = Vec::new();
+        for first_row in (0..self.height).step_by(band_rows.max(1)) {
+            let (mut min_x, mut max_x, mut min_y, mut max_y) = (usize::MAX, 0, usize::MAX, 0);
+            for y in first_row..(first_row + band_rows).min(self.height) {
+                for (k, (a, b)) in self.row(y).iter().zip(other.row(y)).enumerate() {
+                    let changed = a ^ b;
+                    if changed != 0 {
+                        min_x = min_x.min(k * 64 + changed.trailing_zeros() as usize);
+                        max_x = max_x.max(k * 64 + 63 - changed.leading_zeros() as usize);
+                        min_y = min_y.min(y);
+                        max_y = y;
+                    }
+                }
+            }
+            if min_y <= max_y {
+                regions.push([min_x, min_y, max_x - min_x + 1, max_y - min_y + 1]);
+            }
+        }
+        regions
+    }
+
+    /// Counts the cells alive here but not in `other`, a grid of the same size, and the other
+    /// way around.
+    pub fn changes_since(&self, other: &BitGrid) -> Changes {
+        let mut changes = Changes::default();
+        for (a, b) in self.words.iter().zip(&other.words) {
+            changes.births += (a & !b).count_ones() as usize;
+            changes.deaths += (b & !a).count_ones() as usize;
+        }
+        changes
+    }
+
+    /// Computes the next generation with every word's 64 cells updated at once, splitting the
+    /// rows into `threads` bands that are stepped in parallel (on native targets).
+    pub fn step(&self, topology: Topology, rule: Rule, threads: usize) -> BitGrid {
+        self.step_bands(threads, |first_row, out| self.step_band(topology, rule, first_row, out))
+    }
+
+    /// Same result as `step`, but lines up each row's neighbor words into arrays first so a
+    /// SIMD kernel can process several words per instruction.
+    pub fn step_simd(&self, topology: Topology, rule: Rule, threads: usize) -> BitGrid {
+        self.step_bands(threads, |first_row, out| self.step_band_simd(topology, rule, first_row, out))
+    }
+
+    /// The next generation under a Larger than Life rule.
+    pub fn step_range(&self, topology: Topology, range: LargerThanLife, threads: usize) -> BitGrid {
+        self.step_counts(topology, range.neighborhood, threads, |alive, count| {
+            let count = count - (alive && !range.middle) as u32;
+            let (min, max) = if alive { range.survival } else { range.birth };
+            (min..=max).contains(&count)
+        })
+    }
+
+    /// The next generation under a B/S rule with a neighborhood other than the 8 adjacent
+    /// cells, which the bit-sliced counts of `step` can't cover.
+    pub fn step_wide(&self, topology: Topology, rule: Rule, threads: usize) -> BitGrid {
+        self.step_counts(topology, rule.neighborhood, threads, |alive, count| rule.next_count(alive, count - alive as u32))
+    }
+
+    /// The next generation under a Margolus rule, with the blocks starting `offset` cells from
+    /// the top left corner. Blocks that stick out past the edges see the cells the topology puts
+    /// there, but only change those inside the grid.
+    pub fn step_blocks(&self, topology: Topology, blocks: Margolus, offset: i64) -> BitGrid {
+        let mut next = BitGrid::new(self.width, self.height);
+        let (width, height) = (self.width as i64, self.height as i64);
+        for top in (-offset..height).step_by(2) {
+            for left in (-offset..width).step_by(2) {
+                let cells = Margolus::CELLS.map(|(dx, dy)| (left + dx, top + dy));
+                let block = cells.iter().enumerate().fold(0, |block, (bit, &(x, y))| {
+                    let alive = topology.resolve(x as isize, y as isize, self.width, self.height).is_some_and(|(x, y)| self.get(x, y));
+                    block | (alive as usize) << bit
+                });
+                let block = blocks.table[block];
+                for (bit, &(x, y)) in cells.iter().enumerate() {
+                    if (0..width).contains(&x) && (0..height).contains(&y) && block & (1 << bit) != 0 {
+                        next.set(x as usize, y as usize, true);
+                    }
+                }
+            }
+        }
+        next
+    }
+
+    /// The space-time diagram of an elementary rule one generation on: the row below the one for
+    /// `generation`, or once that was the bottom row, the grid moved up a row to make room for
+    /// the new one. Only the horizontal edges of the topology matter, with twisted ones wrapping.
+    pub fn step_elementary(&self, topology: Topology, code: u8, generation: u64) -> BitGrid {
+        if self.height == 0 {
+            return self.clone();
+        }
+        let row = (generation as usize).min(self.height - 1);
+        let horizontal = match topology.horizontal {
+            Edge::Twist => Edge::Wrap,
+            edge => edge,
+        };
+        let edges = Topology { horizontal, vertical: Edge::Dead };
+        let alive = |x: isize| edges.resolve(x, row as isize, self.width, self.height).is_some_and(|(x, y)| self.get(x, y));
+        let cells: Vec<bool> = (0..self.width as isize)
+            .map(|x| code >> ((alive(x - 1) as u8) << 2 | (alive(x) as u8) << 1 | alive(x + 1) as u8) & 1 != 0)
+            .collect();
+
+        let mut next = self.clone();
+        let target = row + 1;
+        if target == self.height {
+            next.words.copy_within(self.words_per_row.., 0);
+        }
+        let target = target.min(self.height - 1);
+        for (x, alive) in cells.into_iter().enumerate() {
+            next.set(x, target, alive);
+        }
+        next
+    }
+
+    /// The next generation with each cell's state given by `next(alive, count)`, where the
+    /// count of live cells in its neighborhood includes the cell itself. Each count takes a few
+    /// lookups in a summed-area table per row of the neighborhood, however wide it is.
+    fn step_counts(
+        &self,
+        topology: Topology,
+        neighborhood: Neighborhood,
+        threads: usize,
+        next: impl Fn(bool, u32) -> bool + Sync,
+    ) -> BitGrid {
+        let sums = SummedArea::new(self, topology, neighborhood.reach() as usize);
+        self.step_bands(threads, |first_row, out| {
+            let rows = out.len() / self.words_per_row;
+            for y in first_row..first_row + rows {
+                let row = &mut out[(y - first_row) * self.words_per_row..][..self.words_per_row];
+                for x in 0..self.width {
+                    if next(self.get(x, y), sums.neighbors(x, y, neighborhood)) {
+                        row[x / 64] |= 1 << (x % 64);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Creates the next grid and fills it band by band with `step_band(first_row, words)`.
+    fn step_bands(&self, threads: usize, step_band: impl Fn(usize, &mut [u64]) + Sync) -> BitGrid {
+        profile_scope!();
+        let mut next = BitGrid::new(self.width, self.height);
+        if self.width == 0 || self.height == 0 {
+            return next;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if threads > 1 {
+            use rayon::prelude::*;
+            let band_rows = self.height.div_ceil(threads);
+            thread_pool(threads).install(|| {
+                next.words
+                    .par_chunks_mut(band_rows * self.words_per_row)
+                    .enumerate()
+                    .for_each(|(band, out)| {
+                        profile_scope!("band");
+                        step_band(band * band_rows, out)
+                    });
+            });
+            return next;
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = threads;
+
+        step_band(0, &mut next.words);
+        next
+    }
+
+    /// Steps the rows starting at `first_row` into `out`, which holds whole rows.
+    fn step_band(&self, topology: Topology, rule: Rule, first_row: usize, out: &mut [u64]) {
+        // Padded rows hold x = -1..=width as bits 0..=width+1, for the rows of the band and the
+        // one above and below it, with the cells beyond the edges filled in by the topology
+        let rows = out.len() / self.words_per_row;
+        let padded: Vec<Vec<u64>> = (first_row as isize - 1..=(first_row + rows) as isize)
+            .map(|y| self.padded_row(y, topology))
+            .collect();
+
+        for (y, out) in out.chunks_mut(self.words_per_row).enumerate() {
+            let (above, row, below) = (&padded[y], &padded[y + 1], &padded[y + 2]);
+            for (k, word) in out.iter_mut().enumerate() {
+                let neighbors = [
+                    window(above, k, 0),
+                    window(above, k, 1),
+                    window(above, k, 2),
+                    window(row, k, 0),
+                    window(row, k, 2),
+                    window(below, k, 0),
+                    window(below, k, 1),
+                    window(below, k, 2),
+                ];
+
+                // Bit-sliced counter: bit i of each cell's neighbor count is in counts[i]
+                let mut counts = [0u64; 4];
+                for n in neighbors {
+                    rule::add_to_counts(&mut counts, n);
+                }
+                *word = rule.next_word(counts, window(row, k, 1));
+            }
+            *out.last_mut().unwrap() &= self.last_mask();
+        }
+    }
+
+    fn step_band_simd(&self, topology: Topology, rule: Rule, first_row: usize, out: &mut [u64]) {
+        // For each padded row, the words seen by the left, center and right neighbor columns
+        let rows = out.len() / self.words_per_row;
+        let windows: Vec<[Vec<u64>; 3]> = (first_row as isize - 1..=(first_row + rows) as isize)
+            .map(|y| {
+                let padded = self.padded_row(y, topology);
+                [0, 1, 2].map(|offset| (0..self.words_per_row).map(|k| window(&padded, k, offset)).collect())
+            })
+            .collect();
+
+        for (y, out) in out.chunks_mut(self.words_per_row).enumerate() {
+            simd::next_words(&windows[y], &windows[y + 1], &windows[y + 2], out, rule);
+            *out.last_mut().unwrap() &= self.last_mask();
+        }
+    }
+
+    /// The bits of a row's last word that hold cells.
+    fn last_mask(&self) -> u64 {
+        if self.width.is_multiple_of(64) {
+            !0
+        } else {
+            (1u64 << (self.width % 64)) - 1
+        }
+    }
+
+    fn padded_row(&self, y: isize, topology: Topology) -> Vec<u64> {
+        let mut padded = vec![0u64; (self.width + 2).div_ceil(64) + 1];
+        if y >= 0 && (y as usize) < self.height {
+            // Copy the row shifted up by one bit, then resolve the two cells beyond the edges
+            for (k, &word) in self.row(y as usize).iter().enumerate() {
+                padded[k] |= word << 1;
+                padded[k + 1] |= word >> 63;
+            }
+            let mut set_bit = |i: usize| padded[i / 64] |= 1 << (i % 64);
+            for (i, x) in [(0, -1), (self.width + 1, self.width as isize)] {
+                if let Some((rx, ry)) = topology.resolve(x, y, self.width, self.height) {
+                    if self.get(rx, ry) {
+                        set_bit(i);
+                    }
+                }
+            }
+        } else {
+            // Rows beyond the top or bottom edge are resolved cell by cell
+            let mut set_bit = |i: usize| padded[i / 64] |= 1 << (i % 64);
+            for x in -1..=self.width as isize {
+                if let Some((rx, ry)) = topology.resolve(x, y, self.width, self.height) {
+                    if self.get(rx, ry) {
+                        set_bit((x + 1) as usize);
+                    }
+                }
+            }
+        }
+        padded
+    }
+}
+
+/// The 64 bits of a padded row starting at bit `64 * k + offset`.
+fn window(padded: &[u64], k: usize, offset: usize) -> u64 {
+    if offset == 0 {
+        padded[k]
+    } else {
+        (padded[k] >> offset) | (padded[k + 1] << (64 - offset))
+    }
+}
+
+/// Running totals of live cells over a grid padded by `margin` on every side, with the cells
+/// beyond the edges filled in by the topology: `totals[y][x]` counts the live cells above and to
+/// the left of padded cell (x, y).
+struct SummedArea {
+    margin: usize,
+    stride: usize, // padded width plus one
+    totals: Vec<u32>,
+}
+
+impl SummedArea {
+    fn new(grid: &BitGrid, topology: Topology, margin: usize) -> Self {
+        let (width, height) = (grid.width + 2 * margin, grid.height + 2 * margin);
+        let stride = width + 1;
+        let mut totals = vec![0; stride * (height + 1)];
+        for py in 0..height {
+            let mut row_total = 0;
+            for px in 0..width {
+                let (x, y) = (px as isize - margin as isize, py as isize - margin as isize);
+                let alive = match (usize::try_from(x), usize::try_from(y)) {
+                    (Ok(x), Ok(y)) if x < grid.width && y < grid.height => grid.get(x, y),
+                    _ => topology.resolve(x, y, grid.width, grid.height).is_some_and(|(x, y)| grid.get(x, y)),
+                };
+                row_total += alive as u32;
+                totals[(py + 1) * stride + px + 1] = totals[py * stride + px + 1] + row_total;
+            }
+        }
+        Self { margin, stride, totals }
+    }
+
+    /// Live cells in padded rows `top..bottom` and columns `left..right`.
+    fn sum(&self, left: usize, top: usize, right: usize, bottom: usize) -> u32 {
+        let at = |x: usize, y: usize| self.totals[y * self.stride + x];
+        at(right, bottom) + at(left, top) - at(left, bottom) - at(right, top)
+    }
+
+    /// Live cells in the neighborhood of grid cell (x, y), the cell itself included.
+    fn neighbors(&self, x: usize, y: usize, neighborhood: Neighborhood) -> u32 {
+        let r = neighborhood.radius as usize;
+        // Grid cell (x, y) is padded cell (x + margin, y + margin)
+        let (px, py) = (x + self.margin, y + self.margin);
+        match neighborhood.shape {
+            Shape::Moore => self.sum(px - r, py - r, px + r + 1, py + r + 1),
+            _ => (py - r..=py + r)
+                .map(|row| {
+                    let (first, last) = neighborhood.span(row as i64 - py as i64, rule::is_upward(x as i64, y as i64));
+                    match last >= first {
+                        true => self.sum((px as i64 + first) as usize, row, (px as i64 + last + 1) as usize, row + 1),
+                        false => 0,
+                    }
+                })
+                .sum(),
+        }
+    }
+}
+
+/// A shared rayon pool with the given number of threads, built on first use.
+#[cfg(not(target_arch = "wasm32"))]
+fn thread_pool(threads: usize) -> std::sync::Arc<rayon::ThreadPool> {
+    use once_cell::sync::Lazy;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    static POOLS: Lazy<Mutex<HashMap<usize, Arc<rayon::ThreadPool>>>> = Lazy::new(Default::default);
+    let mut pools = POOLS.lock().unwrap();
+    pools
+        .entry(threads)
+        .or_insert_with(|| Arc::new(rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap()))
+        .clone()
+}
@@ -0,0 +1,55 @@
+//! Mapping between screen positions and grid coordinates, with pan and zoom.
+
+use eframe::egui::{Pos2, Rect, Vec2};
+
+pub const MIN_CELL_SIZE: f32 = 0.25;
+pub const MAX_CELL_SIZE: f32 = 80.0;
+
+/// World coordinates are measured in cells, with visible cell (i, j) covering [i, i+1) x [j, j+1).
+pub struct Camera {
+    pub center: Pos2, // world position shown at the middle of the viewport
+    pub cell_size: f32, // on-screen size of one cell, in points
+}
+
+impl Camera {
+    pub fn new(center: Pos2, cell_size: f32) -> Self {
+        Self { center, cell_size }
+    }
+
+    pub fn world_to_screen(&self, viewport: Rect, world: Pos2) -> Pos2 {
+        viewport.center() + (world - self.center) * self.cell_size
+    }
+
+    pub fn screen_to_world(&self, viewport: Rect, screen: Pos2) -> Pos2 {
+        self.center + (screen - viewport.center()) / self.cell_size
+    }
+
+    /// The part of the world visible through the viewport.
+    pub fn visible_world(&self, viewport: Rect) -> Rect {
+        Rect::from_min_max(
+            self.screen_to_world(viewport, viewport.min),
+            self.screen_to_world(viewport, viewport.max),
+        )
+    }
+
+    /// Moves the camera so the world follows a drag of `delta` screen points.
+    pub fn pan(&mut self, delta: Vec2) {
+        self.center -= delta / self.cell_size;
+    }
+
+    /// Zooms by `factor`, keeping the world point under `anchor` fixed on screen.
+    pub fn zoom_at(&mut self, viewport: Rect, anchor: Pos2, factor: f32) {
+        let before = self.screen_to_world(viewport, anchor);
+        self.cell_size = (self.cell_size * factor).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+        let after = self.screen_to_world(viewport, anchor);
+        self.center += before - after;
+    }
+
+    /// Centers on `world` and zooms so it fills the viewport, leaving `margin` points on each side.
+    pub fn fit(&mut self, viewport: Rect, world: Rect, margin: f32) {
+        let available = (viewport.size() - Vec2::splat(2.0 * margin)).max(Vec2::splat(1.0));
+        let size = world.size().max(Vec2::splat(1.0));
+        self.center = world.center();
+        self.cell_size = (available.x / size.x).min(available.y / size.y).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+    }
+}
@@ -0,0 +1,125 @@
+//! The pattern browser's catalog: the built-in library followed by the patterns the user saved,
+//! searchable by name, author and category, each with a thumbnail drawn once and kept.
+
+use std::collections::HashMap;
+
+use eframe::egui::{self, Color32, ColorImage};
+use serde::{Deserialize, Serialize};
+
+use crate::formats::rle;
+use crate::library::LIBRARY;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+pub const THUMBNAIL_SIZE: usize = 64; // Side of a thumbnail, in pixels
+pub const SAVED: &str = "Saved"; // Category of the user's patterns
+
+/// A pattern in the catalog.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub author: String,
+    pub category: String,
+    pub rle: String,
+}
+
+impl Entry {
+    /// A user's pattern, saved as RLE under the rule it was made for.
+    pub fn saved(name: &str, author: &str, pattern: &Pattern, rule: Rule) -> Self {
+        Self { name: name.trim().to_string(), author: author.trim().to_string(), category: SAVED.to_string(), rle: rle::write(pattern, rule) }
+    }
+
+    pub fn pattern(&self) -> Result<Pattern, String> {
+        rle::parse(&self.rle)
+    }
+
+    /// Whether every word of the query turns up in the name, author or category, ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        let text = format!("{} {} {}", self.name, self.author, self.category).to_lowercase();
+        query.to_lowercase().split_whitespace().all(|word| text.contains(word))
+    }
+}
+
+pub struct Catalog {
+    entries: Vec<Entry>, // the library's, then the saved ones
+    thumbnails: HashMap<String, egui::TextureHandle>, // by RLE
+    colors: (Color32, Color32), // alive and dead colors the thumbnails were drawn in
+}
+
+impl Catalog {
+    pub const STORAGE_KEY: &'static str = "patterns";
+
+    pub fn new(saved: Vec<Entry>) -> Self {
+        let library = LIBRARY.iter().map(|entry| Entry {
+            name: entry.name.to_string(),
+            author: entry.author.to_string(),
+            category: entry.category.to_string(),
+            rle: entry.rle.to_string(),
+        });
+        Self { entries: library.chain(saved).collect(), thumbnails: HashMap::new(), colors: (Color32::WHITE, Color32::BLACK) }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The user's patterns, which are what gets stored between runs.
+    pub fn saved(&self) -> Vec<Entry> {
+        self.entries.iter().filter(|entry| entry.category == SAVED).cloned().collect()
+    }
+
+    /// Adds a user's pattern, in place of a saved one of the same name.
+    pub fn save(&mut self, entry: Entry) {
+        match self.entries.iter_mut().find(|saved| saved.category == SAVED && saved.name == entry.name) {
+            Some(saved) => *saved = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// Removes a user's pattern; the library's stay.
+    pub fn remove(&mut self, index: usize) {
+        if self.entries.get(index).is_some_and(|entry| entry.category == SAVED) {
+            self.entries.remove(index);
+        }
+    }
+
+    /// The entry's thumbnail, drawn the first time it is asked for in these colors.
+    pub fn thumbnail(&mut self, ctx: &egui::Context, index: usize, alive: Color32, dead: Color32) -> egui::TextureHandle {
+        if self.colors != (alive, dead) {
+            self.thumbnails.clear();
+            self.colors = (alive, dead);
+        }
+        let entry = &self.entries[index];
+        let texture = self.thumbnails.entry(entry.rle.clone()).or_insert_with(|| {
+            let image = thumbnail(&entry.pattern().unwrap_or_default(), alive, dead);
+            ctx.load_texture(format!("thumbnail {}", entry.name), image, egui::TextureOptions::NEAREST)
+        });
+        texture.clone()
+    }
+}
+
+/// The pattern scaled to fit a square thumbnail and centered in it. Cells get whole pixels
+/// while they fit, and larger patterns share pixels, which show alive if any of theirs is.
+fn thumbnail(pattern: &Pattern, alive: Color32, dead: Color32) -> ColorImage {
+    let mut image = ColorImage::new([THUMBNAIL_SIZE; 2], dead);
+    let side = pattern.width.max(pattern.height).max(1);
+    let (pixels, scale) = match THUMBNAIL_SIZE / side {
+        0 => (THUMBNAIL_SIZE, THUMBNAIL_SIZE as f64 / side as f64),
+        cell => (side * cell, cell as f64),
+    };
+    let margin = (THUMBNAIL_SIZE - pixels) / 2;
+    let offset = |length: usize| margin + (pixels - (length as f64 * scale).round() as usize) / 2;
+    let (left, top) = (offset(pattern.width), offset(pattern.height));
+    let cell = (scale as usize).max(1);
+    for &(x, y) in &pattern.cells {
+        let (px, py) = (left + (x as f64 * scale) as usize, top + (y as f64 * scale) as usize);
+        for dy in 0..cell {
+            for dx in 0..cell {
+                if px + dx < THUMBNAIL_SIZE && py + dy < THUMBNAIL_SIZE {
+                    image[(px + dx, py + dy)] = alive;
+                }
+            }
+        }
+    }
+    image
+}
@@ -0,0 +1,118 @@
+//! `game_of_life run`: advances a pattern file in an infinite universe and writes the result,
+//! optionally printing every generation on the way. Needs no window, and builds without the
+//! `gui` feature.
+
+use clap::Args;
+
+use crate::formats::{plaintext, Format};
+use crate::rule::Rule;
+use crate::stats::{Changes, PopulationLog};
+use crate::universe::{Engine, Universe};
+
+const MAX_GRID_SIDE: usize = 4096; // Largest finite grid kept behind the infinite universe
+
+/// Writes the pattern after some generations to a file, in the format its extension names, or
+/// as RLE to stdout.
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// Pattern file to start from
+    #[arg(long, value_name = "FILE")]
+    pattern: String,
+    /// Generations to advance
+    #[arg(long, value_name = "N")]
+    generations: u64,
+    /// Where to write the result; stdout if not given
+    #[arg(long, value_name = "FILE")]
+    out: Option<String>,
+    /// Rule to run, in B/S or another notation the app reads; by default the one the pattern
+    /// file names, or Conway's Life
+    #[arg(long, value_parser = Rule::parse)]
+    rule: Option<Rule>,
+    /// Show every generation as text on the way
+    #[arg(long)]
+    print: bool,
+    /// Write the population, births, deaths and bounding box of every generation to this CSV file
+    #[arg(long, value_name = "FILE")]
+    stats: Option<String>,
+}
+
+/// Runs as the `run` subcommand asks, returning what was done.
+pub fn run(args: RunArgs) -> Result<String, String> {
+    let RunArgs { pattern, generations, out, rule, print, stats } = args;
+    let mut universe = load(&pattern, rule)?;
+    let mut log = stats.as_ref().map(|_| PopulationLog::new(usize::MAX));
+    if print || log.is_some() {
+        // One generation at a time, to show or record each
+        let record = log.is_some();
+        let mut observe = |universe: &Universe, changes: Option<Changes>| {
+            if print {
+                show(universe);
+            }
+            if let Some(log) = &mut log {
+                log.record(universe, changes);
+            }
+        };
+        observe(&universe, None);
+        for _ in 0..generations {
+            let previous = record.then(|| universe.clone());
+            universe.advance(1);
+            observe(&universe, previous.map(|previous| universe.changes_since(&previous)));
+        }
+    } else {
+        universe.advance(generations);
+    }
+
+    let pattern = universe.to_pattern();
+    let done = format!("Generation {} has {} cells", universe.generation(), universe.population());
+    let mut saved_stats = String::new();
+    if let (Some(path), Some(log)) = (&stats, &log) {
+        std::fs::write(path, log.to_csv()).map_err(|e| format!("Failed to save {path}: {e}"))?;
+        saved_stats = format!(", stats saved to {path}");
+    }
+    match out.as_deref() {
+        Some(path) => {
+            let format = Format::from_path(path).unwrap_or(Format::Rle);
+            std::fs::write(path, format.write(&pattern, universe.rule())).map_err(|e| format!("Failed to save {path}: {e}"))?;
+            Ok(format!("{done}, saved to {path} as {}{saved_stats}", format.name()))
+        }
+        None => {
+            print!("{}", Format::Rle.write(&pattern, universe.rule()));
+            Ok(format!("{done}{saved_stats}"))
+        }
+    }
+}
+
+/// Reads a grid size such as `200x150`.
+pub fn parse_size(text: &str) -> Result<(usize, usize), String> {
+    let size = text.split_once(['x', 'X']).and_then(|(width, height)| Some((width.trim().parse().ok()?, height.trim().parse().ok()?)));
+    size.filter(|&(width, height)| width > 0 && height > 0).ok_or(format!("{text} isn't a size such as 200x150"))
+}
+
+/// Reads a pattern file, in the format its extension or contents show, into an infinite
+/// universe following the rule, or else the one the file names, centered on (0, 0). HashLife
+/// runs it when it can.
+pub fn load(path: &str, rule: Option<Rule>) -> Result<Universe, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to open {path}: {e}"))?;
+    let format = Format::from_path(path).unwrap_or_else(|| Format::detect(&text));
+    let pattern = format.parse(&text).map_err(|e| format!("Failed to read {path}: {e}"))?;
+    let rule = match rule {
+        Some(rule) => rule,
+        None => format.rule(&text).transpose().map_err(|e| format!("Failed to read the rule of {path}: {e}"))?.unwrap_or_default(),
+    };
+    // The grid is only kept for switching back to a finite universe, so it needn't fit the pattern
+    let mut universe = Universe::new(pattern.width.min(MAX_GRID_SIDE), pattern.height.min(MAX_GRID_SIDE));
+    universe.set_infinite(true);
+    universe.set_rule(rule)?;
+    if Engine::HashLife.supports_rule(rule) {
+        universe.set_engine(Engine::HashLife)?;
+    }
+    universe.place(&pattern, -(pattern.width as i64 / 2), -(pattern.height as i64 / 2));
+    Ok(universe)
+}
+
+/// Prints a generation as rows of `.` and `O`, under a line saying which it is.
+fn show(universe: &Universe) {
+    println!("Generation {}, population {}", universe.generation(), universe.population());
+    print!("{}", plaintext::write_art(&universe.to_pattern()));
+    println!();
+}
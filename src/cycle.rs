@@ -0,0 +1,36 @@
+//! Noticing when the universe repeats an earlier state, which means it will cycle forever.
+
+use std::collections::VecDeque;
+
+/// Hashes of the most recent generations, oldest first, for spotting a repeat.
+pub struct CycleDetector {
+    recent: VecDeque<(u64, u64)>, // (generation, hash of its cells)
+    capacity: usize,
+}
+
+impl CycleDetector {
+    /// A detector that can find cycles of up to `capacity` generations.
+    pub fn new(capacity: usize) -> Self {
+        Self { recent: VecDeque::with_capacity(capacity), capacity: capacity.max(1) }
+    }
+
+    pub fn clear(&mut self) {
+        self.recent.clear();
+    }
+
+    /// Adds a generation and returns the number of generations since the same state was last
+    /// seen, if it was. When generations are skipped that is a multiple of the true period.
+    /// A generation that isn't later than the last one means time went backwards, so
+    /// everything seen before is forgotten.
+    pub fn observe(&mut self, generation: u64, hash: u64) -> Option<u64> {
+        if self.recent.back().is_some_and(|&(last, _)| generation <= last) {
+            self.clear();
+        }
+        let repeat = self.recent.iter().rev().find(|&&(_, h)| h == hash).map(|&(g, _)| generation - g);
+        if self.recent.len() == self.capacity {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((generation, hash));
+        repeat
+    }
+}
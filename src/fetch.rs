@@ -0,0 +1,82 @@
+//! Pattern files downloaded over HTTP for the "Load from URL" dialog: with reqwest on a thread
+//! on native targets, and with the browser's fetch on the web. A bare LifeWiki pattern name,
+//! or a link to its wiki page, stands for the RLE file the wiki keeps for it.
+
+use std::sync::mpsc::{self, Receiver};
+
+use eframe::egui;
+
+const LIFEWIKI_PATTERNS: &str = "https://conwaylife.com/patterns/";
+
+/// A download under way.
+pub struct Download {
+    pub url: String,
+    result: Receiver<Result<String, String>>,
+}
+
+impl Download {
+    /// Starts fetching the text at `url`, waking up `ctx` once it is in.
+    pub fn start(url: String, ctx: egui::Context) -> Self {
+        let (sender, result) = mpsc::channel();
+        let fetched_url = url.clone();
+        let send = move |text| {
+            let _ = sender.send(text);
+            ctx.request_repaint();
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || send(get(&fetched_url)));
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move { send(get(&fetched_url).await) });
+        Self { url, result }
+    }
+
+    /// The downloaded text, or why it couldn't be had, once the download is over.
+    pub fn poll(&self) -> Option<Result<String, String>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err("The download stopped".to_string())),
+        }
+    }
+}
+
+/// The URL to download for what was typed: URLs as they are, except links to LifeWiki pages,
+/// and anything else as the name of a LifeWiki pattern. The wiki names its RLE files after
+/// the pattern, in lower case with only the letters and digits, so "Gosper glider gun" is
+/// `gosperglidergun.rle`.
+pub fn pattern_url(text: &str) -> String {
+    let text = text.trim();
+    let address = text.strip_prefix("https://").or_else(|| text.strip_prefix("http://")).unwrap_or(text);
+    let address = address.strip_prefix("www.").unwrap_or(address);
+    let name = match address.strip_prefix("conwaylife.com/wiki/") {
+        Some(page) => page.split(['#', '?']).next().unwrap_or(page),
+        None if text.contains("://") => return text.to_string(),
+        None => text,
+    };
+    let file: String = name.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect();
+    format!("{LIFEWIKI_PATTERNS}{file}.rle")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn get(url: &str) -> Result<String, String> {
+    let response = reqwest::blocking::get(url).map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("the server answered {}", response.status()));
+    }
+    response.text().map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn get(url: &str) -> Result<String, String> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+    // Most often the server doesn't allow pages from elsewhere to read its files
+    let refused = |_| "the browser couldn't fetch it; the site may not allow it".to_string();
+    let window = web_sys::window().ok_or("No window")?;
+    let response: web_sys::Response = JsFuture::from(window.fetch_with_str(url)).await.map_err(refused)?.dyn_into().map_err(|_| "No response")?;
+    if !response.ok() {
+        return Err(format!("the server answered {} {}", response.status(), response.status_text()));
+    }
+    let text = JsFuture::from(response.text().map_err(refused)?).await.map_err(refused)?;
+    text.as_string().ok_or_else(|| "the file isn't text".to_string())
+}
@@ -0,0 +1,74 @@
+//! Life 1.06 files: a `#Life 1.06` header followed by one `x y` coordinate pair per live cell.
+
+use super::Limits;
+use crate::pattern::Pattern;
+
+const HEADER: &str = "#Life 1.06";
+
+pub fn parse(text: &str) -> Result<Pattern, String> {
+    parse_within(text, Limits::FILE)
+}
+
+/// Parses Life 1.06 text whose cells span and number no more than the limits allow.
+pub fn parse_within(text: &str, limits: Limits) -> Result<Pattern, String> {
+    let mut live = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut coordinates = line.split_whitespace().map(str::parse::<i64>);
+        match (coordinates.next(), coordinates.next(), coordinates.next()) {
+            (Some(Ok(_)), Some(Ok(_)), None) if live.len() == limits.cells => return Err(limits.too_many()),
+            (Some(Ok(x)), Some(Ok(y)), None) => live.push((x, y)),
+            _ => return Err(format!("Expected an \"x y\" pair on line {}", number + 1)),
+        }
+    }
+    let pattern = Pattern::try_from_cells(live)?;
+    limits.check(&pattern)?;
+    Ok(pattern)
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = format!("{}\n", HEADER);
+    for &(x, y) in &pattern.cells {
+        out.push_str(&format!("{} {}\n", x, y));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(cells: &[(i64, i64)]) {
+        let pattern = Pattern::from_cells(cells.to_vec());
+        assert_eq!(parse(&write(&pattern)), Ok(pattern));
+    }
+
+    #[test]
+    fn round_trips_patterns() {
+        round_trip(&[]);
+        round_trip(&[(0, 0)]);
+        round_trip(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]); // glider
+        round_trip(&[(0, 0), (0, 4), (3, 7)]); // empty rows in between
+    }
+
+    #[test]
+    fn reads_negative_coordinates() {
+        let glider = Pattern::from_cells(vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(parse("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n"), Ok(glider));
+    }
+
+    #[test]
+    fn rejects_coordinates_out_of_range() {
+        assert!(parse("#Life 1.06\n-9223372036854775808 0\n9223372036854775807 0\n").is_err());
+        assert!(parse("#Life 1.06\n0 -9223372036854775808\n0 9223372036854775807\n").is_err());
+        assert!(parse("#Life 1.06\n0 0\n0 9223372036854775808\n").is_err());
+        assert!(parse("#Life 1.06\n0 0\n1099511627774 0\n").is_err());
+        let limits = Limits { cells: 2, side: 10 };
+        assert!(parse_within("#Life 1.06\n0 0\n9 9\n", limits).is_ok());
+        assert!(parse_within("#Life 1.06\n0 0\n10 0\n", limits).is_err());
+        assert!(parse_within("#Life 1.06\n0 0\n1 0\n2 0\n", limits).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! Golly's macrocell (`.mc`) format, which stores the quadtree itself rather than the cells.
+//!
+//! Each line defines one node, numbered from 1; child number 0 means an empty node. Level-3
+//! nodes are written as 8x8 bitmaps (`.` dead, `*` alive, `$` end of row), higher levels as
+//! `level nw ne sw se`.
+
+use std::collections::HashMap;
+
+use super::Limits;
+use crate::pattern::Pattern;
+use crate::quadtree::{NodeId, Quadtree, ALIVE, DEAD};
+use crate::rule::Rule;
+
+const HEADER: &str = "[M2] (game_of_life)";
+const LEAF_LEVEL: u8 = 3;
+
+pub fn parse(text: &str) -> Result<Pattern, String> {
+    parse_within(text, Limits::FILE)
+}
+
+/// Parses macrocell text whose pattern spans and numbers no more than the limits allow. Node
+/// populations are checked as the nodes are read, before any cells are listed.
+pub fn parse_within(text: &str, limits: Limits) -> Result<Pattern, String> {
+    let mut tree = Quadtree::new();
+    let mut ids: Vec<NodeId> = vec![DEAD]; // ids[n] is the node defined on line n; 0 is a placeholder
+    let mut levels: Vec<u8> = vec![0];
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('[') || line.starts_with('#') {
+            continue;
+        }
+
+        let (id, level) = if line.starts_with(['.', '*', '$']) {
+            (parse_leaf(&mut tree, line)?, LEAF_LEVEL)
+        } else {
+            let fields: Vec<usize> = line
+                .split_whitespace()
+                .map(|f| f.parse::<usize>().map_err(|_| format!("Invalid macrocell line \"{}\"", line)))
+                .collect::<Result<_, _>>()?;
+            let [level, nw, ne, sw, se] = fields[..] else {
+                return Err(format!("Expected 5 numbers in macrocell line \"{}\"", line));
+            };
+            let level = u8::try_from(level).ok().filter(|l| (1..64).contains(l)).ok_or("Invalid node level")?;
+
+            let mut resolve = |n: usize| -> Result<NodeId, String> {
+                if level == 1 {
+                    // Level-1 children are cell states rather than node numbers
+                    return Ok(if n == 0 { DEAD } else { ALIVE });
+                }
+                if n == 0 {
+                    return Ok(tree.empty(level - 1));
+                }
+                match (ids.get(n), levels.get(n)) {
+                    (Some(&id), Some(&l)) if l == level - 1 => Ok(id),
+                    _ => Err(format!("Node {} is missing or has the wrong level", n)),
+                }
+            };
+            let children = [resolve(nw)?, resolve(ne)?, resolve(sw)?, resolve(se)?];
+            (tree.try_join(children[0], children[1], children[2], children[3]).ok_or_else(|| limits.too_many())?, level)
+        };
+        if tree.population(id) > limits.cells as u64 {
+            return Err(limits.too_many());
+        }
+        ids.push(id);
+        levels.push(level);
+    }
+
+    let pattern = match ids.last() {
+        Some(&root) if ids.len() > 1 => tree.to_pattern(root),
+        _ => Pattern::default(),
+    };
+    limits.check(&pattern)?;
+    Ok(pattern)
+}
+
+fn parse_leaf(tree: &mut Quadtree, line: &str) -> Result<NodeId, String> {
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0u64, 0u64);
+    for c in line.chars() {
+        if c != '$' && (x >= 8 || y >= 8) {
+            return Err("Macrocell leaf is larger than 8x8".to_string());
+        }
+        match c {
+            '.' => x += 1,
+            '*' => {
+                cells.push((x, y));
+                x += 1;
+            }
+            '$' => {
+                x = 0;
+                y += 1;
+            }
+            _ => return Err(format!("Unexpected character '{}' in macrocell leaf", c)),
+        }
+    }
+    Ok(tree.build(LEAF_LEVEL, &cells))
+}
+
+/// The rule on the `#R` line, if there is one.
+pub fn header_rule(text: &str) -> Option<Result<Rule, String>> {
+    let rule = text.lines().find_map(|line| line.trim().strip_prefix("#R"))?;
+    Some(Rule::parse(rule.trim()))
+}
+
+pub fn write(pattern: &Pattern, rule: Rule) -> String {
+    let mut tree = Quadtree::new();
+    let root = tree.from_pattern(pattern, LEAF_LEVEL);
+
+    let mut out = format!("{}\n#R {rule}\n", HEADER);
+    let mut numbers = HashMap::new();
+    if tree.population(root) > 0 {
+        write_node(&tree, root, &mut numbers, &mut out);
+    }
+    out
+}
+
+/// Writes a node after its children, returning its line number (0 for empty nodes).
+fn write_node(tree: &Quadtree, id: NodeId, numbers: &mut HashMap<NodeId, usize>, out: &mut String) -> usize {
+    if tree.population(id) == 0 {
+        return 0;
+    }
+    if let Some(&n) = numbers.get(&id) {
+        return n;
+    }
+
+    let node = tree.node(id);
+    if node.level == LEAF_LEVEL {
+        let mut rows = vec![String::new(); 8];
+        for (x, y) in tree.live_cells(id) {
+            let row = &mut rows[y as usize];
+            while row.len() < x as usize {
+                row.push('.');
+            }
+            row.push('*');
+        }
+        for row in rows {
+            out.push_str(&row);
+            out.push('$');
+        }
+    } else {
+        let [nw, ne, sw, se] = node.children.map(|c| write_node(tree, c, numbers, out));
+        out.push_str(&format!("{} {} {} {} {}", node.level, nw, ne, sw, se));
+    }
+    out.push('\n');
+
+    let n = numbers.len() + 1;
+    numbers.insert(id, n);
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(cells: &[(i64, i64)]) {
+        let pattern = Pattern::from_cells(cells.to_vec());
+        assert_eq!(parse(&write(&pattern, Rule::CONWAY)), Ok(pattern));
+    }
+
+    #[test]
+    fn round_trips_patterns() {
+        round_trip(&[]);
+        round_trip(&[(0, 0)]);
+        round_trip(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]); // glider
+        round_trip(&[(7, 0), (0, 7), (8, 8)]); // the corners of neighboring leaves
+        round_trip(&[(0, 0), (700, 0), (0, 1000)]); // mostly empty nodes, written as 0
+        // The same block over and over, so that nodes are shared and numbered once
+        let blocks: Vec<(i64, i64)> = (0..64).flat_map(|i| [(i * 16, 0), (i * 16 + 1, 0), (i * 16, 1), (i * 16 + 1, 1)]).collect();
+        round_trip(&blocks);
+    }
+
+    #[test]
+    fn rejects_leaves_past_eight_cells() {
+        assert!(parse("[M2]\n.......*$").is_ok());
+        assert!(parse("[M2]\n........*$").is_err());
+        assert!(parse("[M2]\n$$$$$$$$*$").is_err());
+    }
+
+    #[test]
+    fn rejects_nodes_past_the_limits() {
+        // Full nodes nested until their population no longer fits in a u64
+        let mut text = "[M2]\n".to_string() + &"********$".repeat(8) + "\n";
+        for level in 4..40 {
+            let child = level - 3;
+            text += &format!("{level} {child} {child} {child} {child}\n");
+        }
+        assert!(parse(&text).is_err());
+        let limits = Limits { cells: 64, side: 16 };
+        assert!(parse_within("[M2]\n*$\n4 1 0 0 1\n", limits).is_ok());
+        assert!(parse_within("[M2]\n*$\n4 1 0 0 1\n5 0 0 0 2\n6 3 0 0 3\n", limits).is_err());
+        assert!(parse_within("[M2]\n********$********$\n4 1 1 1 1\n", Limits { cells: 63, side: 16 }).is_err());
+    }
+}
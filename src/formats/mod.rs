@@ -0,0 +1,133 @@
+//! Reading and writing patterns in the text formats used by other Life programs.
+
+pub mod life106;
+pub mod macrocell;
+pub mod plaintext;
+pub mod rle;
+
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+/// Bounds on what pattern text may expand to, checked before any cells are added, so that a
+/// malformed or hostile file is an error rather than a crash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    pub cells: usize, // live cells
+    pub side: i64,    // width and height
+}
+
+impl Limits {
+    /// What the parsers allow: far more than real patterns. Those larger than the largest grid
+    /// only fit in an unbounded universe, which stores live cells rather than the space
+    /// between them.
+    pub const FILE: Limits = Limits { cells: 1 << 26, side: 1 << 20 };
+
+    /// An error if the pattern is wider or taller than allowed, or has too many live cells.
+    pub fn check(self, pattern: &Pattern) -> Result<(), String> {
+        if pattern.width.max(pattern.height) as u64 > self.side as u64 {
+            return Err(self.too_large());
+        }
+        if pattern.cells.len() > self.cells {
+            return Err(self.too_many());
+        }
+        Ok(())
+    }
+
+    pub fn too_large(self) -> String {
+        format!("The pattern is larger than {0}x{0} cells", self.side)
+    }
+
+    pub fn too_many(self) -> String {
+        format!("The pattern has more than {} live cells", self.cells)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life106,
+    Macrocell,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Rle, Format::Plaintext, Format::Life106, Format::Macrocell];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Rle => "RLE",
+            Format::Plaintext => "Plaintext (.cells)",
+            Format::Life106 => "Life 1.06",
+            Format::Macrocell => "Macrocell (.mc)",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Rle => "rle",
+            Format::Plaintext => "cells",
+            Format::Life106 => "lif",
+            Format::Macrocell => "mc",
+        }
+    }
+
+    /// Guesses the format from a file name, e.g. `glider.cells`.
+    pub fn from_path(path: &str) -> Option<Format> {
+        let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(Format::Rle),
+            "cells" => Some(Format::Plaintext),
+            "lif" | "life" => Some(Format::Life106),
+            "mc" => Some(Format::Macrocell),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the contents of a pattern file.
+    pub fn detect(text: &str) -> Format {
+        let first = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+        if first.starts_with("#Life 1.06") {
+            Format::Life106
+        } else if first.starts_with("[M2]") {
+            Format::Macrocell
+        } else if first.starts_with('!') || first.starts_with('.') || first.starts_with('O') {
+            Format::Plaintext
+        } else {
+            Format::Rle
+        }
+    }
+
+    pub fn parse(self, text: &str) -> Result<Pattern, String> {
+        match self {
+            Format::Rle => rle::parse(text),
+            Format::Plaintext => plaintext::parse(text),
+            Format::Life106 => life106::parse(text),
+            Format::Macrocell => macrocell::parse(text),
+        }
+    }
+
+    /// The rule the text says its pattern runs under, for the formats that name one: `None` if
+    /// it doesn't, or an error if it names a rule that can't be read.
+    pub fn rule(self, text: &str) -> Option<Result<Rule, String>> {
+        match self {
+            Format::Rle => rle::header_rule(text),
+            Format::Macrocell => macrocell::header_rule(text),
+            Format::Plaintext | Format::Life106 => None,
+        }
+    }
+
+    /// The pattern as text, labelled with the rule in the formats that have room for it.
+    pub fn write(self, pattern: &Pattern, rule: Rule) -> String {
+        match self {
+            Format::Rle => rle::write(pattern, rule),
+            Format::Plaintext => plaintext::write(pattern),
+            Format::Life106 => life106::write(pattern),
+            Format::Macrocell => macrocell::write(pattern, rule),
+        }
+    }
+}
+
+/// Parses pattern text in whichever supported format it appears to be in.
+pub fn parse(text: &str) -> Result<Pattern, String> {
+    Format::detect(text).parse(text)
+}
@@ -0,0 +1,98 @@
+//! Plaintext `.cells` files: `!` comment lines followed by rows of `.` (dead) and `O` (alive).
+
+use super::Limits;
+use crate::pattern::Pattern;
+
+pub fn parse(text: &str) -> Result<Pattern, String> {
+    parse_within(text, Limits::FILE)
+}
+
+/// Parses plaintext whose rows and cells are no more than the limits allow.
+pub fn parse_within(text: &str, limits: Limits) -> Result<Pattern, String> {
+    // Art pasted from a forum post or commit message may be indented as a whole
+    let rows_of_cells = text.lines().filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('!'));
+    let indent = rows_of_cells.map(|line| line.len() - line.trim_start().len()).min().unwrap_or(0);
+    let mut rows = Vec::new();
+    let mut live = 0;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.trim_start().starts_with('!') {
+            continue;
+        }
+        let line = line.get(indent..).unwrap_or("");
+        if rows.len() as i64 >= limits.side || line.len() as i64 > limits.side {
+            return Err(limits.too_large());
+        }
+        let mut row = Vec::with_capacity(line.len());
+        for c in line.chars() {
+            match c {
+                '.' => row.push(false),
+                'O' | '*' if live == limits.cells => return Err(limits.too_many()),
+                'O' | '*' => {
+                    row.push(true);
+                    live += 1;
+                }
+                _ => return Err(format!("Unexpected character '{}' on line {}", c, number + 1)),
+            }
+        }
+        rows.push(row);
+    }
+    Ok(Pattern::from_rows(&rows))
+}
+
+/// Just the rows, each as wide as the pattern, so that the text reads as a block wherever it
+/// is pasted, and `parse` takes it back.
+pub fn write_art(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    for row in pattern.to_rows() {
+        out.extend(row.iter().map(|&alive| if alive { 'O' } else { '.' }));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = String::from("!Name: Untitled\n");
+    for row in pattern.to_rows() {
+        // Trailing dead cells are optional, so drop them to keep files small
+        let end = row.iter().rposition(|&alive| alive).map_or(0, |i| i + 1);
+        out.extend(row[..end].iter().map(|&alive| if alive { 'O' } else { '.' }));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(cells: &[(i64, i64)]) {
+        let pattern = Pattern::from_cells(cells.to_vec());
+        assert_eq!(parse(&write(&pattern)), Ok(pattern.clone()));
+        assert_eq!(parse(&write_art(&pattern)), Ok(pattern));
+    }
+
+    #[test]
+    fn round_trips_patterns() {
+        round_trip(&[]);
+        round_trip(&[(0, 0)]);
+        round_trip(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]); // glider
+        round_trip(&[(0, 0), (0, 4), (3, 7)]); // empty rows in between
+        round_trip(&[(0, 0), (9, 0), (0, 1), (0, 2), (5, 3)]); // rows ending well before the widest
+    }
+
+    #[test]
+    fn reads_indented_art() {
+        let glider = Pattern::from_cells(vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(parse("!Name: Glider\n    .O.\n    ..O\n    OOO\n"), Ok(glider));
+    }
+
+    #[test]
+    fn rejects_patterns_past_the_limits() {
+        let limits = Limits { cells: 4, side: 3 };
+        assert!(parse_within("OO\nOO\n", limits).is_ok());
+        assert!(parse_within("OOO\nOO\n", limits).is_err());
+        assert!(parse_within("...O\n", limits).is_err());
+        assert!(parse_within("O\n.\n.\nO\n", limits).is_err());
+    }
+}
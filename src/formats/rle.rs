@@ -0,0 +1,194 @@
+//! Run Length Encoded patterns, as used by Golly and the LifeWiki.
+
+use super::Limits;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+const MAX_LINE_LENGTH: usize = 70;
+
+/// Serializes a pattern as RLE text, including the `x = .., y = .., rule = ..` header.
+pub fn write(pattern: &Pattern, rule: Rule) -> String {
+    format!("x = {}, y = {}, rule = {rule}\n{}", pattern.width, pattern.height, write_cells(pattern))
+}
+
+/// The cells of a pattern as RLE, without the header.
+pub fn write_cells(pattern: &Pattern) -> String {
+    let mut out = String::new();
+
+    // Build the list of (run length, tag) pairs first, then wrap them into lines
+    let mut runs: Vec<(usize, char)> = Vec::new();
+    let mut push = |count: usize, tag: char| {
+        if count == 0 {
+            return;
+        }
+        match runs.last_mut() {
+            Some((n, t)) if *t == tag => *n += count,
+            _ => runs.push((count, tag)),
+        }
+    };
+
+    // Straight from the cells in row order, so far-flung cells don't cost the area between them
+    let mut cells = pattern.cells.clone();
+    cells.sort_unstable_by_key(|&(x, y)| (y, x));
+    cells.dedup();
+    let (mut cx, mut cy) = (0, 0);
+    for (x, y) in cells {
+        if y > cy {
+            push(y - cy, '$');
+            (cx, cy) = (0, y);
+        }
+        push(x - cx, 'b');
+        push(1, 'o');
+        cx = x + 1;
+    }
+
+    let mut line = String::new();
+    for (count, tag) in runs {
+        let token = if count == 1 { tag.to_string() } else { format!("{}{}", count, tag) };
+        if line.len() + token.len() > MAX_LINE_LENGTH {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        line.push_str(&token);
+    }
+    if line.len() + 1 > MAX_LINE_LENGTH {
+        out.push_str(&line);
+        out.push('\n');
+        line.clear();
+    }
+    line.push('!');
+    out.push_str(&line);
+    out.push('\n');
+
+    out
+}
+
+/// The rule the header names, if it has one. It runs to the end of the line, as Golly's wider
+/// rules have commas of their own, or to a `:` and the bounded grid Golly may add after it.
+pub fn header_rule(text: &str) -> Option<Result<Rule, String>> {
+    let header = text.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#'))?;
+    let (_, rule) = header.strip_prefix('x')?.split_once("rule")?;
+    let rule = rule.trim_start().strip_prefix('=')?.split(':').next().unwrap_or_default();
+    Some(Rule::parse(rule.trim()))
+}
+
+/// Parses RLE text. States other than `b`/`.` are treated as alive, and the rule is left to
+/// `header_rule`.
+pub fn parse(text: &str) -> Result<Pattern, String> {
+    parse_within(text, Limits::FILE)
+}
+
+/// Parses RLE text that may expand to no more than the limits allow.
+pub fn parse_within(text: &str, limits: Limits) -> Result<Pattern, String> {
+    let too_large = || limits.too_large();
+    let mut live = Vec::new();
+    let (mut x, mut y) = (0i64, 0i64);
+    let mut count = String::new();
+
+    'lines: for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("x ") || line.starts_with("x=") {
+            continue; // Comments and the header carry nothing we need
+        }
+        for c in line.chars() {
+            if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            }
+            let run = if count.is_empty() {
+                1
+            } else {
+                count.parse::<i64>().ok().filter(|&run| run <= limits.side).ok_or_else(|| format!("Run length {} is too large", count))?
+            };
+            count.clear();
+            let end = |start: i64| start.checked_add(run).filter(|&end| end <= limits.side).ok_or_else(too_large);
+            match c {
+                'b' | '.' => x = end(x)?,
+                '$' => {
+                    y = end(y)?;
+                    x = 0;
+                }
+                '!' => break 'lines,
+                c if c.is_ascii_alphabetic() => {
+                    let next = end(x)?;
+                    if y >= limits.side {
+                        return Err(too_large());
+                    }
+                    if live.len() + run as usize > limits.cells {
+                        return Err(limits.too_many());
+                    }
+                    live.extend((x..next).map(|cx| (cx, y)));
+                    x = next;
+                }
+                c if c.is_whitespace() => {}
+                _ => return Err(format!("Unexpected character '{}' in RLE data", c)),
+            }
+        }
+    }
+
+    Ok(Pattern::from_cells(live))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(cells: &[(i64, i64)]) {
+        let pattern = Pattern::from_cells(cells.to_vec());
+        assert_eq!(parse(&write(&pattern, Rule::CONWAY)), Ok(pattern));
+    }
+
+    #[test]
+    fn round_trips_patterns() {
+        round_trip(&[]);
+        round_trip(&[(0, 0)]);
+        round_trip(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]); // glider
+        round_trip(&[(0, 0), (0, 4), (3, 7)]); // empty rows in between
+        round_trip(&[(0, 0), (9, 0), (0, 1), (0, 2), (5, 3)]); // rows ending well before the widest
+    }
+
+    #[test]
+    fn writes_and_reads_the_rule() {
+        let glider = Pattern::from_cells(vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        for text in ["B36/S23", "B2/S345/C4", "R2,C0,S6-9,B7-8,NC", "Immigration"] {
+            let rule = Rule::parse(text).unwrap();
+            assert_eq!(header_rule(&write(&glider, rule)), Some(Ok(rule)));
+        }
+        assert_eq!(header_rule("#N Glider\nx = 3, y = 3, rule = B3/S23:T20,20\nbo$2bo$3o!"), Some(Ok(Rule::CONWAY)));
+        assert_eq!(header_rule("x=3,y=3,rule=B36/S23\nbo$2bo$3o!"), Some(Rule::parse("B36/S23")));
+        assert_eq!(header_rule("x = 3, y = 3\nbo$2bo$3o!"), None);
+        assert!(header_rule("x = 3, y = 3, rule = LifeHistory\nbo$2bo$3o!").is_some_and(|rule| rule.is_err()));
+    }
+
+    #[test]
+    fn rejects_runs_past_the_limits() {
+        assert!(parse("99999999999999999999o!").is_err());
+        assert!(parse("9223372036854775807b9223372036854775807o!").is_err());
+        assert!(parse("999999999999o!").is_err());
+        assert!(parse("1099511627775$1099511627775$o!").is_err());
+        let limits = Limits { cells: 10, side: 100 };
+        assert_eq!(parse_within("10o!", limits).map(|pattern| pattern.cells.len()), Ok(10));
+        assert!(parse_within("11o!", limits).is_err());
+        assert!(parse_within("5o$5o$o!", limits).is_err());
+        assert!(parse_within("99bo!", limits).is_ok());
+        assert!(parse_within("100bo!", limits).is_err());
+        assert!(parse_within("100$o!", limits).is_err());
+    }
+
+    #[test]
+    fn writes_far_apart_cells_without_the_space_between() {
+        let far = 1 << 40;
+        let pattern = Pattern::from_cells(vec![(far, far), (0, 0), (3, 0)]);
+        assert_eq!(write_cells(&pattern), format!("o2bo{far}${far}bo!\n"));
+    }
+
+    #[test]
+    fn round_trips_long_rows_across_lines() {
+        let cells: Vec<(i64, i64)> = (0..500).filter(|x| x % 3 != 1).map(|x| (x, x % 7)).collect();
+        let pattern = Pattern::from_cells(cells);
+        let text = write(&pattern, Rule::CONWAY);
+        assert!(text.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert_eq!(parse(&text), Ok(pattern));
+    }
+}
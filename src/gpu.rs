@@ -0,0 +1,169 @@
+//! Compute-shader stepping engine. The grid is uploaded to a pair of storage buffers, each
+//! generation is one dispatch from one buffer into the other, and only the final state is read
+//! back, so advancing many generations at once costs a single round trip.
+
+use once_cell::sync::Lazy;
+use wgpu::util::DeviceExt;
+
+use crate::bitgrid::BitGrid;
+use crate::rule::Rule;
+use crate::topology::{Edge, Topology};
+
+const WORKGROUP_SIZE: u32 = 64; // must match @workgroup_size in gpu.wgsl
+const BATCH: u64 = 256; // generations encoded per queue submission
+
+struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+// Created on first use; `None` if there is no adapter that can run compute shaders
+static GPU: Lazy<Option<Gpu>> = Lazy::new(|| pollster::block_on(Gpu::new()));
+
+impl Gpu {
+    async fn new() -> Option<Gpu> {
+        let instance = wgpu::Instance::default();
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions::default()).await?;
+        if !adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS) {
+            return None;
+        }
+        let descriptor = wgpu::DeviceDescriptor {
+            label: Some("life"),
+            required_features: wgpu::Features::empty(),
+            required_limits: adapter.limits(),
+        };
+        let (device, queue) = adapter.request_device(&descriptor, None).await.ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("life"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("life"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+        });
+        Some(Gpu { device, queue, pipeline })
+    }
+}
+
+/// Whether a GPU that can run the engine was found.
+pub fn is_available() -> bool {
+    GPU.is_some()
+}
+
+/// Advances the grid by `generations` on the GPU, or returns `None` without a usable GPU.
+pub fn advance(grid: &BitGrid, topology: Topology, rule: Rule, generations: u64) -> Option<BitGrid> {
+    profile_scope!();
+    let gpu = GPU.as_ref()?;
+    let (width, height) = (grid.width(), grid.height());
+    let words_per_row = width.div_ceil(32);
+    if width == 0 || height == 0 || generations == 0 {
+        return Some(grid.clone());
+    }
+
+    // The shader works on 32-bit words, so split each row's 64-bit words in two
+    let words: Vec<u32> = (0..height)
+        .flat_map(|y| grid.row(y).iter().flat_map(|&w| [w as u32, (w >> 32) as u32]).take(words_per_row))
+        .collect();
+    let params = [
+        width as u32,
+        height as u32,
+        words_per_row as u32,
+        edge_code(topology.horizontal),
+        edge_code(topology.vertical),
+        rule.birth,
+        rule.survival,
+        0,
+    ];
+
+    let device = &gpu.device;
+    let storage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST;
+    let buffers = [
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor { label: None, contents: &to_bytes(&words), usage: storage }),
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor { label: None, contents: &to_bytes(&words), usage: storage }),
+    ];
+    let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: None,
+        contents: &to_bytes(&params),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    // Bind group `i` reads buffer `i` and writes the other one
+    let layout = gpu.pipeline.get_bind_group_layout(0);
+    let bind_groups = [0, 1].map(|i| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: uniform.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: buffers[i].as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: buffers[1 - i].as_entire_binding() },
+            ],
+        })
+    });
+
+    let workgroups = (words.len() as u32).div_ceil(WORKGROUP_SIZE);
+    let mut done = 0;
+    while done < generations {
+        let batch = BATCH.min(generations - done);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&gpu.pipeline);
+            for generation in done..done + batch {
+                pass.set_bind_group(0, &bind_groups[(generation % 2) as usize], &[]);
+                pass.dispatch_workgroups(workgroups, 1, 1);
+            }
+        }
+        gpu.queue.submit([encoder.finish()]);
+        done += batch;
+    }
+
+    // Read back whichever buffer the last generation was written to
+    let size = (words.len() * 4) as u64;
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    encoder.copy_buffer_to_buffer(&buffers[(generations % 2) as usize], 0, &staging, 0, size);
+    gpu.queue.submit([encoder.finish()]);
+
+    let slice = staging.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| sender.send(result).unwrap());
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv().ok()?.ok()?;
+
+    let mut next = BitGrid::new(width, height);
+    {
+        let data = slice.get_mapped_range();
+        let result: Vec<u32> = data.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+        for (y, row) in result.chunks(words_per_row).enumerate() {
+            for (k, word) in next.row_mut(y).iter_mut().enumerate() {
+                let high = row.get(2 * k + 1).copied().unwrap_or(0);
+                *word = row[2 * k] as u64 | (high as u64) << 32;
+            }
+        }
+    }
+    staging.unmap();
+    Some(next)
+}
+
+fn edge_code(edge: Edge) -> u32 {
+    match edge {
+        Edge::Dead => 0,
+        Edge::Wrap => 1,
+        Edge::Mirror => 2,
+        Edge::Twist => 3,
+    }
+}
+
+fn to_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
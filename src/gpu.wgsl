@@ -0,0 +1,90 @@
+// One generation of a Life-like rule on a bit-packed grid: each invocation computes one 32-cell
+// word. Edges are encoded as 0 = dead, 1 = wrap, 2 = mirror, 3 = twist, matching
+// `topology::Edge`, and the rule as the birth and survival masks of `rule::Rule`.
+
+struct Params {
+    width: i32,
+    height: i32,
+    words_per_row: i32,
+    horizontal: u32,
+    vertical: u32,
+    birth: u32,
+    survival: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> cells: array<u32>;
+@group(0) @binding(2) var<storage, read_write> next: array<u32>;
+
+// Neighbor coordinates are at most one cell out of range, so adding one period keeps the
+// operands of % non-negative (the GL backend leaves % of negative numbers undefined)
+fn reflect(x: i32, len: i32) -> i32 {
+    let period = 2 * len;
+    let m = (x + period) % period;
+    return select(period - 1 - m, m, m < len);
+}
+
+fn wrap(x: i32, len: i32) -> i32 {
+    return (x + len) % len;
+}
+
+// 1 if the cell at (x, y) is alive, resolving coordinates beyond the edges like `Topology::resolve`
+fn alive(x0: i32, y0: i32) -> u32 {
+    let w = params.width;
+    let h = params.height;
+    var x = x0;
+    var y = y0;
+    if x < 0 || x >= w {
+        let edge = params.horizontal;
+        if edge == 1u {
+            x = wrap(x, w);
+        } else if edge == 2u {
+            x = reflect(x, w);
+        } else if edge == 3u {
+            x = wrap(x, w);
+            y = h - 1 - y;
+        } else {
+            return 0u;
+        }
+    }
+    if y < 0 || y >= h {
+        let edge = params.vertical;
+        if edge == 1u {
+            y = wrap(y, h);
+        } else if edge == 2u {
+            y = reflect(y, h);
+        } else if edge == 3u {
+            y = wrap(y, h);
+            x = w - 1 - x;
+        } else {
+            return 0u;
+        }
+    }
+    if x < 0 || x >= w || y < 0 || y >= h {
+        return 0u;
+    }
+    return (cells[y * params.words_per_row + x / 32] >> u32(x % 32)) & 1u;
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let word = i32(id.x);
+    if word >= params.words_per_row * params.height {
+        return;
+    }
+    let y = word / params.words_per_row;
+    let first_x = (word % params.words_per_row) * 32;
+
+    var out = 0u;
+    for (var bit = 0; bit < 32 && first_x + bit < params.width; bit++) {
+        let x = first_x + bit;
+        let count = alive(x - 1, y - 1) + alive(x, y - 1) + alive(x + 1, y - 1)
+            + alive(x - 1, y) + alive(x + 1, y)
+            + alive(x - 1, y + 1) + alive(x, y + 1) + alive(x + 1, y + 1);
+        let mask = select(params.birth, params.survival, alive(x, y) == 1u);
+        if ((mask >> count) & 1u) == 1u {
+            out |= 1u << u32(bit);
+        }
+    }
+    next[word] = out;
+}
@@ -0,0 +1,297 @@
+//! Gosper's HashLife: advances an unbounded universe by memoizing the future of every
+//! quadtree node, so repetitive patterns can be pushed through huge numbers of generations.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::quadtree::{NodeId, Quadtree, ALIVE, DEAD};
+use crate::rule::{neighbor_bit, Rule};
+
+/// Rebuild the tree (dropping unreachable nodes and the memo) once it grows past this many nodes.
+const MAX_NODES: usize = 1 << 22;
+
+/// Nodes are never removed from a store, so clones of a `HashLife` (e.g. history snapshots) can
+/// share one and each keep their own root.
+#[derive(Default)]
+struct Store {
+    tree: Quadtree,
+    results: HashMap<(NodeId, u8), NodeId>, // (node, j) -> its center advanced 2^j generations
+    rule: Rule, // the rule the results were computed with
+}
+
+#[derive(Clone)]
+pub struct HashLife {
+    store: Arc<Mutex<Store>>,
+    root: NodeId,
+    origin: (i64, i64), // world coordinates of the root's top-left corner
+    level: u8, // level of the root node
+}
+
+impl PartialEq for HashLife {
+    fn eq(&self, other: &Self) -> bool {
+        let mut a = self.live_cells();
+        let mut b = other.live_cells();
+        a.sort_unstable();
+        b.sort_unstable();
+        a == b
+    }
+}
+
+impl Default for HashLife {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HashLife {
+    pub fn new() -> Self {
+        let mut store = Store::default();
+        let root = store.tree.empty(3);
+        Self {
+            store: Arc::new(Mutex::new(store)),
+            root,
+            origin: (-4, -4),
+            level: 3,
+        }
+    }
+
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap()
+    }
+
+    pub fn from_cells(cells: impl IntoIterator<Item = (i64, i64)>) -> Self {
+        let mut hashlife = Self::new();
+        for (x, y) in cells {
+            hashlife.set(x, y, true);
+        }
+        hashlife
+    }
+
+    pub fn population(&self) -> u64 {
+        self.store().tree.population(self.root)
+    }
+
+    /// Number of distinct quadtree nodes currently stored.
+    pub fn node_count(&self) -> usize {
+        self.store().tree.node_count()
+    }
+
+    fn size(&self) -> i64 {
+        1i64 << self.level
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let (ox, oy) = self.origin;
+        x >= ox && y >= oy && x < ox + self.size() && y < oy + self.size()
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        self.contains(x, y) && self.store().tree.get(self.root, (x - self.origin.0) as u64, (y - self.origin.1) as u64)
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if !alive && !self.contains(x, y) {
+            return;
+        }
+        while !self.contains(x, y) {
+            self.expand();
+        }
+        let (ox, oy) = self.origin;
+        let root = self.root;
+        let new_root = self.store().tree.set(root, (x - ox) as u64, (y - oy) as u64, alive);
+        self.root = new_root;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+        let (ox, oy) = self.origin;
+        self.store()
+            .tree
+            .live_cells(self.root)
+            .into_iter()
+            .map(|(x, y)| (ox + x as i64, oy + y as i64))
+            .collect()
+    }
+
+    /// Advances exactly `generations` generations under the rule, one power of two at a time.
+    /// The rule must not have births from nothing.
+    pub fn advance(&mut self, generations: u64, rule: Rule) {
+        profile_scope!();
+        {
+            // Results remembered under another rule are no use
+            let mut store = self.store();
+            if store.rule != rule {
+                store.results.clear();
+                store.rule = rule;
+            }
+        }
+        for j in 0..64u8 {
+            if generations & (1 << j) != 0 {
+                self.advance_power_of_two(j);
+            }
+        }
+    }
+
+    fn advance_power_of_two(&mut self, j: u8) {
+        if self.node_count() > MAX_NODES {
+            self.collect_garbage();
+        }
+        let rule = self.store().rule;
+
+        // The result only covers the center half of the root, so first pad the root until the
+        // pattern sits in its central quarter and the node is large enough for 2^j steps
+        while self.level < j + 2 || !self.is_centered() {
+            self.expand();
+        }
+        self.expand();
+
+        let quarter = 1i64 << (self.level - 2);
+        let root = self.root;
+        let mut store = self.store();
+        let Store { tree, results, .. } = &mut *store;
+        let next = result(tree, results, rule, root, j);
+        drop(store);
+        self.root = next;
+        self.level -= 1;
+        self.origin = (self.origin.0 + quarter, self.origin.1 + quarter);
+    }
+
+    /// Whether every live cell lies in the central half (by width) of the root.
+    fn is_centered(&self) -> bool {
+        let store = self.store();
+        let tree = &store.tree;
+        let [nw, ne, sw, se] = tree.children(self.root);
+        let center = tree.population(tree.children(nw)[3])
+            + tree.population(tree.children(ne)[2])
+            + tree.population(tree.children(sw)[1])
+            + tree.population(tree.children(se)[0]);
+        center == tree.population(self.root)
+    }
+
+    /// Doubles the root's size, keeping its contents in the middle.
+    fn expand(&mut self) {
+        let (root, level) = (self.root, self.level);
+        let mut store = self.store();
+        let tree = &mut store.tree;
+        let e = tree.empty(level - 1);
+        let [nw, ne, sw, se] = tree.children(root);
+        let new_nw = tree.join(e, e, e, nw);
+        let new_ne = tree.join(e, e, ne, e);
+        let new_sw = tree.join(e, sw, e, e);
+        let new_se = tree.join(se, e, e, e);
+        let new_root = tree.join(new_nw, new_ne, new_sw, new_se);
+        drop(store);
+
+        self.root = new_root;
+        self.level += 1;
+        let half = 1i64 << (level - 1);
+        self.origin = (self.origin.0 - half, self.origin.1 - half);
+    }
+
+    /// Moves this universe into a fresh store holding only its reachable nodes. Other clones
+    /// keep using the old store.
+    fn collect_garbage(&mut self) {
+        let mut fresh = Store { rule: self.store().rule, ..Store::default() };
+        let root = fresh.tree.copy_from(&self.store().tree, self.root, &mut HashMap::new());
+        self.root = root;
+        self.store = Arc::new(Mutex::new(fresh));
+    }
+}
+
+/// The center of a level-k node (k >= 2) advanced 2^j generations, for j <= k - 2.
+fn result(tree: &mut Quadtree, results: &mut HashMap<(NodeId, u8), NodeId>, rule: Rule, id: NodeId, j: u8) -> NodeId {
+    if let Some(&cached) = results.get(&(id, j)) {
+        return cached;
+    }
+
+    let level = tree.level(id);
+    let answer = if tree.population(id) == 0 {
+        tree.empty(level - 1)
+    } else if level == 2 {
+        step_4x4(tree, rule, id)
+    } else {
+        let [nw, ne, sw, se] = tree.children(id);
+        let [_, nw_ne, nw_sw, nw_se] = tree.children(nw);
+        let [ne_nw, _, ne_sw, ne_se] = tree.children(ne);
+        let [sw_nw, sw_ne, _, sw_se] = tree.children(sw);
+        let [se_nw, se_ne, se_sw, _] = tree.children(se);
+
+        // Nine overlapping level k-1 nodes covering the center of this node
+        let n00 = nw;
+        let n01 = tree.join(nw_ne, ne_nw, nw_se, ne_sw);
+        let n02 = ne;
+        let n10 = tree.join(nw_sw, nw_se, sw_nw, sw_ne);
+        let n11 = tree.join(nw_se, ne_sw, sw_ne, se_nw);
+        let n12 = tree.join(ne_sw, ne_se, se_nw, se_ne);
+        let n20 = sw;
+        let n21 = tree.join(sw_ne, se_nw, sw_se, se_sw);
+        let n22 = se;
+
+        // At full speed both halves advance; otherwise the first half just takes the centers
+        let full_speed = j == level - 2;
+        let mut first = |tree: &mut Quadtree, n: NodeId| {
+            if full_speed {
+                result(tree, results, rule, n, j - 1)
+            } else {
+                center(tree, n)
+            }
+        };
+        let c00 = first(tree, n00);
+        let c01 = first(tree, n01);
+        let c02 = first(tree, n02);
+        let c10 = first(tree, n10);
+        let c11 = first(tree, n11);
+        let c12 = first(tree, n12);
+        let c20 = first(tree, n20);
+        let c21 = first(tree, n21);
+        let c22 = first(tree, n22);
+
+        let second_j = if full_speed { j - 1 } else { j };
+        let q_nw = tree.join(c00, c01, c10, c11);
+        let q_ne = tree.join(c01, c02, c11, c12);
+        let q_sw = tree.join(c10, c11, c20, c21);
+        let q_se = tree.join(c11, c12, c21, c22);
+        let r_nw = result(tree, results, rule, q_nw, second_j);
+        let r_ne = result(tree, results, rule, q_ne, second_j);
+        let r_sw = result(tree, results, rule, q_sw, second_j);
+        let r_se = result(tree, results, rule, q_se, second_j);
+        tree.join(r_nw, r_ne, r_sw, r_se)
+    };
+
+    results.insert((id, j), answer);
+    answer
+}
+
+/// The central level k-1 node of a level-k node, without advancing it.
+fn center(tree: &mut Quadtree, id: NodeId) -> NodeId {
+    let [nw, ne, sw, se] = tree.children(id);
+    tree.join(tree.children(nw)[3], tree.children(ne)[2], tree.children(sw)[1], tree.children(se)[0])
+}
+
+/// One generation of the central 2x2 cells of a 4x4 node.
+fn step_4x4(tree: &mut Quadtree, rule: Rule, id: NodeId) -> NodeId {
+    let mut cells = [[false; 4]; 4]; // cells[y][x]
+    for (y, row) in cells.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            *cell = tree.get(id, x as u64, y as u64);
+        }
+    }
+
+    let mut next = [DEAD; 4];
+    for (i, (cx, cy)) in [(1, 1), (2, 1), (1, 2), (2, 2)].into_iter().enumerate() {
+        let mut neighborhood = 0;
+        for dy in 0..3 {
+            for dx in 0..3 {
+                if (dx, dy) != (1, 1) && cells[cy + dy - 1][cx + dx - 1] {
+                    neighborhood |= neighbor_bit(dx as i64 - 1, dy as i64 - 1);
+                }
+            }
+        }
+        let alive = rule.next(cells[cy][cx], neighborhood);
+        next[i] = if alive { ALIVE } else { DEAD };
+    }
+    tree.join(next[0], next[1], next[2], next[3])
+}
@@ -0,0 +1,121 @@
+//! Recent generations of the universe, kept for stepping back and scrubbing through time.
+
+use std::collections::VecDeque;
+
+use crate::universe::Universe;
+
+/// A timeline of snapshots, oldest first, with one of them marked as the one being shown.
+/// Once the snapshots take up more than the memory budget, the oldest are dropped.
+pub struct History {
+    snapshots: VecDeque<Universe>,
+    index: usize,
+    bytes: usize, // estimated memory used by the snapshots
+    max_bytes: usize,
+}
+
+impl History {
+    pub fn new(universe: Universe, max_bytes: usize) -> Self {
+        let mut history = Self { snapshots: VecDeque::new(), index: 0, bytes: 0, max_bytes };
+        history.reset(universe);
+        history
+    }
+
+    /// Forgets everything but the given universe.
+    pub fn reset(&mut self, universe: Universe) {
+        profile_scope!();
+        self.bytes = universe.memory_size();
+        self.snapshots = VecDeque::from([universe]);
+        self.index = 0;
+    }
+
+    /// Records the generation that follows the current one. Anything after the current one
+    /// (left over from scrubbing back) is discarded first.
+    pub fn push(&mut self, universe: Universe) {
+        profile_scope!();
+        for discarded in self.snapshots.drain(self.index + 1..) {
+            self.bytes -= discarded.memory_size();
+        }
+        self.bytes += universe.memory_size();
+        self.snapshots.push_back(universe);
+
+        // Always keep at least the current generation and the one before it
+        while self.bytes > self.max_bytes && self.snapshots.len() > 2 {
+            let dropped = self.snapshots.pop_front().unwrap();
+            self.bytes -= dropped.memory_size();
+        }
+        self.index = self.snapshots.len() - 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get(&self, index: usize) -> &Universe {
+        &self.snapshots[index]
+    }
+
+    /// Makes the snapshot at `index` the current one and returns it.
+    pub fn seek(&mut self, index: usize) -> &Universe {
+        profile_scope!();
+        self.index = index.min(self.snapshots.len() - 1);
+        &self.snapshots[self.index]
+    }
+
+    /// Estimated memory used by all snapshots, in bytes.
+    pub fn memory_size(&self) -> usize {
+        self.bytes
+    }
+}
+
+/// Snapshots of the universe from before each edit, for undo and redo.
+pub struct UndoStack {
+    undo: Vec<Universe>,
+    redo: Vec<Universe>,
+    limit: usize, // most edits that can be undone
+}
+
+impl UndoStack {
+    pub fn new(limit: usize) -> Self {
+        Self { undo: Vec::new(), redo: Vec::new(), limit }
+    }
+
+    /// Remembers the universe as it was before an edit. Edits that were undone can no longer
+    /// be redone after this.
+    pub fn record(&mut self, before: Universe) {
+        self.redo.clear();
+        self.undo.push(before);
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Returns the universe from before the last edit, keeping `current` for redo.
+    pub fn undo(&mut self, current: Universe) -> Option<Universe> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// Returns the universe from before the last undo, keeping `current` for undo.
+    pub fn redo(&mut self, current: Universe) -> Option<Universe> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
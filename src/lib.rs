@@ -1,263 +1,83 @@
-use std::collections::VecDeque;
-use eframe::egui::{self, Color32, Rect, Vec2};
-use eframe::App;
-#[cfg(not(target_arch = "wasm32"))]
-use std::time::Instant;
-#[cfg(not(target_arch = "wasm32"))]
-use once_cell::sync::Lazy;
-
-#[cfg(not(target_arch = "wasm32"))]
-static START_TIME: Lazy<Instant> = Lazy::new(Instant::now);
-
-pub struct GameOfLifeApp {
-    grid_length: usize,
-    grid: Vec<Vec<bool>>, // true for alive, false for dead
-    is_playing: bool, // track if the game is playing, e.g. evolving
-    last_update: f64,
-    update_frequency: f32,
-    history: VecDeque<Vec<Vec<bool>>>,
-    history_index: usize, // Current index in the history
-}
-
-impl GameOfLifeApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        let grid_length = 32;
-        let grid = vec![vec![false; grid_length + 2]; grid_length + 2];
-        let history = VecDeque::new();
-        Self {
-            grid_length,
-            grid,
-            is_playing: false,
-            last_update: get_current_time(),
-            update_frequency: 0.5,
-            history,
-            history_index: 0
-        }
-    }
-
-    fn draw_grid(&mut self, ui: &mut egui::Ui) {
-        let cell_size = 20.0; // size of each cell in the grid
-        let grid_size = cell_size * (self.grid_length as f32); // total size of the grid
-        let (response, painter) = ui.allocate_painter(Vec2::splat(grid_size), egui::Sense::click());
-    
-        // Check for the click and toggle cell state
-        if response.clicked() {
-            if let Some(mouse_pos) = response.interact_pointer_pos() {
-                // Calculate which cell was clicked
-                let x = ((mouse_pos.x - response.rect.left()) / cell_size).floor() as usize;
-                let y = ((mouse_pos.y - response.rect.top()) / cell_size).floor() as usize;
-                if x < self.grid_length && y < self.grid_length {
-                    // Flip the state of the clicked cell
-                    self.grid[x][y] = !self.grid[x][y];
-
-                    // Reset history
-                    self.reset_history();
-                }
-            }
-        }
-
-        // Define the stroke for the grid lines
-        let grid_line_stroke = egui::Stroke::new(1.0, Color32::WHITE);
-
-        // Draw only central part of grid
-        for x in 1..(self.grid_length+1) {
-            for y in 1..(self.grid_length+1) {
-                let rect = Rect::from_min_size(
-                    response.rect.min + Vec2::new(x as f32 * cell_size, y as f32 * cell_size), 
-                    Vec2::splat(cell_size),
-                );
-                let color = if self.grid[x][y] {
-                    Color32::RED // Alive
-                } else {
-                    Color32::BLACK // Dead
-                };
-                painter.rect_filled(rect, 0.0, color);
-                painter.rect_stroke(rect, 0.0, grid_line_stroke); // Grid lines
-            }
-        }
-    }
-
-    fn update_game_state(&mut self) {
-        let mut new_grid = self.grid.clone();
-
-        self.history.push_back(self.grid.clone());
-        self.history_index = self.history.len() - 1;
-        // if self.history.len() > 1024 {
-        //     self.history.pop_front(); // Remove oldest state if history is too long
-        // }
-
-
-        for x in 0..(self.grid_length + 2) {
-            for y in 0..(self.grid_length + 2) {
-                let alive_neighbors = self.count_alive_neighbors(x, y);
-                
-                if self.grid[x][y] {
-                    // Rule for alive cells
-                    new_grid[x][y] = alive_neighbors == 2 || alive_neighbors == 3;
-                } else {
-                    // Rule for dead cells
-                    new_grid[x][y] = alive_neighbors == 3;
-                }
-            }
-        }
-
-        self.grid = new_grid;
-    }
-
-    fn count_alive_neighbors(&self, x: usize, y: usize) -> usize {
-        let mut count = 0;
-
-        for i in 0..3 {
-            for j in 0..3 {
-                if i == 1 && j == 1 { continue; } // Skip the cell itself
-
-                let ni = x as isize + i - 1; // x-index of i-th offset (so for i=0 is left , i=2 is right)
-                let nj = y as isize + j - 1; // y-index of j-th offset (so for i=0 is above, j=2 is below)
-
-                // Check if the neighbor is within grid bounds, including "buffer"
-                if ni >= 0 && ni < (self.grid_length + 2) as isize && nj >= 0 && nj < (self.grid_length + 2) as isize {
-                    if self.grid[ni as usize][nj as usize] {
-                        count += 1;
-                    }
-                }
-            }
-        }
-
-        count
-    }
-
-    fn clear_grid(&mut self) {
-        self.grid = vec![vec![false; self.grid_length + 2]; self.grid_length + 2];
-    }
-
-    fn resize_grid(&mut self, new_size: usize) {
-        let mut new_grid = vec![vec![false; new_size + 2]; new_size + 2];
-
-        for x in 1..=self.grid_length.min(new_size) {
-            for y in 1..=self.grid_length.min(new_size) {
-                new_grid[x][y] = self.grid[x][y];
-            }
-        }
-
-        self.grid = new_grid;
-        self.grid_length = new_size;
-    }
-
-    fn reset_history(&mut self) {
-        self.history.clear();
-        self.history.push_back(self.grid.clone());
-        self.history_index = 0;
-    }
-}
-
-impl App for GameOfLifeApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Conway's Game of Life");
-            self.draw_grid(ui);
-
-            // Use vertical layout for sliders
-            ui.vertical(|ui| {
-                ui.horizontal(|ui| {
-                    ui.label("Update frequency (s):");
-                    ui.add(egui::Slider::new(&mut self.update_frequency, 0.1..=2.0));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Grid Edge Length:");
-                    let mut new_grid_length = self.grid_length as i32;
-                    ui.add(egui::Slider::new(&mut new_grid_length, 10..=100));
-                    if new_grid_length != self.grid_length as i32 {
-                        self.resize_grid(new_grid_length as usize);
-                        self.is_playing = false;
-                    }
-                });
-
-                // Add history slider if there is history
-                if !self.history.is_empty() {
-                    ui.horizontal(|ui| {
-                        ui.label("History:");
-                        let mut history_slider_value = self.history_index as i32;
-                        ui.add(egui::Slider::new(&mut history_slider_value, 0..=self.history.len() as i32 - 1));
-                        if history_slider_value != self.history_index as i32 {
-                            self.history_index = history_slider_value as usize;
-                            self.grid = self.history[self.history_index].clone();
-                        }
-                    });
-                }
-            });
-
-            // Play, Pause, and Clear buttons
-            ui.horizontal(|ui| {
-                if ui.button("Play").clicked() {
-                    self.is_playing = true;
-                }
-                if ui.button("Pause").clicked() {
-                    self.is_playing = false;
-                }
-                if ui.button("Clear").clicked() {
-                    self.clear_grid();
-                    self.is_playing = false;
-                }
-            });
-
-            let now = get_current_time();
-            // Check if more than the update frequency time has passed and game is playing
-            if self.is_playing && (now - self.last_update) >= self.update_frequency as f64 {
-                self.update_game_state();
-                self.last_update = now; // Reset the timer
-            }
-        });
-
-        // Request a repaint
-        ctx.request_repaint();
-    }
-}
-
-
-#[cfg(target_arch = "wasm32")]
-mod wasm {
-    use wasm_bindgen::prelude::*;
-    use super::GameOfLifeApp; // Import our app
-    
-    #[wasm_bindgen]
-    pub struct WebHandle {
-        runner: eframe::WebRunner,
-    }
-
-    #[wasm_bindgen]
-    impl WebHandle {
-        // Installs a panic hook, then returns
-        #[allow(clippy::new_without_default)]
-        #[wasm_bindgen(constructor)]
-        pub fn new() -> Self {
-            // Redirect [`log`] message to `console.log` and friends:
-            eframe::WebLogger::init(log::LevelFilter::Debug).ok();
-
-            Self {
-                runner: eframe::WebRunner::new(),
-            }
-        }
-
-        // Call this once from JavaScript to start the app
-        #[wasm_bindgen]
-        pub async fn start(&self, canvas_id: &str) -> Result<(), wasm_bindgen::JsValue> {
-            self.runner
-                .start(
-                    canvas_id,
-                    eframe::WebOptions::default(),
-                    Box::new(|cc| Box::new(GameOfLifeApp::new(cc))),
-                )
-                .await
-        }
-    }
-}
-
-#[cfg(target_arch = "wasm32")]
-pub fn get_current_time() -> f64 {
-    web_sys::window().unwrap().performance().unwrap().now() / 1000.0
+/// A puffin scope to the end of the block with the `profiling` feature, and nothing without
+/// it: `profile_scope!("step")`, or `profile_scope!()` for one named after the function.
+macro_rules! profile_scope {
+    () => {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+    };
+    ($name:expr) => {
+        #[cfg(feature = "profiling")]
+        puffin::profile_scope!($name);
+    };
 }
 
+// The simulation modules don't need eframe; those marked `gui` make up the app around them
+pub mod ages;
+#[cfg(feature = "gui")]
+pub mod animation;
+pub mod ants;
+#[cfg(feature = "gui")]
+mod app;
+pub mod automaton;
+pub mod benchmark;
+pub mod bitgrid;
+#[cfg(feature = "gui")]
+pub mod camera;
+#[cfg(feature = "gui")]
+pub mod catalog;
 #[cfg(not(target_arch = "wasm32"))]
-pub fn get_current_time() -> f64 {
-    START_TIME.elapsed().as_secs_f64()
-}
\ No newline at end of file
+pub mod cli;
+pub mod cycle;
+#[cfg(feature = "gui")]
+pub mod fetch;
+pub mod formats;
+#[cfg(all(feature = "gpu", not(target_arch = "wasm32")))]
+pub mod gpu;
+pub mod hashlife;
+pub mod history;
+pub mod library;
+pub mod life3d;
+pub mod noise;
+pub mod pattern;
+pub mod perf;
+#[cfg(feature = "gui")]
+pub mod permalink;
+pub mod quadtree;
+pub mod rainbow;
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+pub mod render;
+#[cfg(feature = "gui")]
+pub mod recovery;
+#[cfg(feature = "serde")]
+pub mod replay;
+pub mod rng;
+pub mod rule;
+pub mod rulefile;
+pub mod selection;
+#[cfg(all(feature = "serde", feature = "gui"))]
+pub mod session;
+#[cfg(feature = "gui")]
+pub mod screenshot;
+pub mod shapes;
+pub mod simd;
+pub mod soup;
+pub mod stats;
+#[cfg(feature = "gui")]
+pub mod svg;
+#[cfg(feature = "gui")]
+pub mod theme;
+pub mod topology;
+pub mod tracking;
+#[cfg(all(feature = "tui", not(target_arch = "wasm32")))]
+pub mod tui;
+pub mod universe;
+#[cfg(all(feature = "video", not(target_arch = "wasm32")))]
+pub mod video;
+#[cfg(feature = "gui")]
+pub mod worker;
+
+#[cfg(feature = "gui")]
+pub use app::GameOfLifeApp;
+
+/// Title of the native window, which eframe also names its storage folder after.
+pub const APP_NAME: &str = "Game of Life";
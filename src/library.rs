@@ -0,0 +1,48 @@
+//! Classic patterns shipped with the app, stored as RLE.
+
+use crate::formats::rle;
+use crate::pattern::Pattern;
+
+pub struct LibraryPattern {
+    pub name: &'static str,
+    pub category: &'static str,
+    pub author: &'static str, // who found it, where that is known
+    pub rle: &'static str,
+}
+
+impl LibraryPattern {
+    pub fn pattern(&self) -> Pattern {
+        rle::parse(self.rle).expect("built-in patterns are valid RLE")
+    }
+}
+
+pub const LIBRARY: &[LibraryPattern] = &[
+    LibraryPattern { name: "Glider", category: "Spaceships", author: "Richard K. Guy", rle: "bo$2bo$3o!" },
+    LibraryPattern { name: "LWSS", category: "Spaceships", author: "John Conway", rle: "bo2bo$o$o3bo$4o!" },
+    LibraryPattern { name: "MWSS", category: "Spaceships", author: "John Conway", rle: "3bo$bo3bo$o$o4bo$5o!" },
+    LibraryPattern { name: "HWSS", category: "Spaceships", author: "John Conway", rle: "3b2o$bo4bo$o$o5bo$6o!" },
+    LibraryPattern { name: "Block", category: "Still lifes", author: "", rle: "2o$2o!" },
+    LibraryPattern { name: "Beehive", category: "Still lifes", author: "", rle: "b2o$o2bo$b2o!" },
+    LibraryPattern { name: "Loaf", category: "Still lifes", author: "", rle: "b2o$o2bo$bobo$2bo!" },
+    LibraryPattern { name: "Boat", category: "Still lifes", author: "", rle: "2o$obo$bo!" },
+    LibraryPattern { name: "Eater 1", category: "Still lifes", author: "Bill Gosper", rle: "2o$obo$2bo$2b2o!" },
+    LibraryPattern { name: "Blinker", category: "Oscillators", author: "John Conway", rle: "3o!" },
+    LibraryPattern { name: "Toad", category: "Oscillators", author: "Simon Norton", rle: "b3o$3o!" },
+    LibraryPattern { name: "Beacon", category: "Oscillators", author: "John Conway", rle: "2o$2o$2b2o$2b2o!" },
+    LibraryPattern {
+        name: "Pulsar",
+        category: "Oscillators",
+        author: "John Conway",
+        rle: "2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    },
+    LibraryPattern { name: "Pentadecathlon", category: "Oscillators", author: "John Conway", rle: "2bo4bo$2ob4ob2o$2bo4bo!" },
+    LibraryPattern {
+        name: "Gosper glider gun",
+        category: "Guns",
+        author: "Bill Gosper",
+        rle: "24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!",
+    },
+    LibraryPattern { name: "R-pentomino", category: "Methuselahs", author: "John Conway", rle: "b2o$2o$bo!" },
+    LibraryPattern { name: "Acorn", category: "Methuselahs", author: "Charles Corderman", rle: "bo$3bo$2o2b3o!" },
+    LibraryPattern { name: "Diehard", category: "Methuselahs", author: "", rle: "6bo$2o$bo3b3o!" },
+];
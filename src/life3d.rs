@@ -0,0 +1,241 @@
+//! Three-dimensional Life on a cube of voxels, each with the 26 voxels around it as neighbors,
+//! following Carter Bays' rules: a live voxel survives if its live neighbors are within one
+//! range and a dead one is born if they are within another.
+
+use std::fmt;
+
+use crate::bitgrid::BitGrid;
+use crate::rng::Rng;
+
+/// A 3D rule in Bays' notation E_l E_u F_l F_u: a live voxel with between `survival.0` and
+/// `survival.1` live neighbors survives, and a dead one with between `birth.0` and `birth.1` is
+/// born, both inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule3d {
+    pub survival: (u8, u8),
+    pub birth: (u8, u8),
+}
+
+impl Rule3d {
+    pub const NEIGHBORS: u8 = 26;
+    pub const LIFE_4555: Rule3d = Rule3d { survival: (4, 5), birth: (5, 5) };
+    pub const LIFE_5766: Rule3d = Rule3d { survival: (5, 7), birth: (6, 6) };
+
+    /// Well-known 3D rules, with their names and what to expect.
+    pub const PRESETS: [(&'static str, Rule3d, &'static str); 3] = [
+        ("Life 4555", Rule3d::LIFE_4555, "Bays' first 3D Life, with a glider that moves along an axis"),
+        ("Life 5766", Rule3d::LIFE_5766, "Bays' second 3D Life: sparser, with its own glider"),
+        ("Crystal", Rule3d { survival: (0, 26), birth: (1, 1) }, "Voxels never die and are born next to exactly one live voxel, growing a crystal from a single one"),
+    ];
+
+    /// Name of the matching preset, if any.
+    pub fn name(&self) -> &'static str {
+        Self::PRESETS.iter().find(|(_, rule, _)| rule == self).map_or("Custom", |(name, _, _)| name)
+    }
+
+    /// Whether a voxel is alive next generation.
+    pub fn next(&self, alive: bool, neighbors: u8) -> bool {
+        let (low, high) = if alive { self.survival } else { self.birth };
+        (low..=high).contains(&neighbors)
+    }
+
+    /// Parses Bays' notation: four digits such as `5766`, or four numbers separated by commas or
+    /// slashes such as `10,21,10,21` when some are above 9.
+    pub fn parse(text: &str) -> Result<Rule3d, String> {
+        let text = text.trim();
+        let numbers: Result<Vec<u8>, _> = if text.contains([',', '/']) {
+            text.split([',', '/']).map(|n| n.trim().parse()).collect()
+        } else {
+            text.chars().map(|c| c.to_string().parse()).collect()
+        };
+        match numbers.as_deref() {
+            Ok(&[e_low, e_high, f_low, f_high]) if e_low <= e_high && f_low <= f_high && e_high.max(f_high) <= Self::NEIGHBORS => {
+                Ok(Rule3d { survival: (e_low, e_high), birth: (f_low, f_high) })
+            }
+            _ => Err(format!(
+                "Expected the lowest and highest counts for survival, then for birth, up to {}: \"{text}\"",
+                Self::NEIGHBORS
+            )),
+        }
+    }
+}
+
+/// Bays' four digits, separated by commas when some number has two.
+impl fmt::Display for Rule3d {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ((e_low, e_high), (f_low, f_high)) = (self.survival, self.birth);
+        if e_high.max(f_high) < 10 {
+            write!(f, "{e_low}{e_high}{f_low}{f_high}")
+        } else {
+            write!(f, "{e_low},{e_high},{f_low},{f_high}")
+        }
+    }
+}
+
+/// A `size` x `size` x `size` cube of voxels stored as one bit grid per layer, with voxel
+/// (x, y, z) at (x, y) of layer z. Beyond the faces everything is dead, or with `wrap` the
+/// opposite faces meet.
+#[derive(Clone)]
+pub struct Universe3d {
+    size: usize,
+    layers: Vec<BitGrid>,
+    rule: Rule3d,
+    wrap: bool,
+    generation: u64,
+}
+
+impl Universe3d {
+    pub const MIN_SIZE: usize = 8;
+    pub const MAX_SIZE: usize = 96;
+
+    pub fn new(size: usize) -> Self {
+        let size = size.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+        Self { size, layers: vec![BitGrid::new(size, size); size], rule: Rule3d::LIFE_5766, wrap: true, generation: 0 }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn rule(&self) -> Rule3d {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule3d) {
+        self.rule = rule;
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> bool {
+        self.layers[z].get(x, y)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, alive: bool) {
+        self.layers[z].set(x, y, alive);
+    }
+
+    pub fn toggle(&mut self, x: usize, y: usize, z: usize) {
+        let alive = self.get(x, y, z);
+        self.set(x, y, z, !alive);
+    }
+
+    pub fn clear(&mut self) {
+        self.layers.iter_mut().for_each(BitGrid::clear);
+        self.generation = 0;
+    }
+
+    pub fn population(&self) -> usize {
+        self.layers.iter().map(BitGrid::population).sum()
+    }
+
+    /// Bytes used by the voxels.
+    pub fn memory_size(&self) -> usize {
+        self.layers.iter().map(BitGrid::memory_size).sum()
+    }
+
+    /// The live voxels of layer `z`.
+    pub fn layer(&self, z: usize) -> &BitGrid {
+        &self.layers[z]
+    }
+
+    pub fn live_cells(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        self.layers.iter().enumerate().flat_map(|(z, layer)| layer.live_cells().map(move |(x, y)| (x, y, z)))
+    }
+
+    /// Replaces a cube of side `side` in the middle with random voxels, each alive with
+    /// probability `density`, and clears the rest.
+    pub fn randomize(&mut self, rng: &mut Rng, side: usize, density: f64) {
+        self.clear();
+        let start = (self.size - side.min(self.size)) / 2;
+        let range = start..start + side.min(self.size);
+        for z in range.clone() {
+            for y in range.clone() {
+                for x in range.clone() {
+                    self.set(x, y, z, rng.chance(density));
+                }
+            }
+        }
+    }
+
+    /// The same voxels in a cube of another size, kept centered and cut off where they don't
+    /// fit.
+    pub fn resized(&self, size: usize) -> Universe3d {
+        let mut resized = Universe3d { rule: self.rule, wrap: self.wrap, generation: self.generation, ..Universe3d::new(size) };
+        let shift = resized.size as isize / 2 - self.size as isize / 2;
+        for (x, y, z) in self.live_cells() {
+            let moved = [x, y, z].map(|i| i as isize + shift);
+            if moved.iter().all(|&i| (0..resized.size as isize).contains(&i)) {
+                resized.set(moved[0] as usize, moved[1] as usize, moved[2] as usize, true);
+            }
+        }
+        resized
+    }
+
+    pub fn advance(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+
+    /// Steps one generation. The live voxels in the 3x3 square around each position are counted
+    /// once per layer, and a voxel's neighbors are then the counts of its own layer and the two
+    /// either side, less itself.
+    pub fn step(&mut self) {
+        let n = self.size;
+        let planes: Vec<Vec<u8>> = self.layers.iter().map(|layer| self.plane_counts(layer)).collect();
+        let mut next = vec![BitGrid::new(n, n); n];
+        for (z, layer) in next.iter_mut().enumerate() {
+            let (below, above) = (self.neighbor(z, -1), self.neighbor(z, 1));
+            for y in 0..n {
+                for x in 0..n {
+                    let i = y * n + x;
+                    let alive = self.layers[z].get(x, y);
+                    let around = [below, above].iter().flatten().map(|&z| planes[z][i]).sum::<u8>();
+                    if self.rule.next(alive, planes[z][i] + around - alive as u8) {
+                        layer.set(x, y, true);
+                    }
+                }
+            }
+        }
+        self.layers = next;
+        self.generation += 1;
+    }
+
+    /// The number of live voxels in the 3x3 square around each position of a layer, row by row.
+    fn plane_counts(&self, layer: &BitGrid) -> Vec<u8> {
+        let n = self.size;
+        let mut rows = vec![0u8; n * n];
+        for y in 0..n {
+            for x in 0..n {
+                rows[y * n + x] = (-1..=1).filter_map(|d| self.neighbor(x, d)).map(|x| layer.get(x, y) as u8).sum();
+            }
+        }
+        let mut counts = vec![0u8; n * n];
+        for y in 0..n {
+            for x in 0..n {
+                counts[y * n + x] = (-1..=1).filter_map(|d| self.neighbor(y, d)).map(|y| rows[y * n + x]).sum();
+            }
+        }
+        counts
+    }
+
+    /// The coordinate `delta` away from `i` along an axis, wrapping around if the cube does.
+    fn neighbor(&self, i: usize, delta: isize) -> Option<usize> {
+        let j = i as isize + delta;
+        match self.wrap {
+            true => Some(j.rem_euclid(self.size as isize) as usize),
+            false => (0..self.size as isize).contains(&j).then_some(j as usize),
+        }
+    }
+}
@@ -1,10 +1,119 @@
-use game_of_life::GameOfLifeApp;
+use clap::{Parser, Subcommand};
+#[cfg(feature = "gui")]
+use game_of_life::{GameOfLifeApp, APP_NAME};
+use game_of_life::rule::Rule;
+use game_of_life::topology::Topology;
+
+/// Conway's Game of Life and its relatives. Without a subcommand it opens the app, set up as
+/// the flags below ask on top of where the last run left off.
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Finite grid size in cells, such as 200x150
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = game_of_life::cli::parse_size, conflicts_with = "infinite")]
+    size: Option<(usize, usize)>,
+    /// Start in an infinite universe
+    #[arg(long)]
+    infinite: bool,
+    /// Rule to run, in B/S or another notation the app reads
+    #[arg(long, value_parser = Rule::parse)]
+    rule: Option<Rule>,
+    /// Seconds between generations, from 0.1 to 2
+    #[arg(long, value_name = "SECONDS")]
+    update_frequency: Option<f32>,
+    /// What lies beyond the grid's edges: plane, torus, klein-bottle, cross-surface, mirror-box,
+    /// one edge for every side (dead, wrap, mirror, twist), or left/right and top/bottom edges
+    /// apart, as in wrap,twist
+    #[arg(long, value_name = "NAME", value_parser = Topology::parse)]
+    boundary: Option<Topology>,
+    /// Pattern file to open
+    #[arg(long, value_name = "FILE")]
+    pattern: Option<String>,
+    /// Start playing straight away
+    #[arg(long)]
+    play: bool,
+}
+
+// `game_of_life run ...`, `bench ...`, `soups ...`, `render ...` and `tui ...` work without a window
+#[derive(Subcommand, Debug)]
+enum Command {
+    Run(game_of_life::cli::RunArgs),
+    Bench(game_of_life::benchmark::BenchArgs),
+    Soups(game_of_life::soup::SoupArgs),
+    #[cfg(feature = "gui")]
+    Render(game_of_life::render::RenderArgs),
+    #[cfg(feature = "tui")]
+    Tui(game_of_life::tui::TuiArgs),
+}
 
 fn main() {
-    let native_options = eframe::NativeOptions::default();
-    let _ = eframe::run_native(
-        "Game of Life",
-        native_options,
-        Box::new(|cc| Box::new(GameOfLifeApp::new(cc)))
-    );
+    let cli = Cli::parse();
+    if let Some(command) = cli.command {
+        let result = match command {
+            Command::Run(args) => game_of_life::cli::run(args),
+            Command::Bench(args) => game_of_life::benchmark::bench(args),
+            Command::Soups(args) => game_of_life::soup::run(args),
+            #[cfg(feature = "gui")]
+            Command::Render(args) => game_of_life::render::run(args),
+            #[cfg(feature = "tui")]
+            Command::Tui(args) => game_of_life::tui::run(args),
+        };
+        // Status goes to stderr, as stdout may carry the pattern
+        match result {
+            Ok(done) => eprintln!("{done}"),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    #[cfg(feature = "gui")]
+    {
+        let native_options = eframe::NativeOptions {
+            follow_system_theme: true,
+            ..Default::default()
+        };
+        let _ = eframe::run_native(
+            APP_NAME,
+            native_options,
+            Box::new(move |cc| Box::new(configure(GameOfLifeApp::new(cc), cli)))
+        );
+    }
+    #[cfg(not(feature = "gui"))]
+    {
+        eprintln!("This build has no window, only the headless runner: see game_of_life run --help");
+        std::process::exit(1);
+    }
+}
+
+/// Applies the startup flags over the restored session, the grid first so a pattern lands in it.
+#[cfg(feature = "gui")]
+fn configure(mut app: GameOfLifeApp, cli: Cli) -> GameOfLifeApp {
+    if let Some((width, height)) = cli.size {
+        app = app.with_grid_size(width, height);
+    }
+    if cli.infinite {
+        app = app.with_infinite();
+    }
+    if let Some(topology) = cli.boundary {
+        app = app.with_topology(topology);
+    }
+    if let Some(seconds) = cli.update_frequency {
+        app = app.with_update_frequency(seconds);
+    }
+    if let Some(path) = &cli.pattern {
+        app = app.with_pattern_file(path);
+    }
+    // After the pattern, so that the rule given wins over the one the file names
+    if let Some(rule) = cli.rule {
+        app = app.with_rule(rule);
+    }
+    if cli.play {
+        app = app.with_playing(true);
+    }
+    app
 }
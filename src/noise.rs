@@ -0,0 +1,24 @@
+//! Randomness on top of a rule: outcomes that go the other way now and then, like a
+//! temperature, and cells flipped at random. The random numbers come from a seed and the
+//! generation, so that a run from the same cells with the same seed comes out the same.
+
+use crate::rng::Rng;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Noise {
+    pub temperature: f64, // probability that a cell alive or next to a live one does the opposite of what the rule says
+    pub flips: f64, // fraction of the cells of a finite grid flipped each generation
+    pub seed: u64,
+}
+
+impl Noise {
+    pub fn is_active(&self) -> bool {
+        self.temperature > 0.0 || self.flips > 0.0
+    }
+
+    /// The random numbers for stepping to `generation`, the same every time for the same seed.
+    pub fn rng(&self, generation: u64) -> Rng {
+        Rng::new(self.seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ generation)
+    }
+}
@@ -0,0 +1,85 @@
+/// A set of live cells cropped to their bounding box, independent of any grid.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<(usize, usize)>, // (x, y) offsets of live cells, relative to the top-left corner
+}
+
+impl Pattern {
+    /// Crops an arbitrary list of live cells (which may have negative coordinates) to their bounding box.
+    /// Panics if the cells are further apart than `i64` can count, which `try_from_cells` reports instead.
+    pub fn from_cells(live: Vec<(i64, i64)>) -> Self {
+        Self::try_from_cells(live).expect("the cells are too far apart")
+    }
+
+    /// Like `from_cells`, for cells from outside the app, which may be anywhere.
+    pub fn try_from_cells(live: Vec<(i64, i64)>) -> Result<Self, String> {
+        if live.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let min_x = live.iter().map(|&(x, _)| x).min().unwrap();
+        let min_y = live.iter().map(|&(_, y)| y).min().unwrap();
+        let max_x = live.iter().map(|&(x, _)| x).max().unwrap();
+        let max_y = live.iter().map(|&(_, y)| y).max().unwrap();
+        let side = |min: i64, max: i64| max.checked_sub(min).and_then(|span| span.checked_add(1)).ok_or("The cells are too far apart");
+        let (width, height) = (side(min_x, max_x)?, side(min_y, max_y)?);
+
+        let mut cells: Vec<(usize, usize)> = live
+            .into_iter()
+            .map(|(x, y)| ((x - min_x) as usize, (y - min_y) as usize))
+            .collect();
+        cells.sort_by_key(|&(x, y)| (y, x)); // row-major order, which is what the text formats want
+        cells.dedup();
+
+        Ok(Self { width: width as usize, height: height as usize, cells })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Builds a pattern from rows of booleans, `rows[y][x]`, cropping away empty margins.
+    pub fn from_rows(rows: &[Vec<bool>]) -> Self {
+        let mut live = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &alive) in row.iter().enumerate() {
+                if alive {
+                    live.push((x as i64, y as i64));
+                }
+            }
+        }
+        Self::from_cells(live)
+    }
+
+    /// Returns the pattern as rows of booleans, `rows[y][x]`.
+    pub fn to_rows(&self) -> Vec<Vec<bool>> {
+        let mut rows = vec![vec![false; self.width]; self.height];
+        for &(x, y) in &self.cells {
+            rows[y][x] = true;
+        }
+        rows
+    }
+
+    /// Rotates the pattern 90° clockwise.
+    pub fn rotate_clockwise(&self) -> Self {
+        self.map(self.height, self.width, |x, y| (self.height - 1 - y, x))
+    }
+
+    /// Mirrors the pattern left-to-right.
+    pub fn flip_horizontal(&self) -> Self {
+        self.map(self.width, self.height, |x, y| (self.width - 1 - x, y))
+    }
+
+    /// Mirrors the pattern top-to-bottom.
+    pub fn flip_vertical(&self) -> Self {
+        self.map(self.width, self.height, |x, y| (x, self.height - 1 - y))
+    }
+
+    fn map(&self, width: usize, height: usize, f: impl Fn(usize, usize) -> (usize, usize)) -> Self {
+        let mut cells: Vec<(usize, usize)> = self.cells.iter().map(|&(x, y)| f(x, y)).collect();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        Self { width, height, cells }
+    }
+}
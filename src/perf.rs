@@ -0,0 +1,74 @@
+//! Timings for the performance overlay: frames and generations a second, and how long stepping
+//! and painting take, over the last second.
+
+use std::collections::VecDeque;
+
+const WINDOW: f64 = 1.0; // Seconds of samples kept
+
+/// Values seen over the last second, each with the time it was seen at.
+#[derive(Default)]
+struct Samples(VecDeque<(f64, f64)>);
+
+impl Samples {
+    fn add(&mut self, now: f64, value: f64) {
+        self.0.push_back((now, value));
+        while self.0.front().is_some_and(|&(time, _)| time < now - WINDOW) {
+            self.0.pop_front();
+        }
+    }
+
+    fn recent(&self, now: f64) -> impl Iterator<Item = f64> + '_ {
+        self.0.iter().filter(move |&&(time, _)| time >= now - WINDOW).map(|&(_, value)| value)
+    }
+
+    /// The sum of the values seen in the last second.
+    fn per_second(&self, now: f64) -> f64 {
+        self.recent(now).sum()
+    }
+
+    fn mean(&self, now: f64) -> Option<f64> {
+        let count = self.recent(now).count();
+        (count > 0).then(|| self.per_second(now) / count as f64)
+    }
+}
+
+/// Frame and step timings, all in seconds from the same clock.
+#[derive(Default)]
+pub struct PerfStats {
+    frames: Samples,
+    generations: Samples,   // generations each step advanced
+    step_seconds: Samples,  // time each step took to compute
+    paint_seconds: Samples, // time each frame spent drawing the universe
+}
+
+impl PerfStats {
+    /// Notes a frame that started at `now` and spent `paint` seconds drawing the universe.
+    pub fn frame(&mut self, now: f64, paint: f64) {
+        self.frames.add(now, 1.0);
+        self.paint_seconds.add(now, paint);
+    }
+
+    /// Notes a step that finished at `now`, having advanced `generations` in `seconds`.
+    pub fn step(&mut self, now: f64, generations: u64, seconds: f64) {
+        self.generations.add(now, generations as f64);
+        self.step_seconds.add(now, seconds);
+    }
+
+    pub fn frames_per_second(&self, now: f64) -> f64 {
+        self.frames.per_second(now)
+    }
+
+    pub fn generations_per_second(&self, now: f64) -> f64 {
+        self.generations.per_second(now)
+    }
+
+    /// Average time a step took to compute, over the steps of the last second.
+    pub fn step_time(&self, now: f64) -> Option<f64> {
+        self.step_seconds.mean(now)
+    }
+
+    /// Average time a frame spent drawing the universe, over the last second.
+    pub fn paint_time(&self, now: f64) -> Option<f64> {
+        self.paint_seconds.mean(now)
+    }
+}
@@ -0,0 +1,64 @@
+//! Links that carry a board in their fragment, `#life=` and then the pattern as RLE with its
+//! rule, deflated and in URL-safe base64, so a board can be shared without a server.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+
+use crate::formats::{rle, Limits};
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+
+pub const PREFIX: &str = "#life=";
+const MAX_TEXT_BYTES: usize = 64 << 20; // Largest text anything may inflate to
+/// What a link may hold. Links come from anyone, so they get far less room than files.
+const LINK_LIMITS: Limits = Limits { cells: 1 << 22, side: 1 << 14 };
+
+/// The fragment, starting with `#`, for a pattern under a rule.
+pub fn encode(pattern: &Pattern, rule: Rule) -> String {
+    format!("{PREFIX}{}", deflate(&rle::write(pattern, rule)))
+}
+
+/// The pattern and rule in a link, or in its fragment alone. Links without one give `None`.
+pub fn decode(link: &str) -> Option<Result<(Pattern, Rule), String>> {
+    let (_, encoded) = link.split_once(PREFIX)?;
+    Some(unpack(encoded.trim()))
+}
+
+fn unpack(encoded: &str) -> Result<(Pattern, Rule), String> {
+    let text = inflate(encoded).map_err(|e| format!("The link is broken: {e}"))?;
+    let rule = rle::header_rule(&text).unwrap_or(Ok(Rule::default()))?;
+    let pattern = rle::parse_within(&text, LINK_LIMITS).map_err(|e| format!("The link is too large to open: {e}"))?;
+    Ok((pattern, rule))
+}
+
+/// Text deflated and written in URL-safe base64.
+pub fn deflate(text: &str) -> String {
+    URL_SAFE_NO_PAD.encode(miniz_oxide::deflate::compress_to_vec(text.as_bytes(), 9))
+}
+
+/// Text back from `deflate`.
+pub fn inflate(encoded: &str) -> Result<String, String> {
+    let packed = URL_SAFE_NO_PAD.decode(encoded).map_err(|e| e.to_string())?;
+    let text = miniz_oxide::inflate::decompress_to_vec_with_limit(&packed, MAX_TEXT_BYTES).map_err(|e| e.to_string())?;
+    String::from_utf8(text).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_links() {
+        let glider = Pattern { width: 3, height: 3, cells: vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] };
+        let rule = Rule::parse("B36/S23").unwrap();
+        assert_eq!(decode(&format!("https://example.org/{}", encode(&glider, rule))), Some(Ok((glider, rule))));
+    }
+
+    #[test]
+    fn rejects_links_past_the_limits() {
+        let wide = format!("{PREFIX}{}", deflate(&format!("{}bo!", 1 << 14)));
+        assert!(decode(&wide).unwrap().is_err());
+        let crowded = format!("{PREFIX}{}", deflate(&"4096o$".repeat(1025)));
+        assert!(decode(&crowded).unwrap().is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Hash-consed quadtree of cells, for patterns too large or sparse for a dense grid.
+//!
+//! Every node is stored once in an arena and identified by its index, so identical subtrees
+//! (most importantly, the empty ones) are shared.
+
+use std::collections::HashMap;
+
+use crate::pattern::Pattern;
+
+pub type NodeId = usize;
+
+/// The two level-0 nodes, i.e. single cells.
+pub const DEAD: NodeId = 0;
+pub const ALIVE: NodeId = 1;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Node {
+    pub level: u8, // the node covers a 2^level x 2^level square
+    pub children: [NodeId; 4], // nw, ne, sw, se; unused for level 0
+    pub population: u64,
+}
+
+pub struct Quadtree {
+    nodes: Vec<Node>,
+    lookup: HashMap<[NodeId; 4], NodeId>,
+    empty: Vec<NodeId>, // empty[level] is the all-dead node of that level
+}
+
+impl Default for Quadtree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Quadtree {
+    pub fn new() -> Self {
+        let leaf = |population| Node { level: 0, children: [DEAD; 4], population };
+        Self {
+            nodes: vec![leaf(0), leaf(1)],
+            lookup: HashMap::new(),
+            empty: vec![DEAD],
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn level(&self, id: NodeId) -> u8 {
+        self.nodes[id].level
+    }
+
+    pub fn population(&self, id: NodeId) -> u64 {
+        self.nodes[id].population
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id].children
+    }
+
+    /// State of the cell at (x, y) relative to the node's top-left corner.
+    pub fn get(&self, id: NodeId, x: u64, y: u64) -> bool {
+        let node = &self.nodes[id];
+        if node.population == 0 {
+            return false;
+        }
+        if node.level == 0 {
+            return true;
+        }
+        let half = 1u64 << (node.level - 1);
+        let index = (y >= half) as usize * 2 + (x >= half) as usize;
+        self.get(node.children[index], x % half, y % half)
+    }
+
+    /// Returns a copy of the node with the cell at (x, y) set, sharing every untouched subtree.
+    pub fn set(&mut self, id: NodeId, x: u64, y: u64, alive: bool) -> NodeId {
+        let node = self.nodes[id];
+        if node.level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1u64 << (node.level - 1);
+        let index = (y >= half) as usize * 2 + (x >= half) as usize;
+        let mut children = node.children;
+        children[index] = self.set(children[index], x % half, y % half, alive);
+        self.join(children[0], children[1], children[2], children[3])
+    }
+
+    /// Copies a node and everything below it out of another tree, returning its id in this one.
+    pub fn copy_from(&mut self, other: &Quadtree, id: NodeId, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if id == DEAD || id == ALIVE {
+            return id;
+        }
+        if let Some(&new_id) = copied.get(&id) {
+            return new_id;
+        }
+        let [nw, ne, sw, se] = other.children(id).map(|c| self.copy_from(other, c, copied));
+        let new_id = self.join(nw, ne, sw, se);
+        copied.insert(id, new_id);
+        new_id
+    }
+
+    /// Returns the node with the given children, creating it only if it doesn't exist yet.
+    pub fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        self.try_join(nw, ne, sw, se).expect("the population is too large to count")
+    }
+
+    /// Like `join`, but `None` if the node would have more live cells than a `u64` can count,
+    /// which only nodes read from outside the app can.
+    pub fn try_join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> Option<NodeId> {
+        let children = [nw, ne, sw, se];
+        if let Some(&id) = self.lookup.get(&children) {
+            return Some(id);
+        }
+        let population = children.iter().try_fold(0u64, |sum, &c| sum.checked_add(self.nodes[c].population))?;
+        let node = Node { level: self.nodes[nw].level + 1, children, population };
+        let id = self.nodes.len();
+        self.nodes.push(node);
+        self.lookup.insert(children, id);
+        Some(id)
+    }
+
+    pub fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let next = self.join(e, e, e, e);
+            self.empty.push(next);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Builds a node of the given level from live cells with coordinates inside it.
+    pub fn build(&mut self, level: u8, cells: &[(u64, u64)]) -> NodeId {
+        if cells.is_empty() {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return ALIVE;
+        }
+
+        let half = 1u64 << (level - 1);
+        let mut quadrants: [Vec<(u64, u64)>; 4] = Default::default();
+        for &(x, y) in cells {
+            let index = (y >= half) as usize * 2 + (x >= half) as usize;
+            quadrants[index].push((x % half, y % half));
+        }
+        let [nw, ne, sw, se] = quadrants.map(|q| self.build(level - 1, &q));
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Builds a node just large enough to hold the pattern, anchored at its top-left corner.
+    pub fn from_pattern(&mut self, pattern: &Pattern, min_level: u8) -> NodeId {
+        let mut level = min_level;
+        while (1usize << level) < pattern.width.max(pattern.height) {
+            level += 1;
+        }
+        let cells: Vec<(u64, u64)> = pattern.cells.iter().map(|&(x, y)| (x as u64, y as u64)).collect();
+        self.build(level, &cells)
+    }
+
+    /// Collects the live cells of a node, relative to its top-left corner.
+    pub fn live_cells(&self, id: NodeId) -> Vec<(u64, u64)> {
+        let mut cells = Vec::new();
+        self.collect(id, 0, 0, &mut cells);
+        cells
+    }
+
+    fn collect(&self, id: NodeId, x: u64, y: u64, cells: &mut Vec<(u64, u64)>) {
+        let node = &self.nodes[id];
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            cells.push((x, y));
+            return;
+        }
+        let half = 1u64 << (node.level - 1);
+        let [nw, ne, sw, se] = node.children;
+        self.collect(nw, x, y, cells);
+        self.collect(ne, x + half, y, cells);
+        self.collect(sw, x, y + half, cells);
+        self.collect(se, x + half, y + half, cells);
+    }
+
+    pub fn to_pattern(&self, id: NodeId) -> Pattern {
+        Pattern::from_cells(self.live_cells(id).into_iter().map(|(x, y)| (x as i64, y as i64)).collect())
+    }
+}
@@ -0,0 +1,103 @@
+//! Colors carried by live cells for Rainbow Life: each newborn gets the average color of its
+//! parents, so colonies blend into each other where they meet. The colors are only for show and
+//! don't change which cells live or die.
+
+use std::collections::HashMap;
+
+/// The RGB color of every live cell, keyed by its coordinates, and the color of cells drawn in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rainbow {
+    colors: HashMap<(i64, i64), [u8; 3]>,
+    brush: [u8; 3],
+}
+
+impl Rainbow {
+    /// Colors for the given live cells, all in the brush color.
+    pub fn new(live: impl Iterator<Item = (i64, i64)>, brush: [u8; 3]) -> Self {
+        Self { colors: live.map(|cell| (cell, brush)).collect(), brush }
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> Option<[u8; 3]> {
+        self.colors.get(&(x, y)).copied()
+    }
+
+    /// The live cells with their colors.
+    pub fn colors(&self) -> impl Iterator<Item = ((i64, i64), [u8; 3])> + '_ {
+        self.colors.iter().map(|(&cell, &color)| (cell, color))
+    }
+
+    pub fn brush(&self) -> [u8; 3] {
+        self.brush
+    }
+
+    pub fn set_brush(&mut self, brush: [u8; 3]) {
+        self.brush = brush;
+    }
+
+    /// Follows a cell being set by hand: a cell brought to life takes the brush color, even if
+    /// it already was alive.
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if alive {
+            self.colors.insert((x, y), self.brush);
+        } else {
+            self.colors.remove(&(x, y));
+        }
+    }
+
+    /// Recolors a live cell.
+    pub fn recolor(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if let Some(old) = self.colors.get_mut(&(x, y)) {
+            *old = color;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.colors.clear();
+    }
+
+    /// Moves on to the given live cells. Cells alive before keep their colors, and the rest get
+    /// the average color of those of their neighbors, listed by `neighbors`, that were alive,
+    /// or the brush color if none were. This is exact for a single generation and an
+    /// approximation for a jump over several.
+    pub fn advance(&mut self, live: impl Iterator<Item = (i64, i64)>, neighbors: impl Fn((i64, i64)) -> Vec<(i64, i64)>) {
+        let colors = live
+            .map(|cell| {
+                let color = self.colors.get(&cell).copied().unwrap_or_else(|| {
+                    let parents: Vec<[u8; 3]> = neighbors(cell).iter().filter_map(|parent| self.colors.get(parent).copied()).collect();
+                    average(&parents).unwrap_or(self.brush)
+                });
+                (cell, color)
+            })
+            .collect();
+        self.colors = colors;
+    }
+
+    /// A copy with every cell moved by (dx, dy), keeping only those where `keep` holds.
+    pub fn shifted(&self, dx: i64, dy: i64, keep: impl Fn(i64, i64) -> bool) -> Self {
+        let colors = self.colors.iter().map(|(&(x, y), &color)| ((x + dx, y + dy), color)).filter(|&((x, y), _)| keep(x, y)).collect();
+        Self { colors, brush: self.brush }
+    }
+
+    /// Bytes used by the colors.
+    pub fn memory_size(&self) -> usize {
+        self.colors.capacity() * std::mem::size_of::<((i64, i64), [u8; 3])>()
+    }
+}
+
+/// The fully saturated color `turns` of the way around the color wheel from red.
+pub fn hue(turns: f32) -> [u8; 3] {
+    let channel = |offset: f32| {
+        let distance = ((turns - offset).rem_euclid(1.0) - 0.5).abs() * 6.0; // 3 at the channel's own hue, 0 opposite it
+        ((distance - 1.0).clamp(0.0, 1.0) * 255.0).round() as u8
+    };
+    [channel(0.0), channel(1.0 / 3.0), channel(2.0 / 3.0)]
+}
+
+/// The mean of some colors, channel by channel, or `None` if there are none.
+fn average(colors: &[[u8; 3]]) -> Option<[u8; 3]> {
+    if colors.is_empty() {
+        return None;
+    }
+    let mean = |channel: usize| (colors.iter().map(|color| color[channel] as u32).sum::<u32>() as f32 / colors.len() as f32).round() as u8;
+    Some([mean(0), mean(1), mean(2)])
+}
@@ -0,0 +1,47 @@
+//! Crash recovery: a snapshot of the session taken every so often while the app runs and
+//! whenever eframe saves. After a normal exit the snapshot matches the session eframe saved
+//! last, so one that doesn't was left by a crash, with newer work in it. Native builds keep
+//! the snapshot in a file beside eframe's storage, the web build in local storage.
+
+/// Seconds between snapshots.
+pub const INTERVAL: f64 = 10.0;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path() -> Option<std::path::PathBuf> {
+    eframe::storage_dir(crate::APP_NAME).map(|dir| dir.join("recovery.ron"))
+}
+
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "game_of_life_recovery";
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Replaces the snapshot.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(text: &str) -> Result<(), String> {
+    let path = path().ok_or("No folder to keep it in")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, text).map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write(text: &str) -> Result<(), String> {
+    let storage = local_storage().ok_or("No local storage")?;
+    storage.set_item(STORAGE_KEY, text).map_err(|_| "Local storage is full".to_string())
+}
+
+/// The last snapshot, if there is one.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read() -> Option<String> {
+    std::fs::read_to_string(path()?).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read() -> Option<String> {
+    local_storage()?.get_item(STORAGE_KEY).ok()?
+}
@@ -0,0 +1,51 @@
+//! `game_of_life render`: runs a pattern file for a number of generations in an infinite
+//! universe and writes every generation to an animation or video, without opening a window.
+
+use clap::Args;
+
+use crate::animation::AnimationFormat;
+use crate::rule::Rule;
+use crate::theme::Theme;
+
+/// Writes every generation of a run to an animation or video.
+#[derive(Args, Debug)]
+pub struct RenderArgs {
+    /// Pattern file to start from
+    pattern: String,
+    /// Generations to run, each a frame after the first
+    generations: u64,
+    /// File ending in .gif, .png (APNG), .webm or .mp4; videos need the video feature and ffmpeg
+    output: String,
+    /// Rule to run, in B/S or another notation the app reads; by default the one the pattern
+    /// file names, or Conway's Life
+    #[arg(long, value_parser = Rule::parse)]
+    rule: Option<Rule>,
+    /// Pixels a cell
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    scale: u32,
+    /// Milliseconds each frame shows for
+    #[arg(long, value_name = "MS", default_value_t = 100, value_parser = clap::value_parser!(u16).range(1..))]
+    delay: u16,
+}
+
+/// Renders as the `render` subcommand asks, returning what was done.
+pub fn run(args: RenderArgs) -> Result<String, String> {
+    let RenderArgs { pattern: pattern_path, generations, output, rule, scale, delay } = args;
+    let output = output.as_str();
+    let format = AnimationFormat::from_path(output).ok_or(format!("Can't tell the format of {output}: it ends in .gif, .png, .webm or .mp4"))?;
+    if !format.is_available() {
+        return Err(format!("This build can't write a {}: it needs the video feature", format.name()));
+    }
+
+    let universe = crate::cli::load(&pattern_path, rule)?;
+
+    // A first run finds the area every frame has to show, and a second draws the frames
+    let frames = || std::iter::once(universe.clone()).chain(universe.generations().take(generations as usize));
+    let area = crate::app::picture_area(frames()).ok_or("The pattern is empty")?;
+    let scale = crate::app::fit_scale(scale as usize, area)?;
+    let theme = Theme::for_dark_mode(true);
+    let pictures = frames().map(|universe| crate::app::draw_picture(&theme, &universe, area, scale, (false, 0, 0)));
+    let count = generations as usize + 1;
+    format.save(output, [area.2 * scale, area.3 * scale], count, pictures, delay)?;
+    Ok(format!("Saved {count} frames of {}x{} cells to {output} as {}", area.2, area.3, format.name()))
+}
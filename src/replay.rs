@@ -0,0 +1,160 @@
+//! Replays: a universe to start from and everything done to it after, each change stamped with
+//! the generation it was made at, so that a run can be played back exactly. Edits are kept as
+//! the cells they changed, whatever tool made them, and anything the cells can't be patched
+//! into, such as a resize or a step back, starts over from a copy of the whole universe. Rule
+//! files and ants aren't kept.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::noise::Noise;
+use crate::rule::Rule;
+use crate::universe::Universe;
+
+/// Something that happened before a step.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Event {
+    Start(Box<Universe>), // everything replaced, which also sets the generation
+    Rule(Rule),
+    Noise(Noise),
+    Cells(Vec<(i64, i64, u8)>), // cells put in new states, as (x, y, state)
+}
+
+/// The events in the order they happened, with the generation each happened at, and the
+/// generation the recording stopped at.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub events: Vec<(u64, Event)>,
+    pub end: u64,
+}
+
+impl Replay {
+    pub fn to_text(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let replay: Replay = ron::from_str(text).map_err(|e| e.to_string())?;
+        match replay.events.first() {
+            Some((_, Event::Start(_))) => Ok(replay),
+            _ => Err("The replay doesn't start with a universe".to_string()),
+        }
+    }
+}
+
+/// Builds a replay from the universe as it is about to step, before each step.
+pub struct Recorder {
+    replay: Replay,
+    last: Option<Universe>, // the universe as the replay has it, after the events so far
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self { replay: Replay::default(), last: None }
+    }
+
+    /// Notes whatever changed since the last step, just before `universe` steps.
+    pub fn before_step(&mut self, universe: &Universe) {
+        let at = universe.generation();
+        let events = &mut self.replay.events;
+        match &self.last {
+            Some(last) if same_grid(last, universe) => {
+                if universe.rule() != last.rule() {
+                    events.push((at, Event::Rule(universe.rule())));
+                }
+                if universe.noise() != last.noise() {
+                    events.push((at, Event::Noise(universe.noise())));
+                }
+                if universe.state_hash() != last.state_hash() {
+                    events.push((at, Event::Cells(changed_cells(last, universe))));
+                }
+            }
+            // Saved universes have no noise
+            _ => {
+                events.push((at, Event::Start(Box::new(universe.clone()))));
+                if universe.noise() != Noise::default() {
+                    events.push((at, Event::Noise(universe.noise())));
+                }
+            }
+        }
+        self.last = Some(universe.clone());
+        self.replay.end = at;
+    }
+
+    /// Takes the universe a step ended with as the new starting point.
+    pub fn after_step(&mut self, universe: &Universe) {
+        self.replay.end = universe.generation();
+        self.last = Some(universe.clone());
+    }
+
+    pub fn generations(&self) -> u64 {
+        self.replay.events.first().map_or(0, |(start, _)| self.replay.end.saturating_sub(*start))
+    }
+
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hands out the events of a replay as playback reaches their generations.
+pub struct Player {
+    events: std::vec::IntoIter<(u64, Event)>,
+    next: Option<(u64, Event)>,
+    end: u64,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        let mut events = replay.events.into_iter();
+        let next = events.next();
+        Self { events, next, end: replay.end }
+    }
+
+    /// The next event, if it is due by generation `at`.
+    pub fn next_due(&mut self, at: u64) -> Option<Event> {
+        match &self.next {
+            Some((due, _)) if *due <= at => {
+                let (_, event) = std::mem::replace(&mut self.next, self.events.next())?;
+                Some(event)
+            }
+            _ => None,
+        }
+    }
+
+    /// Generations from `at` to the next event, or to the end once there are none.
+    pub fn generations_left(&self, at: u64) -> u64 {
+        self.next.as_ref().map_or(self.end, |(due, _)| *due).saturating_sub(at)
+    }
+
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    pub fn is_finished(&self, at: u64) -> bool {
+        self.next.is_none() && at >= self.end
+    }
+}
+
+/// Whether the universes have the same size, edges and generation, so that one can be patched
+/// into the other cell by cell.
+fn same_grid(a: &Universe, b: &Universe) -> bool {
+    let size = |u: &Universe| (u.width(), u.height(), u.is_infinite(), u.topology());
+    size(a) == size(b) && a.generation() == b.generation()
+}
+
+/// The cells of `now` whose states differ from those in `before`, in order.
+fn changed_cells(before: &Universe, now: &Universe) -> Vec<(i64, i64, u8)> {
+    let states = |u: &Universe| -> HashMap<(i64, i64), u8> { u.live_cells().map(|cell| (cell, 1)).chain(u.higher_states()).collect() };
+    let (before, now) = (states(before), states(now));
+    let mut changed: Vec<(i64, i64, u8)> = now.iter().filter(|&(cell, state)| before.get(cell) != Some(state)).map(|(&(x, y), &state)| (x, y, state)).collect();
+    changed.extend(before.keys().filter(|cell| !now.contains_key(cell)).map(|&(x, y)| (x, y, 0)));
+    changed.sort_unstable();
+    changed
+}
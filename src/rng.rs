@@ -0,0 +1,44 @@
+//! A small, fast pseudo-random number generator (xorshift64*) for random fills. It is not
+//! meant for anything that needs good statistical or cryptographic quality.
+
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Scramble the seed (splitmix64) so that similar seeds give unrelated sequences; the
+        // state must never be zero
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Self { state: (z ^ (z >> 31)).max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number in `0.0..1.0`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// True with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+
+    /// The number of failures before the next success in trials that succeed with probability
+    /// `p`, to pick a random fraction of many items without a trial for each.
+    pub fn gap(&mut self, p: f64) -> u64 {
+        if p >= 1.0 {
+            return 0;
+        }
+        ((1.0 - self.next_f64()).ln() / (1.0 - p).ln()).min(u64::MAX as f64) as u64
+    }
+}
@@ -0,0 +1,969 @@
+//! Life-like rules: which neighbor counts bring a dead cell to life and keep a live one alive,
+//! optionally with the decay states of the Generations family and another neighborhood than the
+//! 8 surrounding cells. Isotropic non-totalistic rules also look at which arrangements of
+//! neighbors are alive, Larger than Life rules count neighbors further away, Margolus rules
+//! replace whole 2x2 blocks at once, and elementary rules grow one row at a time. Colored
+//! variants such as Immigration and QuadLife give live cells colors passed on to newborns.
+
+use std::fmt;
+
+use crate::automaton::Automaton;
+
+/// A rule in B/S notation, with bit `n` of each mask set if `n` live neighbors cause a birth or
+/// a survival. The neighbors are the 8 cells around a cell unless `neighborhood` says otherwise;
+/// wider neighborhoods can have at most 31 cells, more need `larger_than_life`.
+///
+/// A Generations rule has more than two states: a live cell that doesn't survive goes through
+/// the dying states 2, 3, ... up to `states - 1` before it is dead (state 0) again. Dying cells
+/// don't count as neighbors and can't be born.
+///
+/// An isotropic non-totalistic rule also looks at where the live neighbors are. For such rules
+/// the masks have bit `n` set if any arrangement of `n` neighbors causes a birth or survival,
+/// and `isotropic` says which ones exactly.
+///
+/// In a colored rule the live cells come in `colors` colors, 1 and then the states 2, 3, ... up
+/// to `colors`, which don't change whether cells live or die. Survivors keep their color and
+/// newborns take theirs from their parents, see `Rule::newborn_color`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    pub birth: u32,
+    pub survival: u32,
+    pub states: u8, // 2 for Life-like rules
+    pub neighborhood: Neighborhood,
+    pub isotropic: Option<Isotropic>, // `None` when only the number of neighbors matters
+    pub larger_than_life: Option<LargerThanLife>, // replaces the masks for wider neighborhoods
+    pub margolus: Option<Margolus>, // replaces the masks with a block rule
+    pub elementary: Option<u8>, // replaces the masks with a one-dimensional rule, see `Rule::elementary`
+    pub colors: u8, // 1 for rules without colors
+}
+
+/// The neighborhoods that cause births and survivals, as sets of 256 bits: bit `n` is set for
+/// the neighborhood whose live neighbors are the bits of `n`, numbered as in `neighbor_bit`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Isotropic {
+    pub birth: [u64; 4],
+    pub survival: [u64; 4],
+}
+
+/// A Larger than Life rule, which counts the live cells in the neighborhood of a cell, itself
+/// included if `middle` is set. A dead cell is born and a live one survives if the count is in
+/// the inclusive range of `birth` or `survival`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LargerThanLife {
+    pub neighborhood: Neighborhood,
+    pub middle: bool,
+    pub birth: (u32, u32),
+    pub survival: (u32, u32),
+}
+
+impl LargerThanLife {
+    pub const MAX_RADIUS: u16 = 50;
+}
+
+/// A block rule in the Margolus neighborhood: the grid is cut into 2x2 blocks, which shift by one
+/// cell diagonally every generation, and each block becomes `table[n]` as a whole, where the bits
+/// of `n` are its live cells: 1 at the top left, 2 at the top right, 4 at the bottom left and 8
+/// at the bottom right, as in MCell. Rules whose table is a permutation are reversible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Margolus {
+    pub table: [u8; 16],
+}
+
+impl Margolus {
+    /// The cells of a block in the order of their bits, as offsets from its top left corner.
+    pub const CELLS: [(i64, i64); 4] = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+    /// The top left corners of the blocks are at even coordinates in even generations and at odd
+    /// ones in odd generations.
+    pub const fn offset(generation: u64) -> i64 {
+        (generation & 1) as i64
+    }
+}
+
+/// The cells within `radius` of a cell that count as its neighbors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Neighborhood {
+    pub shape: Shape,
+    pub radius: u16,
+}
+
+/// How the distance to a neighbor is measured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Shape {
+    Moore,      // the square around the cell
+    VonNeumann, // the diamond of cells at most `radius` steps away orthogonally
+    Circular,   // the cells whose centers are within `radius + 1/2` or so, like a round brush
+    Hexagonal,  // a hexagon on a grid sheared into hexagons, as in Golly: no NE or SW corners
+    // On a grid of triangles pointing up and down in turn (see `is_upward`), the 12 triangles
+    // touching a triangle, or only the 3 sharing an edge with it; the radius is always 1
+    Triangular,
+    TriangularEdges,
+}
+
+impl Neighborhood {
+    pub const MOORE: Neighborhood = Neighborhood { shape: Shape::Moore, radius: 1 };
+    pub const VON_NEUMANN: Neighborhood = Neighborhood { shape: Shape::VonNeumann, radius: 1 };
+    pub const HEXAGONAL: Neighborhood = Neighborhood { shape: Shape::Hexagonal, radius: 1 };
+    pub const TRIANGULAR: Neighborhood = Neighborhood { shape: Shape::Triangular, radius: 1 };
+    pub const TRIANGULAR_EDGES: Neighborhood = Neighborhood { shape: Shape::TriangularEdges, radius: 1 };
+
+    /// Neighborhoods small enough for B/S rules.
+    pub const PRESETS: [(&'static str, Neighborhood); 7] = [
+        ("Moore (8 cells)", Neighborhood::MOORE),
+        ("von Neumann (4 cells)", Neighborhood::VON_NEUMANN),
+        ("Hexagonal (6 cells)", Neighborhood::HEXAGONAL),
+        ("Triangular (12 cells)", Neighborhood::TRIANGULAR),
+        ("Triangular, edges only (3 cells)", Neighborhood::TRIANGULAR_EDGES),
+        ("Moore, range 2 (24 cells)", Neighborhood { shape: Shape::Moore, radius: 2 }),
+        ("Circular, range 2 (20 cells)", Neighborhood { shape: Shape::Circular, radius: 2 }),
+    ];
+
+    /// Name of the matching preset, if any.
+    pub fn name(self) -> &'static str {
+        Self::PRESETS.iter().find(|(_, n)| *n == self).map_or("Custom", |(name, _)| name)
+    }
+
+    /// The first and last columns dx the neighborhood covers in row dy, for dy from minus the
+    /// radius to the radius, around a cell whose triangle points up if `upward` (which only
+    /// matters on triangular grids). Rows it doesn't cover have a last column before the first.
+    pub fn span(self, dy: i64, upward: bool) -> (i64, i64) {
+        let r = self.radius as i64;
+        // Triangles pointing down have the neighborhoods of those pointing up upside down
+        let side = if upward { dy } else { -dy };
+        match self.shape {
+            Shape::Moore => (-r, r),
+            Shape::VonNeumann => (dy.abs() - r, r - dy.abs()),
+            // Same as `BrushShape::Circle`: dx² + dy² <= r² + r
+            Shape::Circular => {
+                let reach = ((r * r + r - dy * dy) as f64).sqrt() as i64;
+                (-reach, reach)
+            }
+            // Rows above lean left and rows below lean right, as |dx - dy| <= r
+            Shape::Hexagonal => ((dy - r).max(-r), (dy + r).min(r)),
+            // Three triangles share the top corner and five the base
+            Shape::Triangular if side < 0 => (-1, 1),
+            Shape::Triangular => (-2, 2),
+            Shape::TriangularEdges => match side {
+                -1 => (0, -1),
+                0 => (-1, 1),
+                _ => (0, 0),
+            },
+        }
+    }
+
+    /// How many columns the neighborhood reaches to either side.
+    pub fn reach(self) -> u16 {
+        match self.shape {
+            Shape::Triangular => 2,
+            _ => self.radius,
+        }
+    }
+
+    /// The offsets (dx, dy) of the neighbors, row by row, without the cell itself, for a cell
+    /// whose triangle points up if `upward`.
+    pub fn offsets(self, upward: bool) -> Vec<(i64, i64)> {
+        let r = self.radius as i64;
+        let mut offsets = Vec::new();
+        for dy in -r..=r {
+            let (first, last) = self.span(dy, upward);
+            offsets.extend((first..=last).filter(|&dx| dx != 0 || dy != 0).map(|dx| (dx, dy)));
+        }
+        offsets
+    }
+
+    /// The number of neighbors.
+    pub fn size(self) -> u32 {
+        self.offsets(true).len() as u32
+    }
+
+    /// Whether all neighbors are next to the cell, in the same places for every cell, so that
+    /// `neighbor_bit` can tell them apart.
+    pub fn is_adjacent(self) -> bool {
+        self.radius == 1 && !matches!(self.shape, Shape::Triangular | Shape::TriangularEdges)
+    }
+
+    /// Circular neighborhoods of radius 1 are the same cells as Moore's.
+    fn canonical(self) -> Neighborhood {
+        match self {
+            Neighborhood { shape: Shape::Circular, radius: 1 } => Neighborhood::MOORE,
+            other => other,
+        }
+    }
+}
+
+/// The letters Golly and LifeViewer put after B/S rules for the other small neighborhoods.
+const MARKERS: [(Neighborhood, &str); 4] = [
+    (Neighborhood::VON_NEUMANN, "V"),
+    (Neighborhood::HEXAGONAL, "H"),
+    (Neighborhood::TRIANGULAR, "L"),
+    (Neighborhood::TRIANGULAR_EDGES, "LE"),
+];
+
+/// The letters of Hensel notation for 1 to 4 neighbors, in their usual order, each with one of
+/// the neighborhoods it stands for; the others are its rotations and reflections. 5 to 7
+/// neighbors use the letters of the complementary neighborhoods.
+const LETTERS: [&[(char, u8)]; 4] = [
+    &[('c', 0x02), ('e', 0x01)],
+    &[('c', 0x0a), ('e', 0x05), ('k', 0x09), ('a', 0x03), ('i', 0x11), ('n', 0x22)],
+    &[
+        ('c', 0x2a), ('e', 0x45), ('k', 0x25), ('a', 0x07), ('i', 0x83),
+        ('n', 0x0b), ('y', 0x29), ('q', 0x23), ('j', 0x43), ('r', 0x13),
+    ],
+    &[
+        ('c', 0xaa), ('e', 0x55), ('k', 0x4b), ('a', 0x0f), ('i', 0x1b), ('n', 0x8b), ('y', 0x2b),
+        ('q', 0x27), ('j', 0x53), ('r', 0x17), ('t', 0x93), ('w', 0x63), ('z', 0x33),
+    ],
+];
+
+/// Names of the colored variants, with their numbers of colors.
+const COLORED: [(&str, u8); 2] = [("Immigration", 2), ("QuadLife", 4)];
+
+impl Rule {
+    pub const CONWAY: Rule = Rule::new(&[3], &[2, 3]);
+
+    /// Well-known rules with their names and what to expect from them.
+    pub const PRESETS: [(&'static str, Rule, &'static str); 25] = [
+        ("Conway's Life", Rule::CONWAY, "Gliders, oscillators and still lifes out of almost any soup"),
+        ("HighLife", Rule::new(&[3, 6], &[2, 3]), "Much like Life, plus a small pattern that replicates itself"),
+        ("Seeds", Rule::new(&[2], &[]), "Every live cell dies at once; nearly everything explodes"),
+        ("Day & Night", Rule::new(&[3, 6, 7, 8], &[3, 4, 6, 7, 8]), "Live and dead regions behave the same way, like mirror images"),
+        ("Life without Death", Rule::new(&[3], &[0, 1, 2, 3, 4, 5, 6, 7, 8]), "Cells never die, so patterns grow ladders and chaotic blobs"),
+        ("Diamoeba", Rule::new(&[3, 5, 6, 7, 8], &[5, 6, 7, 8]), "Large diamond-shaped amoebas whose edges wobble"),
+        ("Morley", Rule::new(&[3, 6, 8], &[2, 4, 5]), "Also called Move: many small spaceships and puffers"),
+        ("Anneal", Rule::new(&[4, 6, 7, 8], &[3, 5, 6, 7, 8]), "Majority vote: random soup settles into blobs whose edges slowly smooth out"),
+        ("2x2", Rule::new(&[3, 6], &[1, 2, 5]), "Patterns made of 2x2 blocks keep to blocks and act like a simpler automaton"),
+        ("Immigration", Rule::CONWAY.with_colors(2), "Life in two colors: newborns take the color most of their parents have, so colonies compete"),
+        ("QuadLife", Rule::CONWAY.with_colors(4), "Life in four colors: newborns take their parents' majority color, or the fourth one if all three differ"),
+        ("Brian's Brain", Rule::new(&[2], &[]).with_states(3), "Live cells always die and rest a generation while dying, so soup fills with spaceships streaking every way"),
+        ("Star Wars", Rule::new(&[2], &[3, 4, 5]).with_states(4), "Dying cells leave walls behind, and small ships race along them"),
+        ("Bosco's Rule", Rule::larger_than_life(5, (34, 45), (34, 58)), "Radius 5: soup leaves still blobs, oscillators and a large glider, the bug"),
+        ("Majority", Rule::larger_than_life(4, (41, 81), (41, 81)), "Radius 4: each cell joins the majority around it, so soup coarsens into smooth regions"),
+        ("Hex Life", Rule::hexagonal(&[2], &[3, 4]), "Hexagonal grid: the usual starting point for six-neighbor rules"),
+        ("Hex Seeds", Rule::hexagonal(&[2], &[]), "Seeds on a hexagonal grid: every live cell dies at once"),
+        ("Hex Majority", Rule::hexagonal(&[4, 5, 6], &[3, 4, 5, 6]), "Hexagonal grid: each cell joins the majority of its seven-cell hexagon, so soup settles into blobs"),
+        ("Critters", Rule::margolus([15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0]), "Reversible 2x2 block rule: gliders bounce around and empty space flashes"),
+        ("Billiard Ball Machine", Rule::margolus([0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15]), "Reversible 2x2 block rule: balls fly diagonally and bounce off each other and walls"),
+        ("Tron", Rule::margolus([15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0]), "Reversible 2x2 block rule: only empty and full blocks change, each into the other"),
+        ("Rule 30", Rule::elementary(30), "One-dimensional: a single cell grows a chaotic triangle"),
+        ("Rule 90", Rule::elementary(90), "One-dimensional: a single cell grows a Sierpinski triangle"),
+        ("Rule 110", Rule::elementary(110), "One-dimensional: gliders collide on a periodic background; Turing complete"),
+        ("Rule 184", Rule::elementary(184), "One-dimensional traffic flow: live cells move right when the cell ahead is free"),
+    ];
+
+    /// The rule with births and survivals for the given neighbor counts, which must be 0 to 8.
+    pub const fn new(birth: &[u16], survival: &[u16]) -> Rule {
+        const fn mask(counts: &[u16]) -> u32 {
+            let (mut mask, mut i) = (0, 0);
+            while i < counts.len() {
+                mask |= 1 << counts[i];
+                i += 1;
+            }
+            mask
+        }
+        Rule {
+            birth: mask(birth),
+            survival: mask(survival),
+            states: 2,
+            neighborhood: Neighborhood::MOORE,
+            isotropic: None,
+            larger_than_life: None,
+            margolus: None,
+            elementary: None,
+            colors: 1,
+        }
+    }
+
+    /// The rule with births and survivals for the given counts of neighbors out of 6 on a
+    /// hexagonal grid.
+    pub const fn hexagonal(birth: &[u16], survival: &[u16]) -> Rule {
+        Rule { neighborhood: Neighborhood::HEXAGONAL, ..Rule::new(birth, survival) }
+    }
+
+    /// A Larger than Life rule with the Moore neighborhood counting the cell itself.
+    pub const fn larger_than_life(radius: u16, birth: (u32, u32), survival: (u32, u32)) -> Rule {
+        let neighborhood = Neighborhood { shape: Shape::Moore, radius };
+        let range = LargerThanLife { neighborhood, middle: true, birth, survival };
+        Rule { larger_than_life: Some(range), ..Rule::new(&[], &[]) }
+    }
+
+    /// A Margolus block rule with the given table.
+    pub const fn margolus(table: [u8; 16]) -> Rule {
+        Rule { margolus: Some(Margolus { table }), ..Rule::new(&[], &[]) }
+    }
+
+    /// One of Wolfram's elementary rules, numbered 0 to 255. Each generation is a new row below
+    /// the last one, whose cells are alive if bit `4l + 2c + r` of `code` is set, where `l`, `c`
+    /// and `r` are the cells above to the left, above and above to the right.
+    pub const fn elementary(code: u8) -> Rule {
+        Rule { elementary: Some(code), ..Rule::new(&[], &[]) }
+    }
+
+    /// The same rule with the given number of states, at least 2.
+    pub const fn with_states(self, states: u8) -> Rule {
+        Rule { states, ..self }
+    }
+
+    /// The same rule with live cells in the given number of colors, at least 1.
+    pub const fn with_colors(self, colors: u8) -> Rule {
+        Rule { colors, ..self }
+    }
+
+    /// The same B/S rule counting another neighborhood of at most 31 cells, with the counts the
+    /// neighborhood can't reach dropped.
+    pub fn with_neighborhood(self, neighborhood: Neighborhood) -> Rule {
+        let neighborhood = neighborhood.canonical();
+        let counts = u32::MAX >> 31u32.saturating_sub(neighborhood.size());
+        Rule {
+            birth: self.birth & counts,
+            survival: self.survival & counts,
+            neighborhood,
+            // Hensel letters only name arrangements of the 8 adjacent cells
+            isotropic: self.isotropic.filter(|_| neighborhood == Neighborhood::MOORE),
+            ..self
+        }
+    }
+
+    /// Whether live cells decay through dying states instead of dying at once.
+    pub fn has_decay(self) -> bool {
+        self.states > 2
+    }
+
+    /// The number of cell states: dead, the colors of live cells and any dying states.
+    pub fn cell_states(self) -> u8 {
+        self.states + self.colors - 1
+    }
+
+    /// The color of a cell born to live neighbors of which `parents[c]` have color `c + 1`:
+    /// the most common color, or if several are, the first that none of the neighbors have, as
+    /// QuadLife gives a newborn whose three parents all differ the fourth color. Failing that,
+    /// the first of the most common.
+    pub fn newborn_color(self, parents: &[u32]) -> u8 {
+        let most = parents.iter().copied().max().unwrap_or(0);
+        let mut tied = (1..).zip(parents).filter(|&(_, &count)| count == most).map(|(color, _)| color);
+        let first = tied.next().unwrap_or(1);
+        match tied.next() {
+            Some(_) => (1..).zip(parents).find(|&(_, &count)| count == 0).map_or(first, |(color, _)| color),
+            None => first,
+        }
+    }
+
+    /// Name of the matching preset, if any.
+    pub fn name(self) -> &'static str {
+        Self::PRESETS.iter().find(|(_, rule, _)| *rule == self).map_or("Custom", |(name, _, _)| name)
+    }
+
+    /// Parses B/S notation such as `B36/S23`, with the parts in any order, with or without
+    /// slashes and in any case, as well as the older S/B form `23/36`. Generations rules add
+    /// the number of states: `B2/S/C3`, `B2/S/3` or `/2/3`. Counts may be followed by Hensel
+    /// notation letters to pick neighborhoods, as in `B2-a/S12` or `B2ce3/S23`, and a final `V`,
+    /// `H`, `L` or `LE` picks the von Neumann, hexagonal, triangular or edge-only triangular
+    /// neighborhood: `B1/S1V`, `B2/S34H` or `B4/S345L`. Wider neighborhoods and Larger than Life
+    /// rules are written as in Golly, e.g. `R2,C0,S6-9,B7-8,NC` or `R5,C0,M1,S34..58,B34..45,NM`,
+    /// and Margolus rules as in MCell, with the table after `MS,D`:
+    /// `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15`. Elementary rules are `W` and their number, as
+    /// in Golly: `W30`. Colored variants add `Immigration` or `QuadLife` after a rule, which
+    /// on its own means Conway's: `Immigration` or `B36/S23 QuadLife`.
+    pub fn parse(text: &str) -> Result<Rule, String> {
+        let text = text.trim();
+        for (name, colors) in COLORED {
+            let start = text.len().checked_sub(name.len());
+            let Some(start) = start.filter(|&start| text.get(start..).is_some_and(|end| end.eq_ignore_ascii_case(name))) else {
+                continue;
+            };
+            let base = text[..start].trim_end_matches([' ', '/']);
+            let rule = if base.is_empty() { Rule::CONWAY } else { Rule::parse(base)? };
+            if rule.has_decay() || rule.colors > 1 || rule.larger_than_life.is_some() || rule.margolus.is_some() || rule.elementary.is_some() {
+                return Err(format!("Only rules of live and dead cells counting nearby neighbors can be colored, not {rule}"));
+            }
+            return Ok(rule.with_colors(colors));
+        }
+        if let Some(code) = text.strip_prefix(['W', 'w']) {
+            return match code.parse::<u8>() {
+                Ok(code) => Ok(Rule::elementary(code)),
+                Err(_) => Err(format!("\"{code}\" is not an elementary rule from 0 to 255")),
+            };
+        }
+        if text.get(..3).is_some_and(|start| start.eq_ignore_ascii_case("MS,")) {
+            return parse_margolus(text);
+        }
+        if text.contains(',') {
+            return parse_higher_range(text);
+        }
+        let marker = |piece: &str, letters: &[char]| piece.starts_with(|c: char| letters.contains(&c.to_ascii_uppercase()));
+        let mut parts: Vec<(char, &str)> = Vec::new();
+        if marker(text, &['B', 'S', 'C']) {
+            for piece in text.split('/') {
+                if marker(piece, &['C']) {
+                    parts.push(('C', &piece[1..]));
+                    continue;
+                }
+                if !marker(piece, &['B', 'S']) {
+                    // A bare number after the B and S parts, as in B2/S/3
+                    parts.push(('C', piece));
+                    continue;
+                }
+                // Hensel letters include c but never b or s, so C only starts a piece
+                let mut rest = piece;
+                while let Some(letter) = rest.chars().next() {
+                    let end = rest[1..].find(['B', 'S', 'b', 's']).map_or(rest.len(), |i| i + 1);
+                    parts.push((letter.to_ascii_uppercase(), &rest[1..end]));
+                    rest = &rest[end..];
+                }
+            }
+        } else {
+            let pieces: Vec<&str> = text.split('/').collect();
+            if !(2..=3).contains(&pieces.len()) {
+                return Err(format!("Expected B/S, S/B or S/B/C notation, got \"{text}\""));
+            }
+            parts.extend(['S', 'B', 'C'].into_iter().zip(pieces));
+        }
+
+        let part = |letter: char| {
+            let mut matching = parts.iter().filter(|(l, _)| *l == letter).map(|&(_, digits)| digits);
+            match (matching.next(), matching.next()) {
+                (_, Some(_)) => Err(format!("More than one {letter} part in \"{text}\"")),
+                (digits, None) => Ok(digits),
+            }
+        };
+        let (Some(birth), Some(survival)) = (part('B')?, part('S')?) else {
+            return Err(format!("Expected a B part and an S part in \"{text}\""));
+        };
+        // Hensel letters have no h, l or v, so trailing ones can only mark another neighborhood
+        fn suffix(digits: &str) -> Option<(Neighborhood, &'static str)> {
+            MARKERS.into_iter().find(|(_, marker)| {
+                let end = digits.len().checked_sub(marker.len()).and_then(|start| digits.get(start..));
+                end.is_some_and(|end| end.eq_ignore_ascii_case(marker))
+            })
+        }
+        fn unmarked(digits: &str) -> &str {
+            &digits[..digits.len() - suffix(digits).map_or(0, |(_, marker)| marker.len())]
+        }
+        let marked = [Some(birth), Some(survival), part('C')?].into_iter().flatten().find_map(suffix).map(|(n, _)| n);
+        let states = match part('C')? {
+            None => 2,
+            Some(digits) => match unmarked(digits).parse::<u8>() {
+                Ok(states) if states >= 2 => states,
+                _ => return Err(format!("\"{digits}\" is not a number of states from 2 to 255")),
+            },
+        };
+        let (birth, survival) = (unmarked(birth), unmarked(survival));
+        let (birth, survival) = (neighborhoods(birth)?, neighborhoods(survival)?);
+        let mut rule = Rule {
+            birth: count_mask(birth),
+            survival: count_mask(survival),
+            states,
+            neighborhood: Neighborhood::MOORE,
+            isotropic: None,
+            larger_than_life: None,
+            margolus: None,
+            elementary: None,
+            colors: 1,
+        };
+        let isotropic = with_counts(rule.birth) != birth || with_counts(rule.survival) != survival;
+        if let Some(neighborhood) = marked {
+            let size = neighborhood.size();
+            if isotropic || (rule.birth | rule.survival) >> (size + 1) != 0 {
+                return Err(format!("\"{text}\" has {size} neighbors, so its counts go from 0 to {size}, without letters"));
+            }
+            rule.neighborhood = neighborhood;
+        } else if isotropic {
+            rule.isotropic = Some(Isotropic { birth, survival });
+        }
+        Ok(rule)
+    }
+
+    /// Whether a cell is alive in the next generation, given its neighborhood with bits set
+    /// for the live neighbors as in `neighbor_bit`. Only for rules whose neighbors are all
+    /// adjacent to the cell.
+    pub fn next(self, alive: bool, neighborhood: u8) -> bool {
+        if let Some(isotropic) = self.isotropic {
+            let set = if alive { isotropic.survival } else { isotropic.birth };
+            return contains(set, neighborhood);
+        }
+        let neighborhood = match self.neighborhood.shape {
+            // The even bits are the orthogonal neighbors
+            Shape::VonNeumann => neighborhood & 0x55,
+            // Bits 1 and 5 are the NE and SW corners
+            Shape::Hexagonal => neighborhood & !0x22,
+            _ => neighborhood,
+        };
+        self.next_count(alive, neighborhood.count_ones())
+    }
+
+    /// Whether a cell is alive in the next generation, given its number of live neighbors,
+    /// for rules that only count them.
+    pub fn next_count(self, alive: bool, count: u32) -> bool {
+        let mask = if alive { self.survival } else { self.birth };
+        count < 32 && mask & (1 << count) != 0
+    }
+
+    /// Whether only the number of live cells among the 8 neighbors matters, as `next_word`
+    /// needs.
+    pub fn is_totalistic(self) -> bool {
+        self.neighborhood == Neighborhood::MOORE && self.isotropic.is_none() && self.larger_than_life.is_none() && self.margolus.is_none() && self.elementary.is_none()
+    }
+
+    /// Whether dead cells with no live neighbors come to life, which makes empty space fill up
+    /// and so can't be simulated in an unbounded universe.
+    pub fn births_from_nothing(self) -> bool {
+        match (self.larger_than_life, self.margolus, self.elementary) {
+            (Some(range), _, _) => range.birth.0 == 0,
+            (_, Some(blocks), _) => blocks.table[0] != 0,
+            (_, _, Some(code)) => code & 1 != 0,
+            _ => self.birth & 1 != 0,
+        }
+    }
+
+    /// The next states of 64 cells at once under a totalistic rule, given their neighbor counts
+    /// bit-sliced into four words (`counts[i]` holds bit `i` of each count) and their current
+    /// states.
+    pub fn next_word(self, counts: [u64; 4], center: u64) -> u64 {
+        if self == Rule::CONWAY {
+            // A count of 2 or 3 is 001x, and a live cell survives both while a dead one needs 3
+            let [s0, s1, s2, s3] = counts;
+            return s1 & !s2 & !s3 & (s0 | center);
+        }
+        let (mut born, mut survive) = (0, 0);
+        for n in 0..=8 {
+            if (self.birth | self.survival) & (1 << n) == 0 {
+                continue;
+            }
+            let equal = (0..4).fold(!0u64, |mask, i| mask & if n & (1 << i) != 0 { counts[i] } else { !counts[i] });
+            if self.birth & (1 << n) != 0 {
+                born |= equal;
+            }
+            if self.survival & (1 << n) != 0 {
+                survive |= equal;
+            }
+        }
+        (born & !center) | (survive & center)
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::CONWAY
+    }
+}
+
+/// A rule as a transition on the 8 neighbors, which fits the Life-like, isotropic and
+/// Generations rules on the square and hexagonal neighborhoods of radius 1. Wider rules,
+/// triangular neighborhoods, block rules, elementary rules and colors need more than that and
+/// only run as the universe's own rule.
+impl Automaton for Rule {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn states(&self) -> u8 {
+        self.states
+    }
+
+    fn next(&self, center: u8, neighbors: [u8; 8]) -> u8 {
+        let live = neighbors.iter().enumerate().filter(|(_, &state)| state == 1).fold(0, |bits, (i, _)| bits | 1 << i);
+        match center {
+            0 | 1 if Rule::next(*self, center == 1, live) => 1,
+            1 if self.has_decay() => 2,
+            0 | 1 => 0,
+            dying => (dying + 1) % self.states,
+        }
+    }
+
+    fn births_from_nothing(&self) -> bool {
+        Rule::births_from_nothing(*self)
+    }
+}
+
+/// Saved in its notation, as typed in.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Rule {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Rule {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Rule::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(&(name, _)) = COLORED.iter().find(|&&(_, colors)| colors == self.colors) {
+            return match self.with_colors(1) {
+                Rule::CONWAY => write!(f, "{name}"),
+                base => write!(f, "{base} {name}"),
+            };
+        }
+        if let Some(code) = self.elementary {
+            return write!(f, "W{code}");
+        }
+        if let Some(blocks) = self.margolus {
+            let table: Vec<String> = blocks.table.iter().map(|block| block.to_string()).collect();
+            return write!(f, "MS,D{}", table.join(";"));
+        }
+        if let Some(range) = self.larger_than_life {
+            // Golly writes C0 for the two states of rules without decay
+            let states = if self.has_decay() { self.states } else { 0 };
+            let (b, s) = (range.birth, range.survival);
+            return write!(
+                f,
+                "R{},C{states},M{},S{}..{},B{}..{},N{}",
+                range.neighborhood.radius,
+                range.middle as u8,
+                s.0,
+                s.1,
+                b.0,
+                b.1,
+                shape_letter(range.neighborhood.shape)
+            );
+        }
+        // B/S digits only go up to 8 neighbors
+        let marker = MARKERS.iter().find(|(neighborhood, _)| *neighborhood == self.neighborhood);
+        if let Some(&(_, marker)) = marker.filter(|_| (self.birth | self.survival) >> 9 == 0) {
+            write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))?;
+            if self.has_decay() {
+                write!(f, "/C{}", self.states)?;
+            }
+            return write!(f, "{marker}");
+        }
+        if self.neighborhood != Neighborhood::MOORE {
+            let states = if self.has_decay() { self.states } else { 0 };
+            let ranges = |mask: u32| {
+                let mut ranges: Vec<String> = Vec::new();
+                let mut n = 0;
+                while n < 32 {
+                    if mask & (1 << n) == 0 {
+                        n += 1;
+                        continue;
+                    }
+                    let end = (n..32).take_while(|&m| mask & (1 << m) != 0).last().unwrap_or(n);
+                    ranges.push(if end == n { n.to_string() } else { format!("{n}-{end}") });
+                    n = end + 1;
+                }
+                ranges.join(",")
+            };
+            return write!(
+                f,
+                "R{},C{states},S{},B{},N{}",
+                self.neighborhood.radius,
+                ranges(self.survival),
+                ranges(self.birth),
+                shape_letter(self.neighborhood.shape)
+            );
+        }
+        let (birth, survival) = match self.isotropic {
+            Some(isotropic) => (isotropic.birth, isotropic.survival),
+            None => (with_counts(self.birth), with_counts(self.survival)),
+        };
+        write!(f, "B{}/S{}", hensel(birth), hensel(survival))?;
+        if self.has_decay() {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses Golly's comma-separated notation for rules with wider neighborhoods: the radius `R`,
+/// number of states `C` (0 or 2 without decay), `M1` to count the cell itself, survival and
+/// birth counts `S` and `B` as lists of counts and ranges written `min..max` or `min-max`, and
+/// the neighborhood, `NM` for Moore, `NN` for von Neumann, `NC` for circular or `NH` for
+/// hexagonal. Neighborhoods of up to 31 cells make B/S rules, which take any counts; larger ones
+/// make Larger than Life rules, which take a single range each.
+fn parse_higher_range(text: &str) -> Result<Rule, String> {
+    let mut neighborhood = Neighborhood::MOORE;
+    let mut middle = false;
+    let mut states = 2;
+    let (mut birth, mut survival) = (Vec::new(), Vec::new());
+    let mut list = None; // the letter whose counts continue after a comma
+    let number = |value: &str| value.parse::<u32>().map_err(|_| format!("\"{value}\" is not a number in \"{text}\""));
+    let bounds = |value: &str| match value.split_once("..").or_else(|| value.split_once('-')) {
+        Some((min, max)) => Ok((number(min)?, number(max)?)),
+        None => number(value).map(|n| (n, n)),
+    };
+    for part in text.split(',') {
+        let part = part.trim();
+        let Some(letter) = part.chars().next() else {
+            continue;
+        };
+        if letter.is_ascii_digit() {
+            match list {
+                Some('S') => survival.push(bounds(part)?),
+                Some('B') => birth.push(bounds(part)?),
+                _ => return Err(format!("Unexpected \"{part}\" in \"{text}\"")),
+            }
+            continue;
+        }
+        list = None;
+        let value = &part[1..];
+        match letter.to_ascii_uppercase() {
+            'R' => match number(value)? {
+                radius if (1..=LargerThanLife::MAX_RADIUS as u32).contains(&radius) => neighborhood.radius = radius as u16,
+                _ => return Err(format!("The radius must be from 1 to {}", LargerThanLife::MAX_RADIUS)),
+            },
+            'C' => match number(value)? {
+                0..=2 => states = 2,
+                n @ 3..=255 => states = n as u8,
+                _ => return Err(format!("\"{value}\" is not a number of states from 2 to 255")),
+            },
+            'M' => middle = number(value)? != 0,
+            'S' | 'B' => {
+                let counts = if letter.eq_ignore_ascii_case(&'S') { &mut survival } else { &mut birth };
+                if !value.is_empty() {
+                    counts.push(bounds(value)?);
+                }
+                list = Some(letter.to_ascii_uppercase());
+            }
+            'N' => {
+                neighborhood.shape = match value.to_ascii_uppercase().as_str() {
+                    "M" => Shape::Moore,
+                    "N" => Shape::VonNeumann,
+                    "C" => Shape::Circular,
+                    "H" => Shape::Hexagonal,
+                    "L" => Shape::Triangular,
+                    "LE" => Shape::TriangularEdges,
+                    _ => return Err(format!("Unknown neighborhood \"{value}\", expected NM, NN, NC, NH, NL or NLE")),
+                }
+            }
+            _ => return Err(format!("Unexpected \"{part}\" in \"{text}\"")),
+        }
+    }
+
+    if matches!(neighborhood.shape, Shape::Triangular | Shape::TriangularEdges) && neighborhood.radius != 1 {
+        return Err(format!("Triangular neighborhoods have radius 1: \"{text}\""));
+    }
+    let neighborhood = neighborhood.canonical();
+    let size = neighborhood.size();
+    if size <= 31 {
+        // With the middle cell counted, a live cell sees one more than its live neighbors
+        let mask = |ranges: &[(u32, u32)], offset: u32| {
+            ranges.iter().flat_map(|&(min, max)| min.max(offset)..=max.min(size + offset)).fold(0, |mask, n| mask | 1 << (n - offset))
+        };
+        let rule = Rule::new(&[], &[]).with_states(states);
+        return Ok(Rule {
+            birth: mask(&birth, 0),
+            survival: mask(&survival, middle as u32),
+            neighborhood,
+            ..rule
+        });
+    }
+    let single = |ranges: &[(u32, u32)], letter: char| match ranges {
+        [] => Ok((1, 0)),
+        &[range] => Ok(range),
+        _ => Err(format!("Neighborhoods of more than 31 cells take a single {letter} range: \"{text}\"")),
+    };
+    let range = LargerThanLife { neighborhood, middle, birth: single(&birth, 'B')?, survival: single(&survival, 'S')? };
+    Ok(Rule { states, larger_than_life: Some(range), ..Rule::new(&[], &[]) })
+}
+
+/// Parses MCell's notation for Margolus rules, `MS,D` followed by the 16 blocks of the table
+/// separated by semicolons.
+fn parse_margolus(text: &str) -> Result<Rule, String> {
+    let expected = || format!("Expected MS,D and 16 blocks from 0 to 15 separated by semicolons, got \"{text}\"");
+    let Some(table) = text[3..].trim().strip_prefix(['D', 'd']) else {
+        return Err(expected());
+    };
+    let blocks: Vec<u8> = table.split(';').map(|block| block.trim().parse::<u8>().ok().filter(|&block| block < 16)).collect::<Option<_>>().ok_or_else(expected)?;
+    let table = blocks.try_into().map_err(|_| expected())?;
+    Ok(Rule::margolus(table))
+}
+
+/// Golly's letter for a neighborhood shape, and ours for the triangular ones.
+fn shape_letter(shape: Shape) -> &'static str {
+    match shape {
+        Shape::Moore => "M",
+        Shape::VonNeumann => "N",
+        Shape::Circular => "C",
+        Shape::Hexagonal => "H",
+        Shape::Triangular => "L",
+        Shape::TriangularEdges => "LE",
+    }
+}
+
+/// The counts up to 8 in a mask as digits.
+fn counts(mask: u32) -> String {
+    (0..9).filter(|n| mask & (1 << n) != 0).map(|n: u32| n.to_string()).collect()
+}
+
+/// The offsets (dx, dy) of the 8 neighbors in the order of `neighbor_bit`.
+pub const NEIGHBORS: [(i64, i64); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+/// Whether the triangle of cell (x, y) on a triangular grid points up: they alternate along
+/// rows and columns, and the one at the origin does.
+pub const fn is_upward(x: i64, y: i64) -> bool {
+    (x + y) & 1 == 0
+}
+
+/// The bit for a live neighbor at offset (dx, dy), with y growing downwards: 0 for the one
+/// above, then clockwise up to 7 for the one above and to the left.
+pub const fn neighbor_bit(dx: i64, dy: i64) -> u8 {
+    let index = match (dx, dy) {
+        (0, -1) => 0,
+        (1, -1) => 1,
+        (1, 0) => 2,
+        (1, 1) => 3,
+        (0, 1) => 4,
+        (-1, 1) => 5,
+        (-1, 0) => 6,
+        _ => 7,
+    };
+    1 << index
+}
+
+/// The neighborhoods for a string of neighbor counts, each optionally followed by Hensel
+/// letters for only those arrangements, or by `-` and letters for all but those: `2-a3`.
+fn neighborhoods(text: &str) -> Result<[u64; 4], String> {
+    let mut set = [0; 4];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let n = match c.to_digit(10) {
+            Some(n) if n <= 8 => n,
+            _ => return Err(format!("'{c}' is not a neighbor count from 0 to 8")),
+        };
+        let exclude = chars.next_if_eq(&'-').is_some();
+        let mut letters = String::new();
+        while let Some(letter) = chars.next_if(char::is_ascii_alphabetic) {
+            letters.push(letter.to_ascii_lowercase());
+        }
+        if exclude && letters.is_empty() {
+            return Err(format!("Expected letters after \"{n}-\""));
+        }
+        let mut chosen = if letters.is_empty() || exclude { with_counts(1 << n) } else { [0; 4] };
+        for letter in letters.chars() {
+            let Some(shape) = letter_neighborhoods(n, letter) else {
+                return Err(format!("'{letter}' is not a Hensel letter for {n} neighbors"));
+            };
+            for (word, bits) in chosen.iter_mut().zip(shape) {
+                *word = if exclude { *word & !bits } else { *word | bits };
+            }
+        }
+        for (word, bits) in set.iter_mut().zip(chosen) {
+            *word |= bits;
+        }
+    }
+    Ok(set)
+}
+
+/// The neighborhoods of `n` neighbors a Hensel letter stands for, if it is one for `n`.
+fn letter_neighborhoods(n: u32, letter: char) -> Option<[u64; 4]> {
+    let (letters, complement) = match n {
+        1..=4 => (LETTERS[n as usize - 1], false),
+        5..=7 => (LETTERS[7 - n as usize], true),
+        _ => return None,
+    };
+    let &(_, shape) = letters.iter().find(|(l, _)| *l == letter)?;
+    let shape = if complement { !shape } else { shape };
+    // Rotations by a quarter turn move each neighbor two places round, and reflecting through
+    // the vertical axis swaps neighbors 1 and 7, 2 and 6, and 3 and 5
+    let reflected = (0..8).filter(|i| shape & (1 << i) != 0).fold(0u8, |m, i| m | 1 << ((8 - i) % 8));
+    let mut set = [0; 4];
+    for turns in 0..4 {
+        for shape in [shape, reflected] {
+            let turned = shape.rotate_left(2 * turns);
+            set[turned as usize / 64] |= 1 << (turned % 64);
+        }
+    }
+    Some(set)
+}
+
+/// All neighborhoods with numbers of neighbors in the mask.
+fn with_counts(mask: u32) -> [u64; 4] {
+    let mut set = [0; 4];
+    for n in 0..=255u8 {
+        if mask & (1 << n.count_ones()) != 0 {
+            set[n as usize / 64] |= 1 << (n % 64);
+        }
+    }
+    set
+}
+
+/// The mask of the numbers of neighbors that some neighborhood in the set has.
+fn count_mask(set: [u64; 4]) -> u32 {
+    (0..=255u8).filter(|&n| contains(set, n)).fold(0, |mask, n| mask | 1 << n.count_ones())
+}
+
+fn contains(set: [u64; 4], neighborhood: u8) -> bool {
+    set[neighborhood as usize / 64] & (1 << (neighborhood % 64)) != 0
+}
+
+/// The neighborhoods in Hensel notation: each count alone if all its neighborhoods are in the
+/// set, otherwise followed by the letters that are in, or `-` and those that aren't if that's
+/// shorter.
+fn hensel(set: [u64; 4]) -> String {
+    let mut text = String::new();
+    for n in 0..=8 {
+        let all = with_counts(1 << n);
+        let included: [u64; 4] = std::array::from_fn(|i| set[i] & all[i]);
+        if included == [0; 4] {
+            continue;
+        }
+        text += &n.to_string();
+        if included == all {
+            continue;
+        }
+        // Counts of 0 and 8 have a single neighborhood, so this is 1 to 7
+        let letters = LETTERS[if n <= 4 { n as usize - 1 } else { 7 - n as usize }];
+        let (inside, outside): (Vec<char>, Vec<char>) = letters.iter().map(|&(l, _)| l).partition(|&l| {
+            let shape = letter_neighborhoods(n, l).unwrap_or_default();
+            shape.iter().zip(included).all(|(s, i)| s & i == *s)
+        });
+        if outside.len() < inside.len() {
+            text.push('-');
+            text.extend(outside);
+        } else {
+            text.extend(inside);
+        }
+    }
+    text
+}
+
+/// Adds one bit-sliced word of neighbors to a count kept in `counts`, as in `Rule::next_word`.
+pub fn add_to_counts(counts: &mut [u64; 4], neighbors: u64) {
+    let carry0 = counts[0] & neighbors;
+    counts[0] ^= neighbors;
+    let carry1 = counts[1] & carry0;
+    counts[1] ^= carry0;
+    let carry2 = counts[2] & carry1;
+    counts[2] ^= carry1;
+    counts[3] ^= carry2;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hensel_rules_read_back_as_written() {
+        for text in ["B2-a/S12", "B2ce3/S23", "B3/S2-i34q", "B2n3/S23-q", "B35y/S1e2-a3i", "B2ci3ai4c8/S02ea3ekiqj4iz5ar6i7e"] {
+            let rule = Rule::parse(text).unwrap();
+            assert_eq!(rule.to_string(), text);
+            assert_eq!(Rule::parse(&rule.to_string()), Ok(rule));
+        }
+    }
+
+    #[test]
+    fn hensel_letters_cover_each_neighborhood_once() {
+        for neighborhood in 0..=255u8 {
+            let n = neighborhood.count_ones();
+            let letters = match n {
+                1..=4 => LETTERS[n as usize - 1],
+                5..=7 => LETTERS[7 - n as usize],
+                _ => continue, // no letters: all or no neighbors
+            };
+            let matching: Vec<char> =
+                letters.iter().map(|&(l, _)| l).filter(|&l| contains(letter_neighborhoods(n, l).unwrap(), neighborhood)).collect();
+            assert_eq!(matching.len(), 1, "{neighborhood:08b} with {n} neighbors is under {matching:?}");
+        }
+        for n in 1..=7 {
+            let letters = LETTERS[if n <= 4 { n as usize - 1 } else { 7 - n as usize }];
+            let union = letters.iter().fold([0; 4], |union: [u64; 4], &(l, _)| {
+                let shape = letter_neighborhoods(n, l).unwrap();
+                std::array::from_fn(|i| union[i] | shape[i])
+            });
+            assert_eq!(union, with_counts(1 << n), "letters for {n} neighbors");
+        }
+    }
+}
@@ -0,0 +1,449 @@
+//! Golly `.rule` files: transition rules for any number of states, given as a table of
+//! transitions (`@TABLE`) or a decision tree (`@TREE`), with optional state colors (`@COLORS`).
+
+use crate::automaton::Automaton;
+use crate::rule::{Neighborhood, Shape};
+
+/// A rule loaded from a `.rule` file. Neighbors are given in the order N, NE, E, SE, S, SW, W,
+/// NW, as in `rule::neighbor_bit`; under the von Neumann neighborhood only N, E, S and W count.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleFile {
+    pub name: String,
+    pub states: u8,
+    pub neighborhood: Neighborhood,
+    pub colors: Vec<Option<[u8; 3]>>, // by state, where the file gives one
+    transitions: Transitions,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Transitions {
+    Table(Table),
+    Tree(Tree),
+}
+
+impl RuleFile {
+    /// Reads the `@RULE` name, the first `@TABLE` or `@TREE` section and any `@COLORS`. Other
+    /// sections, such as `@ICONS`, are skipped.
+    pub fn parse(text: &str) -> Result<RuleFile, String> {
+        let mut name = String::new();
+        let mut sections: Vec<(&str, Vec<&str>)> = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if let Some(header) = line.strip_prefix('@') {
+                let (section, rest) = header.split_once(char::is_whitespace).unwrap_or((header, ""));
+                if section == "RULE" {
+                    name = rest.trim().to_string();
+                }
+                sections.push((section, Vec::new()));
+            } else if let Some((_, lines)) = sections.last_mut() {
+                // Comments run from # to the end of the line
+                let line = line.split('#').next().unwrap_or("").trim();
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+            }
+        }
+
+        let section = |wanted: &str| sections.iter().find(|(s, _)| *s == wanted).map(|(_, lines)| lines.as_slice());
+        let (states, neighborhood, transitions) = match (section("TABLE"), section("TREE")) {
+            (Some(lines), _) => {
+                let table = Table::parse(lines)?;
+                (table.states, table.neighborhood, Transitions::Table(table))
+            }
+            (None, Some(lines)) => {
+                let tree = Tree::parse(lines)?;
+                (tree.states, tree.neighborhood, Transitions::Tree(tree))
+            }
+            (None, None) => return Err("Expected a @TABLE or @TREE section".to_string()),
+        };
+        let colors = section("COLORS").map_or(Ok(Vec::new()), |lines| parse_colors(lines, states))?;
+        Ok(RuleFile { name, states, neighborhood, colors, transitions })
+    }
+
+    /// The next state of a cell in state `center` with the given neighbors.
+    pub fn next(&self, center: u8, neighbors: [u8; 8]) -> u8 {
+        match &self.transitions {
+            Transitions::Table(table) => table.next(center, neighbors),
+            Transitions::Tree(tree) => tree.next(center, neighbors),
+        }
+    }
+
+    /// Whether empty space comes to life, which an unbounded universe can't hold.
+    pub fn births_from_nothing(&self) -> bool {
+        self.next(0, [0; 8]) != 0
+    }
+
+    /// The color the file gives a state, if any.
+    pub fn color(&self, state: u8) -> Option<[u8; 3]> {
+        self.colors.get(state as usize).copied().flatten()
+    }
+}
+
+impl Automaton for RuleFile {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn states(&self) -> u8 {
+        self.states
+    }
+
+    fn next(&self, center: u8, neighbors: [u8; 8]) -> u8 {
+        RuleFile::next(self, center, neighbors)
+    }
+
+    fn color(&self, state: u8) -> Option<[u8; 3]> {
+        RuleFile::color(self, state)
+    }
+}
+
+/// The neighbors a rule looks at, in the order its transitions list them.
+fn neighbor_indices(neighborhood: Neighborhood) -> &'static [usize] {
+    // Rule files only have the two neighborhoods of radius 1
+    match neighborhood.shape {
+        Shape::VonNeumann => &[0, 2, 4, 6],
+        _ => &[0, 1, 2, 3, 4, 5, 6, 7],
+    }
+}
+
+fn parse_neighborhood(name: &str) -> Result<Neighborhood, String> {
+    match name {
+        "Moore" => Ok(Neighborhood::MOORE),
+        "vonNeumann" => Ok(Neighborhood::VON_NEUMANN),
+        _ => Err(format!("Unsupported neighborhood \"{name}\", expected Moore or vonNeumann")),
+    }
+}
+
+/// A set of states as 256 bits.
+type States = [u64; 4];
+
+fn has(set: &States, state: u8) -> bool {
+    set[state as usize / 64] & (1 << (state % 64)) != 0
+}
+
+/// One cell of a transition: the states it matches, and the variable they are bound to if the
+/// transition names one, so that every use of that variable matches the same state.
+#[derive(Clone, Debug, PartialEq)]
+struct Input {
+    states: States,
+    variable: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Output {
+    State(u8),
+    Variable(usize),
+}
+
+/// A transition: the center cell, then its neighbors, then what the center becomes.
+#[derive(Clone, Debug, PartialEq)]
+struct Transition {
+    inputs: Vec<Input>,
+    output: Output,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Table {
+    states: u8,
+    neighborhood: Neighborhood,
+    symmetries: Vec<Vec<usize>>, // orderings of the neighbors to try, unless `permute`
+    permute: bool,               // the neighbors may match in any order
+    variables: usize,
+    transitions: Vec<Transition>,
+}
+
+impl Table {
+    fn parse(lines: &[&str]) -> Result<Table, String> {
+        let mut states = None;
+        let mut neighborhood = Neighborhood::MOORE;
+        let mut symmetry_name = "none".to_string();
+        let mut variables: Vec<(String, States)> = Vec::new();
+        let mut rows: Vec<&str> = Vec::new();
+        for &line in lines {
+            if let Some(value) = line.strip_prefix("n_states:") {
+                let n = value.trim().parse::<u16>().map_err(|_| format!("Bad number of states \"{value}\""))?;
+                states = Some(u8::try_from(n).ok().filter(|&n| n >= 2).ok_or("The number of states must be from 2 to 255")?);
+            } else if let Some(value) = line.strip_prefix("neighborhood:") {
+                neighborhood = parse_neighborhood(value.trim())?;
+            } else if let Some(value) = line.strip_prefix("symmetries:") {
+                symmetry_name = value.trim().to_string();
+            } else if let Some(definition) = line.strip_prefix("var ") {
+                let (name, values) = definition.split_once('=').ok_or_else(|| format!("Bad variable \"{line}\""))?;
+                let set = parse_set(values.trim(), &variables)?;
+                variables.push((name.trim().to_string(), set));
+            } else {
+                rows.push(line);
+            }
+        }
+        let states = states.ok_or("Missing n_states")?;
+
+        let count = neighbor_indices(neighborhood).len();
+        let ring = |step: usize, reflect: bool| -> Vec<Vec<usize>> {
+            (0..count / step)
+                .flat_map(|turn| {
+                    let rotated = move |i: usize| (i + turn * step) % count;
+                    let mut orders = vec![(0..count).map(rotated).collect::<Vec<_>>()];
+                    if reflect {
+                        orders.push((0..count).map(|i| rotated((count - i) % count)).collect());
+                    }
+                    orders
+                })
+                .collect()
+        };
+        // Rotating by a quarter turn moves two places round the 8 Moore neighbors but one round
+        // the 4 von Neumann ones
+        let quarter = count / 4;
+        let symmetries = match symmetry_name.as_str() {
+            "none" | "permute" => ring(count, false),
+            "rotate4" => ring(quarter, false),
+            "rotate8" if count == 8 => ring(1, false),
+            "reflect_horizontal" => ring(count, true),
+            "rotate4reflect" => ring(quarter, true),
+            "rotate8reflect" if count == 8 => ring(1, true),
+            other => return Err(format!("Unsupported symmetries \"{other}\"")),
+        };
+
+        let mut transitions = Vec::new();
+        for row in rows {
+            // Rules with up to 10 states may leave out the commas: 0000000011
+            let tokens: Vec<String> = if !row.contains(',') && row.chars().all(|c| c.is_ascii_digit()) {
+                row.chars().map(String::from).collect()
+            } else {
+                split_top_level(row).into_iter().map(|t| t.trim().to_string()).collect()
+            };
+            if tokens.len() != count + 2 {
+                return Err(format!("Expected {} entries in transition \"{row}\"", count + 2));
+            }
+            let mut inputs = Vec::new();
+            for token in &tokens[..count + 1] {
+                let variable = variables.iter().position(|(name, _)| name == token);
+                let states = match variable {
+                    Some(index) => variables[index].1,
+                    None => parse_set(token, &variables)?,
+                };
+                inputs.push(Input { states, variable });
+            }
+            let last = &tokens[count + 1];
+            let output = match variables.iter().position(|(name, _)| name == last) {
+                Some(index) if inputs.iter().any(|input| input.variable == Some(index)) => Output::Variable(index),
+                Some(_) => return Err(format!("Output variable \"{last}\" isn't used earlier in \"{row}\"")),
+                None => match last.parse::<u8>() {
+                    Ok(state) if state < states => Output::State(state),
+                    _ => return Err(format!("Bad output \"{last}\" in \"{row}\"")),
+                },
+            };
+            transitions.push(Transition { inputs, output });
+        }
+
+        Ok(Table {
+            states,
+            neighborhood,
+            symmetries,
+            permute: symmetry_name == "permute",
+            variables: variables.len(),
+            transitions,
+        })
+    }
+
+    /// The output of the first transition that matches, or the same state if none do.
+    fn next(&self, center: u8, neighbors: [u8; 8]) -> u8 {
+        let indices = neighbor_indices(self.neighborhood);
+        let neighbors: Vec<u8> = indices.iter().map(|&i| neighbors[i]).collect();
+        let mut bound = vec![None; self.variables];
+        for transition in &self.transitions {
+            bound.fill(None);
+            if !bind(&transition.inputs[0], center, &mut bound) {
+                continue;
+            }
+            let pattern = &transition.inputs[1..];
+            let matched = if self.permute {
+                let mut used = vec![false; neighbors.len()];
+                match_any_order(pattern, &neighbors, &mut used, &mut bound)
+            } else {
+                self.symmetries.iter().any(|order| {
+                    let mut attempt = bound.clone();
+                    let all = pattern.iter().zip(order).all(|(input, &i)| bind(input, neighbors[i], &mut attempt));
+                    if all {
+                        bound = attempt;
+                    }
+                    all
+                })
+            };
+            if matched {
+                return match transition.output {
+                    Output::State(state) => state,
+                    Output::Variable(index) => bound[index].unwrap_or(center),
+                };
+            }
+        }
+        center
+    }
+}
+
+/// Whether the state fits the input, binding its variable to the state on first use.
+fn bind(input: &Input, state: u8, bound: &mut [Option<u8>]) -> bool {
+    if !has(&input.states, state) {
+        return false;
+    }
+    match input.variable {
+        Some(index) => match bound[index] {
+            Some(value) => value == state,
+            None => {
+                bound[index] = Some(state);
+                true
+            }
+        },
+        None => true,
+    }
+}
+
+/// Whether the pattern's inputs can each be matched to a different one of the neighbors.
+fn match_any_order(pattern: &[Input], neighbors: &[u8], used: &mut [bool], bound: &mut Vec<Option<u8>>) -> bool {
+    let Some((input, rest)) = pattern.split_first() else {
+        return true;
+    };
+    for i in 0..neighbors.len() {
+        if used[i] {
+            continue;
+        }
+        let mut attempt = bound.clone();
+        if bind(input, neighbors[i], &mut attempt) {
+            used[i] = true;
+            if match_any_order(rest, neighbors, used, &mut attempt) {
+                *bound = attempt;
+                return true;
+            }
+            used[i] = false;
+        }
+    }
+    false
+}
+
+/// Splits at the commas that aren't inside braces.
+fn split_top_level(text: &str) -> Vec<&str> {
+    let (mut parts, mut depth, mut start) = (Vec::new(), 0, 0);
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// A state, a variable or a braced list of those.
+fn parse_set(text: &str, variables: &[(String, States)]) -> Result<States, String> {
+    if let Some(list) = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+        let mut set = [0; 4];
+        for item in split_top_level(list) {
+            for (word, bits) in set.iter_mut().zip(parse_set(item.trim(), variables)?) {
+                *word |= bits;
+            }
+        }
+        return Ok(set);
+    }
+    if let Some((_, set)) = variables.iter().find(|(name, _)| name == text) {
+        return Ok(*set);
+    }
+    let state = text.parse::<u8>().map_err(|_| format!("Unknown state or variable \"{text}\""))?;
+    let mut set = [0; 4];
+    set[state as usize / 64] |= 1 << (state % 64);
+    Ok(set)
+}
+
+/// A decision tree with one level per cell of the neighborhood. Each node has a child for every
+/// state of its cell; the nodes one above the leaves hold the next states themselves.
+#[derive(Clone, Debug, PartialEq)]
+struct Tree {
+    states: u8,
+    neighborhood: Neighborhood,
+    nodes: Vec<Vec<u32>>, // the root is the last node
+}
+
+impl Tree {
+    fn parse(lines: &[&str]) -> Result<Tree, String> {
+        let header = |key: &str| -> Result<u32, String> {
+            let line = lines.iter().find(|line| line.starts_with(key)).ok_or(format!("Missing {key}"))?;
+            line[key.len()..].trim().parse().map_err(|_| format!("Bad value in \"{line}\""))
+        };
+        let states = u8::try_from(header("num_states=")?).ok().filter(|&n| n >= 2).ok_or("The number of states must be from 2 to 255")?;
+        let neighborhood = match header("num_neighbors=")? {
+            8 => Neighborhood::MOORE,
+            4 => Neighborhood::VON_NEUMANN,
+            n => return Err(format!("Unsupported number of neighbors {n}, expected 4 or 8")),
+        };
+        let count = header("num_nodes=")? as usize;
+
+        let mut levels: Vec<u32> = Vec::new();
+        let mut nodes = Vec::new();
+        for line in lines.iter().filter(|line| line.starts_with(|c: char| c.is_ascii_digit())) {
+            let numbers: Vec<u32> = line
+                .split_whitespace()
+                .map(|n| n.parse().map_err(|_| format!("Bad number in node \"{line}\"")))
+                .collect::<Result<_, _>>()?;
+            let (&level, children) = numbers.split_first().ok_or("Empty node")?;
+            if children.len() != states as usize {
+                return Err(format!("Expected {states} children in node \"{line}\""));
+            }
+            // Children are states at level 1 and earlier nodes one level down above that
+            let valid = |&child: &u32| match level {
+                1 => child < states as u32,
+                _ => levels.get(child as usize) == Some(&(level - 1)),
+            };
+            if level == 0 || !children.iter().all(valid) {
+                return Err(format!("Bad children in node \"{line}\""));
+            }
+            levels.push(level);
+            nodes.push(children.to_vec());
+        }
+        let depth = neighbor_indices(neighborhood).len() as u32 + 1;
+        if nodes.len() != count || levels.last() != Some(&depth) {
+            return Err(format!("Expected {count} nodes ending with the root at level {depth}"));
+        }
+        Ok(Tree { states, neighborhood, nodes })
+    }
+
+    /// Follows the tree down through the neighbors in Golly's order, then the center cell.
+    fn next(&self, center: u8, neighbors: [u8; 8]) -> u8 {
+        let order: &[usize] = match self.neighborhood.shape {
+            Shape::VonNeumann => &[0, 6, 2, 4],   // N, W, E, S
+            _ => &[7, 1, 5, 3, 0, 6, 2, 4], // NW, NE, SW, SE, N, W, E, S
+        };
+        let mut node = self.nodes.len() - 1;
+        for &i in order {
+            node = self.nodes[node][neighbors[i] as usize] as usize;
+        }
+        self.nodes[node][center as usize] as u8
+    }
+}
+
+/// `@COLORS` lines: `state r g b` for one state, or `r g b r g b` for a gradient from state 1
+/// to the last state.
+fn parse_colors(lines: &[&str], states: u8) -> Result<Vec<Option<[u8; 3]>>, String> {
+    let mut colors = vec![None; states as usize];
+    for line in lines {
+        let numbers: Vec<u8> = line
+            .split_whitespace()
+            .map(|n| n.parse().map_err(|_| format!("Bad color \"{line}\"")))
+            .collect::<Result<_, _>>()?;
+        match numbers[..] {
+            [state, r, g, b] if state < states => colors[state as usize] = Some([r, g, b]),
+            [r0, g0, b0, r1, g1, b1] => {
+                let last = states as usize - 1;
+                for (state, color) in colors.iter_mut().enumerate().skip(1) {
+                    let t = if last > 1 { (state - 1) as f32 / (last - 1) as f32 } else { 0.0 };
+                    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                    *color = Some([mix(r0, r1), mix(g0, g1), mix(b0, b1)]);
+                }
+            }
+            _ => return Err(format!("Bad color \"{line}\"")),
+        }
+    }
+    Ok(colors)
+}
@@ -0,0 +1,40 @@
+//! Pictures of the whole universe as PNG files, drawn a few pixels per cell instead of captured
+//! from the screen. Native builds write the file, the web build hands it to the browser as a
+//! download.
+
+use eframe::egui;
+
+/// Most pixels an image may have; the scale comes down to fit.
+pub const MAX_PIXELS: usize = 1 << 26;
+
+/// Encodes an image as an RGB PNG.
+pub fn encode(image: &egui::ColorImage) -> Result<Vec<u8>, String> {
+    let [width, height] = image.size;
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    let data: Vec<u8> = image.pixels.iter().flat_map(|color| [color.r(), color.g(), color.b()]).collect();
+    writer.write_image_data(&data).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// Offers a file to the browser as a download named `name`.
+#[cfg(target_arch = "wasm32")]
+pub fn download(name: &str, mime_type: &str, bytes: &[u8]) -> Result<(), String> {
+    use wasm_bindgen::JsCast;
+    let refused = |_| "The browser refused the download".to_string();
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options).map_err(refused)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(refused)?;
+    let document = web_sys::window().and_then(|window| window.document()).ok_or("No document")?;
+    let link: web_sys::HtmlAnchorElement = document.create_element("a").map_err(refused)?.dyn_into().map_err(|_| "No link element")?;
+    link.set_href(&url);
+    link.set_download(name);
+    link.click();
+    web_sys::Url::revoke_object_url(&url).map_err(refused)
+}
@@ -0,0 +1,45 @@
+//! Rectangular selections of cells.
+
+/// The cells with `left <= x < right` and `top <= y < bottom`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub left: i64,
+    pub top: i64,
+    pub right: i64,
+    pub bottom: i64,
+}
+
+impl Selection {
+    /// The smallest selection containing both cells, e.g. where a drag started and ended.
+    pub fn from_corners((x0, y0): (i64, i64), (x1, y1): (i64, i64)) -> Self {
+        Self {
+            left: x0.min(x1),
+            top: y0.min(y1),
+            right: x0.max(x1) + 1,
+            bottom: y0.max(y1) + 1,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.right - self.left) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.bottom - self.top) as usize
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        (self.left..self.right).contains(&x) && (self.top..self.bottom).contains(&y)
+    }
+
+    /// The part of the selection that lies within a `width` x `height` grid, if any.
+    pub fn clamped(&self, width: usize, height: usize) -> Option<Selection> {
+        let clamped = Self {
+            left: self.left.max(0),
+            top: self.top.max(0),
+            right: self.right.min(width as i64),
+            bottom: self.bottom.min(height as i64),
+        };
+        (clamped.left < clamped.right && clamped.top < clamped.bottom).then_some(clamped)
+    }
+}
@@ -0,0 +1,58 @@
+//! Sessions: the universe and the settings around it, saved to a file so that a setup outlives
+//! the window. They are written as RON, the JSON-like format eframe keeps its settings in.
+
+use eframe::egui::Pos2;
+use serde::{Deserialize, Serialize};
+
+use crate::noise::Noise;
+use crate::universe::Universe;
+
+const DEFLATED: &str = "deflate:"; // Marks a stored session as deflated
+
+/// Everything a session file holds. Rule files, ants, the 3D universe and history aren't kept.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub universe: Universe, // grid, topology, rule and generation
+    pub engine: String, // name of the engine, which is skipped if this build doesn't have it
+    pub update_frequency: f32,
+    pub warp: bool,
+    pub warp_generations: u64,
+    pub warp_unlimited: bool,
+    pub step_exponent: u32,
+    pub camera_center: Pos2,
+    pub cell_size: f32,
+    pub noise: Noise,
+    pub color_by_age: bool,
+    pub age_span: u32,
+    pub trail_length: u32,
+}
+
+impl Session {
+    /// Key of the session eframe keeps between runs.
+    pub const STORAGE_KEY: &'static str = "session";
+
+    pub fn to_text(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())
+    }
+
+    pub fn parse(text: &str) -> Result<Session, String> {
+        ron::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// The session as eframe keeps it between runs, and as crash recovery snapshots it: RON,
+    /// deflated on the web, where local storage only holds a few megabytes.
+    pub fn to_stored(&self) -> Result<String, String> {
+        let text = ron::to_string(self).map_err(|e| e.to_string())?;
+        #[cfg(target_arch = "wasm32")]
+        let text = format!("{DEFLATED}{}", crate::permalink::deflate(&text));
+        Ok(text)
+    }
+
+    /// Reads a session kept by `to_stored`, deflated or not.
+    pub fn from_stored(text: &str) -> Result<Session, String> {
+        match text.strip_prefix(DEFLATED) {
+            Some(deflated) => Session::parse(&crate::permalink::inflate(deflated)?),
+            None => Session::parse(text),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Cell coordinates covered by the shapes the drawing tools produce.
+
+/// The cells on a straight line between two cells, both ends included, without gaps.
+pub fn line((x0, y0): (i64, i64), (x1, y1): (i64, i64)) -> Vec<(i64, i64)> {
+    // Bresenham's algorithm
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+    let mut cells = vec![(x, y)];
+    while (x, y) != (x1, y1) {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        cells.push((x, y));
+    }
+    cells
+}
+
+/// The footprint of the painting brush.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrushShape {
+    Square,
+    Circle,
+    Cross,
+}
+
+impl BrushShape {
+    pub const ALL: [BrushShape; 3] = [BrushShape::Square, BrushShape::Circle, BrushShape::Cross];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BrushShape::Square => "Square",
+            BrushShape::Circle => "Circle",
+            BrushShape::Cross => "Cross",
+        }
+    }
+
+    /// Offsets from the center of every cell the brush covers; a radius of 0 is a single cell.
+    pub fn offsets(self, radius: i64) -> Vec<(i64, i64)> {
+        let mut offsets = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let covered = match self {
+                    BrushShape::Square => true,
+                    // The + radius lets the circle reach the middle of each side
+                    BrushShape::Circle => dx * dx + dy * dy <= radius * radius + radius,
+                    BrushShape::Cross => dx == 0 || dy == 0,
+                };
+                if covered {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        offsets
+    }
+}
+
+/// The cells of the rectangle with the given opposite corners, or just its outline.
+pub fn rectangle((x0, y0): (i64, i64), (x1, y1): (i64, i64), filled: bool) -> Vec<(i64, i64)> {
+    let (left, right, top, bottom) = (x0.min(x1), x0.max(x1), y0.min(y1), y0.max(y1));
+    let mut cells = Vec::new();
+    for y in top..=bottom {
+        for x in left..=right {
+            if filled || x == left || x == right || y == top || y == bottom {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+/// The cells of the ellipse inscribed in the rectangle with the given opposite corners, or
+/// just the ones on its edge.
+pub fn ellipse((x0, y0): (i64, i64), (x1, y1): (i64, i64), filled: bool) -> Vec<(i64, i64)> {
+    let (left, right, top, bottom) = (x0.min(x1), x0.max(x1), y0.min(y1), y0.max(y1));
+    let (rx, ry) = ((right - left + 1) as f64 / 2.0, (bottom - top + 1) as f64 / 2.0);
+    let (cx, cy) = (left as f64 + rx, top as f64 + ry);
+    // A cell is inside if its center is
+    let inside = |x: i64, y: i64| {
+        let (dx, dy) = ((x as f64 + 0.5 - cx) / rx, (y as f64 + 0.5 - cy) / ry);
+        dx * dx + dy * dy <= 1.0
+    };
+
+    let mut cells = Vec::new();
+    for y in top..=bottom {
+        for x in left..=right {
+            // The edge is every inside cell with a neighbor outside, which leaves no gaps
+            let edge = || !inside(x - 1, y) || !inside(x + 1, y) || !inside(x, y - 1) || !inside(x, y + 1);
+            if inside(x, y) && (filled || edge()) {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+/// The cells reachable from `start` through edge-adjacent cells for which `open` holds, or
+/// `None` if there are more than `limit` of them (e.g. the region is unbounded).
+pub fn flood_fill(start: (i64, i64), limit: usize, open: impl Fn(i64, i64) -> bool) -> Option<Vec<(i64, i64)>> {
+    if !open(start.0, start.1) {
+        return Some(Vec::new());
+    }
+    let mut seen = std::collections::HashSet::from([start]);
+    let mut stack = vec![start];
+    let mut cells = Vec::new();
+    while let Some((x, y)) = stack.pop() {
+        cells.push((x, y));
+        if cells.len() > limit {
+            return None;
+        }
+        for next in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if open(next.0, next.1) && seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    Some(cells)
+}
+
+/// Symmetry applied while editing: every cell that is edited also gets its images edited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    MirrorX,  // mirrored across the vertical axis
+    MirrorY,  // mirrored across the horizontal axis
+    FourFold, // mirrored across both axes
+    Rotate2,  // rotated by 180°
+    Rotate4,  // rotated by 90°, 180° and 270°
+    Dihedral8, // rotated by quarter turns and mirrored across both axes and both diagonals
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 7] = [
+        Symmetry::None,
+        Symmetry::MirrorX,
+        Symmetry::MirrorY,
+        Symmetry::FourFold,
+        Symmetry::Rotate2,
+        Symmetry::Rotate4,
+        Symmetry::Dihedral8,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Symmetry::None => "None",
+            Symmetry::MirrorX => "Mirror X",
+            Symmetry::MirrorY => "Mirror Y",
+            Symmetry::FourFold => "Mirror X and Y",
+            Symmetry::Rotate2 => "Rotate 180°",
+            Symmetry::Rotate4 => "Rotate 90°",
+            Symmetry::Dihedral8 => "Rotate 90° and mirror",
+        }
+    }
+
+    /// The symmetry group as soup searchers write it.
+    pub fn group(self) -> &'static str {
+        match self {
+            Symmetry::None => "C1",
+            Symmetry::MirrorX | Symmetry::MirrorY => "D2",
+            Symmetry::FourFold => "D4",
+            Symmetry::Rotate2 => "C2",
+            Symmetry::Rotate4 => "C4",
+            Symmetry::Dihedral8 => "D8",
+        }
+    }
+
+    /// Whether the images include quarter turns, which only stay inside a square.
+    pub fn turns_quarter(self) -> bool {
+        matches!(self, Symmetry::Rotate4 | Symmetry::Dihedral8)
+    }
+
+    /// The cell and its images. The center is given doubled, `(2 * cx, 2 * cy)`, so that it
+    /// can fall between cells: the mirror image of `x` is `2 * cx - x`.
+    pub fn images(self, (x, y): (i64, i64), (sum_x, sum_y): (i64, i64)) -> Vec<(i64, i64)> {
+        let (mx, my) = (sum_x - x, sum_y - y);
+        // Quarter turns, rounded onto the grid if the center's halves don't line up
+        let quarter = || {
+            let (dx, dy) = (2 * x - sum_x, 2 * y - sum_y);
+            [((sum_x - dy).div_euclid(2), (sum_y + dx).div_euclid(2)), ((sum_x + dy).div_euclid(2), (sum_y - dx).div_euclid(2))]
+        };
+        match self {
+            Symmetry::None => vec![(x, y)],
+            Symmetry::MirrorX => vec![(x, y), (mx, y)],
+            Symmetry::MirrorY => vec![(x, y), (x, my)],
+            Symmetry::FourFold => vec![(x, y), (mx, y), (x, my), (mx, my)],
+            Symmetry::Rotate2 => vec![(x, y), (mx, my)],
+            Symmetry::Rotate4 => {
+                let [a, b] = quarter();
+                vec![(x, y), a, (mx, my), b]
+            }
+            Symmetry::Dihedral8 => {
+                // The quarter turns of the cell and of its mirror image
+                let [a, b] = quarter();
+                let (ax, ay) = a;
+                let (bx, by) = b;
+                vec![(x, y), a, (mx, my), b, (mx, y), (sum_x - ax, ay), (x, my), (sum_x - bx, by)]
+            }
+        }
+    }
+}
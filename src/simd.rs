@@ -0,0 +1,100 @@
+//! SIMD kernel for the bit-packed engine: applies a Life-like rule to whole arrays of words.
+//!
+//! With the `simd` feature on x86_64, AVX2 is used when the CPU supports it, processing four
+//! words (256 cells) per instruction. Everywhere else, including wasm, a scalar loop is used.
+
+use crate::rule::{self, Rule};
+
+/// Computes the next words of one row from the left/center/right neighbor words of the rows
+/// above, at and below it. All slices have the same length as `out`.
+pub fn next_words(above: &[Vec<u64>; 3], row: &[Vec<u64>; 3], below: &[Vec<u64>; 3], out: &mut [u64], rule: Rule) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Safety: AVX2 support was just checked
+            unsafe { avx2::next_words(above, row, below, out, rule) };
+            return;
+        }
+    }
+    next_words_scalar(above, row, below, out, rule, 0);
+}
+
+/// Scalar version of the kernel for words `start..`.
+fn next_words_scalar(above: &[Vec<u64>; 3], row: &[Vec<u64>; 3], below: &[Vec<u64>; 3], out: &mut [u64], rule: Rule, start: usize) {
+    for k in start..out.len() {
+        let neighbors = [above[0][k], above[1][k], above[2][k], row[0][k], row[2][k], below[0][k], below[1][k], below[2][k]];
+
+        // Bit-sliced neighbor counts, as in `BitGrid::step`
+        let mut counts = [0u64; 4];
+        for n in neighbors {
+            rule::add_to_counts(&mut counts, n);
+        }
+        out[k] = rule.next_word(counts, row[1][k]);
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    use crate::rule::Rule;
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn next_words(above: &[Vec<u64>; 3], row: &[Vec<u64>; 3], below: &[Vec<u64>; 3], out: &mut [u64], rule: Rule) {
+        let load = |words: &[u64], k: usize| _mm256_loadu_si256(words[k..].as_ptr() as *const __m256i);
+
+        let lanes = out.len() / 4 * 4;
+        for k in (0..lanes).step_by(4) {
+            let neighbors = [
+                load(&above[0], k),
+                load(&above[1], k),
+                load(&above[2], k),
+                load(&row[0], k),
+                load(&row[2], k),
+                load(&below[0], k),
+                load(&below[1], k),
+                load(&below[2], k),
+            ];
+
+            let mut s = [_mm256_setzero_si256(); 4];
+            for n in neighbors {
+                let carry0 = _mm256_and_si256(s[0], n);
+                s[0] = _mm256_xor_si256(s[0], n);
+                let carry1 = _mm256_and_si256(s[1], carry0);
+                s[1] = _mm256_xor_si256(s[1], carry0);
+                let carry2 = _mm256_and_si256(s[2], carry1);
+                s[2] = _mm256_xor_si256(s[2], carry1);
+                s[3] = _mm256_xor_si256(s[3], carry2);
+            }
+
+            // andnot(a, b) computes !a & b
+            let center = load(&row[1], k);
+            let next = if rule == Rule::CONWAY {
+                // s1 & !s2 & !s3 & (s0 | center)
+                let two_or_three = _mm256_andnot_si256(s[3], _mm256_andnot_si256(s[2], s[1]));
+                _mm256_and_si256(two_or_three, _mm256_or_si256(s[0], center))
+            } else {
+                let (mut born, mut survive) = (_mm256_setzero_si256(), _mm256_setzero_si256());
+                for n in 0..=8 {
+                    if (rule.birth | rule.survival) & (1 << n) == 0 {
+                        continue;
+                    }
+                    let mut equal = _mm256_set1_epi64x(-1);
+                    for (i, &bit) in s.iter().enumerate() {
+                        equal = if n & (1 << i) != 0 { _mm256_and_si256(equal, bit) } else { _mm256_andnot_si256(bit, equal) };
+                    }
+                    if rule.birth & (1 << n) != 0 {
+                        born = _mm256_or_si256(born, equal);
+                    }
+                    if rule.survival & (1 << n) != 0 {
+                        survive = _mm256_or_si256(survive, equal);
+                    }
+                }
+                _mm256_or_si256(_mm256_andnot_si256(center, born), _mm256_and_si256(survive, center))
+            };
+            _mm256_storeu_si256(out[k..].as_mut_ptr() as *mut __m256i, next);
+        }
+
+        super::next_words_scalar(above, row, below, out, rule, lanes);
+    }
+}
@@ -0,0 +1,510 @@
+//! Telling objects apart and naming them, for the census of the universe in the app, and soup
+//! searches: many random soups, one per seed, each run in an infinite universe until it
+//! settles, followed by a census of the objects it leaves behind. `game_of_life soups` totals
+//! them up in a report.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+
+#[cfg(not(target_arch = "wasm32"))]
+use clap::Args;
+
+use crate::formats::rle;
+use crate::library::LIBRARY;
+use crate::pattern::Pattern;
+use crate::rng::Rng;
+use crate::rule::Rule;
+use crate::universe::{Engine, Universe};
+
+const MAX_PERIOD: u64 = 64; // Longest population cycle and object period looked for
+const SETTLED_REPEATS: u64 = 4; // Times the population cycle repeats before a soup counts as settled
+const MIN_SETTLED_SPAN: u64 = 100; // Fewest generations the population cycle has to hold for
+const CENSUS_INTERVAL: u64 = 100; // Generations between censuses while one finds objects that never settle
+const TOP_SOUPS: usize = 10; // Longest-lived and most populous soups listed in the report
+
+/// Runs random soups and writes a census of what they settle into.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Args, Debug)]
+pub struct SoupArgs {
+    /// Seeds to run, one soup each, both ends included
+    #[arg(long, value_name = "FIRST-LAST", default_value = "1-1000", value_parser = parse_seeds)]
+    seeds: RangeInclusive<u64>,
+    /// Soup size in cells
+    #[arg(long, value_name = "WIDTHxHEIGHT", default_value = "16x16", value_parser = crate::cli::parse_size)]
+    size: (usize, usize),
+    /// Chance of each soup cell starting alive
+    #[arg(long, default_value_t = 0.5)]
+    density: f64,
+    /// Rule to run, in B/S notation on the 8 adjacent cells
+    #[arg(long, default_value_t = Rule::default(), value_parser = Rule::parse)]
+    rule: Rule,
+    /// Generations after which a soup that hasn't settled is given up on
+    #[arg(long, value_name = "N", default_value_t = 50_000)]
+    max_generations: u64,
+    /// File the report is written to
+    #[arg(long, value_name = "FILE", default_value = "soups.txt")]
+    report: String,
+}
+
+/// Runs the search as the `soups` subcommand asks, showing progress on stderr.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run(args: SoupArgs) -> Result<String, String> {
+    use rayon::prelude::*;
+
+    let SoupArgs { seeds, size: (width, height), density, rule, max_generations, report } = args;
+    let search = Search::new(Settings { width, height, density, rule, max_generations })?;
+    let (mut first, last) = (*seeds.start(), *seeds.end());
+    let count = last - first + 1;
+    let mut results = Vec::new();
+    // Chunks keep the progress moving without a lock around the results
+    loop {
+        let end = first.saturating_add(255).min(last);
+        results.par_extend((first..=end).into_par_iter().map(|seed| search.run(seed)));
+        eprint!("\r{} of {count} soups", results.len());
+        if end == last {
+            break;
+        }
+        first = end + 1;
+    }
+    eprintln!();
+    let census = Census::new(&search, &results);
+    std::fs::write(&report, census.report()).map_err(|e| format!("Failed to save {report}: {e}"))?;
+    Ok(format!("Ran {} soups, {} settled, finding {} kinds of object; report saved to {report}", results.len(), census.settled, census.objects.len()))
+}
+
+/// Reads a seed range such as `1-1000`, or a single seed.
+pub fn parse_seeds(text: &str) -> Result<RangeInclusive<u64>, String> {
+    let parse = |seed: &str| seed.trim().parse::<u64>().map_err(|_| format!("{text} isn't a seed range such as 1-1000"));
+    let (first, last) = match text.split_once('-') {
+        Some((first, last)) => (parse(first)?, parse(last)?),
+        None => (parse(text)?, parse(text)?),
+    };
+    match first <= last {
+        true => Ok(first..=last),
+        false => Err(format!("The seed range {text} is empty")),
+    }
+}
+
+/// How every soup of a search is made and run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+    pub width: usize,
+    pub height: usize,
+    pub density: f64,
+    pub rule: Rule,
+    pub max_generations: u64,
+}
+
+/// What an object does when left alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    StillLife,
+    Oscillator(u64),
+    Spaceship(u64),
+    Unknown, // didn't repeat within MAX_PERIOD generations
+}
+
+impl Kind {
+    pub fn name(self) -> String {
+        match self {
+            Kind::StillLife => "still life".to_string(),
+            Kind::Oscillator(period) => format!("oscillator, period {period}"),
+            Kind::Spaceship(period) => format!("spaceship, period {period}"),
+            Kind::Unknown => "unknown".to_string(),
+        }
+    }
+}
+
+/// A cluster of live cells, classified by running it on its own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Object {
+    pub kind: Kind,
+    pub shape: Pattern, // the same for every phase and orientation of the object
+}
+
+impl Object {
+    /// Classifies the live cells, which shouldn't touch anything else.
+    pub fn classify(cells: &[(i64, i64)], rule: Rule) -> Object {
+        let mut universe = infinite_universe(rule);
+        for &(x, y) in cells {
+            universe.set(x, y, true);
+        }
+        let first = Pattern::from_cells(cells.to_vec());
+        let corner = |cells: &[(i64, i64)]| cells.iter().copied().reduce(|(x0, y0), (x, y)| (x0.min(x), y0.min(y)));
+        let start = corner(cells);
+        let mut phases = vec![first.clone()];
+        for period in 1..=MAX_PERIOD {
+            universe.advance(1);
+            let live: Vec<(i64, i64)> = universe.live_cells().collect();
+            let pattern = Pattern::from_cells(live.clone());
+            if pattern == first && !first.is_empty() {
+                let kind = match (period, corner(&live) == start) {
+                    (1, true) => Kind::StillLife,
+                    (_, true) => Kind::Oscillator(period),
+                    (_, false) => Kind::Spaceship(period),
+                };
+                return Object { kind, shape: phases.iter().map(canonical).min_by_key(shape_key).unwrap() };
+            }
+            phases.push(pattern);
+        }
+        Object { kind: Kind::Unknown, shape: canonical(&first) }
+    }
+
+    /// The object's code: `xs` and the cells of a still life, `xp` and the period of an
+    /// oscillator, `xq` and the period of a spaceship, after those of apgsearch.
+    pub fn code(&self) -> String {
+        match self.kind {
+            Kind::StillLife => format!("xs{}", self.shape.cells.len()),
+            Kind::Oscillator(period) => format!("xp{period}"),
+            Kind::Spaceship(period) => format!("xq{period}"),
+            Kind::Unknown => "unknown".to_string(),
+        }
+    }
+
+    /// The object's apgcode, as Catagolue names objects: the code, then the shape in extended
+    /// Wechsler format. Objects that don't repeat have none.
+    pub fn apgcode(&self) -> Option<String> {
+        match self.kind {
+            _ if self.shape.is_empty() => Some("xs0_0".to_string()),
+            Kind::Unknown => None,
+            _ => Some(format!("{}_{}", self.code(), wechsler(&self.shape))),
+        }
+    }
+
+    /// The shape as one line of RLE, without the header.
+    pub fn rle(&self) -> String {
+        rle::write_cells(&self.shape).lines().collect()
+    }
+}
+
+/// The orientation of a pattern that sorts first, so rotated and mirrored copies match.
+fn canonical(pattern: &Pattern) -> Pattern {
+    let mut orientations = Vec::new();
+    let mut turned = pattern.clone();
+    for _ in 0..4 {
+        orientations.push(turned.flip_horizontal());
+        turned = turned.rotate_clockwise();
+        orientations.push(turned.clone());
+    }
+    orientations.into_iter().min_by_key(shape_key).unwrap()
+}
+
+/// Shapes sort as apgsearch picks the phase and orientation for an apgcode: the shortest
+/// extended Wechsler format first, then alphabetically.
+fn shape_key(pattern: &Pattern) -> (usize, String) {
+    let code = wechsler(pattern);
+    (code.len(), code)
+}
+
+/// The shape in extended Wechsler format: strips of five rows, separated by `z`, each written a
+/// column at a time as a digit in base 32 with the top row as the lowest bit. Runs of empty
+/// columns shorten to `0`, `w`, `x` or `y` and a count, and those at the ends of strips are left
+/// out.
+fn wechsler(pattern: &Pattern) -> String {
+    const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let strips = pattern.height.div_ceil(5).max(1);
+    let mut columns = vec![0u8; strips * pattern.width];
+    for &(x, y) in &pattern.cells {
+        columns[y / 5 * pattern.width + x] |= 1 << (y % 5);
+    }
+    let mut code = String::new();
+    for (i, strip) in columns.chunks(pattern.width.max(1)).enumerate() {
+        if i > 0 {
+            code.push('z');
+        }
+        let mut zeroes = 0;
+        for &column in strip {
+            if column == 0 {
+                zeroes += 1;
+                continue;
+            }
+            while zeroes > 39 {
+                code.push_str("yz");
+                zeroes -= 39;
+            }
+            match zeroes {
+                0 => {}
+                1 => code.push('0'),
+                2 => code.push('w'),
+                3 => code.push('x'),
+                _ => {
+                    code.push('y');
+                    code.push(DIGITS[zeroes - 4] as char);
+                }
+            }
+            zeroes = 0;
+            code.push(DIGITS[column as usize] as char);
+        }
+    }
+    code
+}
+
+/// Where Catagolue shows an object under a rule, for rules written in B/S notation.
+pub fn catagolue_url(apgcode: &str, rule: Rule) -> String {
+    let rule = rule.to_string().to_lowercase().replace('/', "");
+    format!("https://catagolue.hatsya.com/object/{apgcode}/{rule}")
+}
+
+fn infinite_universe(rule: Rule) -> Universe {
+    let mut universe = Universe::new(1, 1);
+    let _ = universe.set_rule(rule);
+    universe.set_infinite(true);
+    universe
+}
+
+/// Splits live cells into clusters of cells no more than two apart, which can't affect each
+/// other under a rule of the adjacent cells.
+pub fn clusters(cells: impl Iterator<Item = (i64, i64)>) -> Vec<Vec<(i64, i64)>> {
+    let mut left: HashSet<(i64, i64)> = cells.collect();
+    let mut clusters = Vec::new();
+    while let Some(&start) = left.iter().next() {
+        left.remove(&start);
+        let (mut cluster, mut next) = (Vec::new(), vec![start]);
+        while let Some((x, y)) = next.pop() {
+            cluster.push((x, y));
+            for dy in -2..=2 {
+                for dx in -2..=2 {
+                    if left.remove(&(x + dx, y + dy)) {
+                        next.push((x + dx, y + dy));
+                    }
+                }
+            }
+        }
+        clusters.push(cluster);
+    }
+    clusters
+}
+
+/// Checks that objects under the rule can be told apart and classified: that its cells only
+/// see their 8 neighbors, and that empty space stays empty.
+pub fn check_rule(rule: Rule) -> Result<(), String> {
+    if !Engine::HashLife.supports_rule(rule) {
+        return Err(format!("Objects can only be found under rules of the 8 adjacent cells without colors, blocks or rows, not {rule}"));
+    }
+    if infinite_universe(rule).births_from_nothing() {
+        return Err(format!("{rule} brings empty space to life, so nothing ever settles"));
+    }
+    Ok(())
+}
+
+/// Classifies every cluster of the live cells and counts each kind of object, most common
+/// first. The rule must pass `check_rule`.
+pub fn census(cells: impl Iterator<Item = (i64, i64)>, rule: Rule) -> Vec<(Object, usize)> {
+    let mut counts: Vec<(Object, usize)> = Vec::new();
+    for cluster in clusters(cells) {
+        let object = Object::classify(&cluster, rule);
+        match counts.iter_mut().find(|(counted, _)| *counted == object) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((object, 1)),
+        }
+    }
+    counts.sort_by_key(|(object, count)| (std::cmp::Reverse(*count), object.code(), object.rle()));
+    counts
+}
+
+/// The library's patterns classified under a rule, to name the objects that match them.
+pub struct ObjectNames(Vec<(Object, &'static str)>);
+
+impl ObjectNames {
+    pub fn new(rule: Rule) -> Self {
+        let names = LIBRARY
+            .iter()
+            .map(|entry| {
+                let cells: Vec<(i64, i64)> = entry.pattern().cells.iter().map(|&(x, y)| (x as i64, y as i64)).collect();
+                (Object::classify(&cells, rule), entry.name)
+            })
+            .filter(|(object, _)| object.kind != Kind::Unknown)
+            .collect();
+        Self(names)
+    }
+
+    /// The library's name for an object, if it has one.
+    pub fn get(&self, object: &Object) -> Option<&'static str> {
+        self.0.iter().find(|(named, _)| named == object).map(|&(_, name)| name)
+    }
+}
+
+/// How one soup went.
+#[derive(Clone, Debug)]
+pub struct SoupResult {
+    pub seed: u64,
+    pub lifespan: Option<u64>, // generations until its population settled into its final cycle, if it did
+    pub population: usize,     // once settled, or when given up on
+    pub objects: Vec<Object>,  // the census once settled
+}
+
+/// A search with its settings checked and the library's objects named under its rule.
+pub struct Search {
+    settings: Settings,
+    names: ObjectNames,
+}
+
+impl Search {
+    pub fn new(settings: Settings) -> Result<Search, String> {
+        check_rule(settings.rule)?;
+        Ok(Search { settings, names: ObjectNames::new(settings.rule) })
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// The library's name for an object, if it has one.
+    pub fn name(&self, object: &Object) -> Option<&'static str> {
+        self.names.get(object)
+    }
+
+    /// The soup from a seed.
+    pub fn soup(&self, seed: u64) -> Universe {
+        let mut universe = infinite_universe(self.settings.rule);
+        let mut rng = Rng::new(seed);
+        for y in 0..self.settings.height as i64 {
+            for x in 0..self.settings.width as i64 {
+                if rng.chance(self.settings.density) {
+                    universe.set(x, y, true);
+                }
+            }
+        }
+        universe
+    }
+
+    /// Runs the soup from a seed until it settles: until its population has cycled for a while
+    /// and every object in it repeats on its own.
+    pub fn run(&self, seed: u64) -> SoupResult {
+        let mut universe = self.soup(seed);
+        let mut populations = vec![universe.population()];
+        let mut next_census = 0;
+        while universe.generation() < self.settings.max_generations {
+            universe.advance(1);
+            populations.push(universe.population());
+            let Some(lifespan) = settled_since(&populations) else { continue };
+            if universe.generation() < next_census {
+                continue;
+            }
+            let objects: Vec<Object> = clusters(universe.live_cells()).iter().map(|cells| Object::classify(cells, self.settings.rule)).collect();
+            if objects.iter().all(|object| object.kind != Kind::Unknown) {
+                return SoupResult { seed, lifespan: Some(lifespan), population: universe.population(), objects };
+            }
+            next_census = universe.generation() + CENSUS_INTERVAL;
+        }
+        SoupResult { seed, lifespan: None, population: universe.population(), objects: Vec::new() }
+    }
+}
+
+/// The generation the population's final cycle started at, if the last populations cycle with a
+/// period of at most MAX_PERIOD and have done so for long enough.
+fn settled_since(populations: &[usize]) -> Option<u64> {
+    let last = populations.len() - 1;
+    (1..=MAX_PERIOD as usize).find_map(|period| {
+        let span = (SETTLED_REPEATS as usize * period).max(MIN_SETTLED_SPAN as usize);
+        let repeats = |t: usize| populations[t] == populations[t - period];
+        if last < span + period || !(last - span..=last).all(repeats) {
+            return None;
+        }
+        let start = (period..=last).rev().take_while(|&t| repeats(t)).last().unwrap_or(last);
+        Some((start - period) as u64)
+    })
+}
+
+/// Totals over the soups of a search.
+pub struct Census<'a> {
+    search: &'a Search,
+    pub soups: usize,
+    pub settled: usize,
+    pub objects: Vec<Tally>, // most common first
+    lifespans: Vec<(u64, u64)>, // (lifespan, seed) of the settled soups, longest first
+    populations: Vec<(usize, u64)>, // (final population, seed) of the settled soups, largest first
+    unsettled: Vec<u64>,
+}
+
+/// How often one kind of object turned up.
+pub struct Tally {
+    pub object: Object,
+    pub count: usize,
+    pub soups: usize, // soups it turned up in
+    pub first_seed: u64,
+}
+
+impl<'a> Census<'a> {
+    pub fn new(search: &'a Search, results: &[SoupResult]) -> Census<'a> {
+        let mut tallies: HashMap<String, Tally> = HashMap::new();
+        for result in results {
+            let mut seen = HashSet::new();
+            for object in &result.objects {
+                let key = format!("{}_{}", object.code(), object.rle());
+                let tally = tallies.entry(key.clone()).or_insert(Tally { object: object.clone(), count: 0, soups: 0, first_seed: result.seed });
+                tally.count += 1;
+                tally.first_seed = tally.first_seed.min(result.seed);
+                if seen.insert(key) {
+                    tally.soups += 1;
+                }
+            }
+        }
+        let mut objects: Vec<Tally> = tallies.into_values().collect();
+        objects.sort_by_key(|tally| (std::cmp::Reverse(tally.count), tally.object.code(), tally.object.rle()));
+
+        let settled: Vec<&SoupResult> = results.iter().filter(|result| result.lifespan.is_some()).collect();
+        let mut lifespans: Vec<(u64, u64)> = settled.iter().map(|result| (result.lifespan.unwrap_or(0), result.seed)).collect();
+        lifespans.sort_by_key(|&(lifespan, seed)| (std::cmp::Reverse(lifespan), seed));
+        let mut populations: Vec<(usize, u64)> = settled.iter().map(|result| (result.population, result.seed)).collect();
+        populations.sort_by_key(|&(population, seed)| (std::cmp::Reverse(population), seed));
+        let unsettled = results.iter().filter(|result| result.lifespan.is_none()).map(|result| result.seed).collect();
+        Census { search, soups: results.len(), settled: settled.len(), objects, lifespans, populations, unsettled }
+    }
+
+    /// The census as plain text: the settings, the spread of lifespans and final populations,
+    /// the soups that stand out, and every kind of object with how often it turned up.
+    pub fn report(&self) -> String {
+        let settings = self.search.settings();
+        let mut report = format!(
+            "Soup search: {} soups of {}x{} cells at density {} under {}\n",
+            self.soups, settings.width, settings.height, settings.density, settings.rule
+        );
+        report.push_str(&format!("Settled: {} ({} gave up after {} generations)\n", self.settled, self.unsettled.len(), settings.max_generations));
+        if !self.lifespans.is_empty() {
+            let lifespans: Vec<u64> = self.lifespans.iter().map(|&(lifespan, _)| lifespan).collect();
+            let populations: Vec<u64> = self.populations.iter().map(|&(population, _)| population as u64).collect();
+            report.push_str(&format!("Lifespan: {}\n", spread(&lifespans)));
+            report.push_str(&format!("Final population: {}\n", spread(&populations)));
+            let objects: usize = self.objects.iter().map(|tally| tally.count).sum();
+            report.push_str(&format!("Objects: {objects} of {} kinds\n", self.objects.len()));
+        }
+
+        report.push_str("\nLongest-lived soups (seed: generations)\n");
+        for &(lifespan, seed) in self.lifespans.iter().take(TOP_SOUPS) {
+            report.push_str(&format!("  {seed}: {lifespan}\n"));
+        }
+        report.push_str("\nMost populous settled soups (seed: cells)\n");
+        for &(population, seed) in self.populations.iter().take(TOP_SOUPS) {
+            report.push_str(&format!("  {seed}: {population}\n"));
+        }
+        if !self.unsettled.is_empty() {
+            let seeds: Vec<String> = self.unsettled.iter().map(u64::to_string).collect();
+            report.push_str(&format!("\nUnsettled seeds: {}\n", seeds.join(", ")));
+        }
+
+        report.push_str("\nObjects (count, soups, apgcode, name, first seed, shape)\n");
+        for tally in &self.objects {
+            let name = self.search.name(&tally.object).unwrap_or("-");
+            report.push_str(&format!(
+                "{:>8} {:>7}  {:<16} {:<16} {:>8}  {}  ({})\n",
+                tally.count,
+                tally.soups,
+                tally.object.apgcode().unwrap_or_else(|| tally.object.code()),
+                name,
+                tally.first_seed,
+                tally.object.rle(),
+                tally.object.kind.name()
+            ));
+        }
+        report
+    }
+}
+
+/// Mean, median and range of some numbers, which mustn't be empty.
+fn spread(values: &[u64]) -> String {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mean = sorted.iter().sum::<u64>() as f64 / sorted.len() as f64;
+    format!("mean {mean:.1}, median {}, min {}, max {}", sorted[sorted.len() / 2], sorted[0], sorted[sorted.len() - 1])
+}
@@ -0,0 +1,88 @@
+//! Population, births, deaths and the size of the bounding box over the generations, for
+//! charting how a pattern grows and settles and for exporting as CSV.
+
+use crate::universe::Universe;
+
+/// Cells that came to life and cells that died between two universes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub births: usize,
+    pub deaths: usize,
+}
+
+/// The population at one generation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    pub generation: u64,
+    pub population: usize,
+    pub changes: Option<Changes>, // since the previous step, when this generation was stepped to
+    pub size: Option<(usize, usize)>, // width and height of the live cells' bounding box, if any are alive
+}
+
+impl Sample {
+    pub fn new(universe: &Universe, changes: Option<Changes>) -> Self {
+        let bounds = universe.live_cells().fold(None, |bounds: Option<(i64, i64, i64, i64)>, (x, y)| {
+            Some(bounds.map_or((x, y, x, y), |(left, top, right, bottom)| (left.min(x), top.min(y), right.max(x), bottom.max(y))))
+        });
+        let size = bounds.map(|(left, top, right, bottom)| ((right - left + 1) as usize, (bottom - top + 1) as usize));
+        Self { generation: universe.generation(), population: universe.population(), changes, size }
+    }
+}
+
+/// Samples in order of generation. Once there are more than the limit, every other one is
+/// dropped, so a long run is kept at a lower resolution instead of being cut off.
+pub struct PopulationLog {
+    samples: Vec<Sample>,
+    limit: usize,
+}
+
+impl PopulationLog {
+    pub fn new(limit: usize) -> Self {
+        Self { samples: Vec::new(), limit: limit.max(2) }
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Records the universe's population at its generation, along with the births and deaths
+    /// that led to it if it was just stepped to. Samples from that generation on are replaced,
+    /// so going back in time or editing the cells rewrites the log from there.
+    pub fn record(&mut self, universe: &Universe, changes: Option<Changes>) {
+        let current = (universe.generation(), universe.population());
+        if changes.is_none() && self.samples.last().is_some_and(|last| (last.generation, last.population) == current) {
+            return;
+        }
+        let sample = Sample::new(universe, changes);
+        let keep = self.samples.partition_point(|s| s.generation < sample.generation);
+        self.samples.truncate(keep);
+        self.samples.push(sample);
+
+        if self.samples.len() > self.limit {
+            // Keep the first and the latest, and every other one in between
+            let last = self.samples.len() - 1;
+            let mut index = 0;
+            self.samples.retain(|_| {
+                let keep = index % 2 == 0 || index == last;
+                index += 1;
+                keep
+            });
+        }
+    }
+
+    /// The samples as CSV, a header line and then a line a generation. Births and deaths are
+    /// left empty for generations that weren't stepped to, and the bounding box for empty ones.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "generation,population,births,deaths,width,height\n".to_string();
+        for sample in &self.samples {
+            let (births, deaths) = sample.changes.map_or((String::new(), String::new()), |c| (c.births.to_string(), c.deaths.to_string()));
+            let (width, height) = sample.size.map_or((String::new(), String::new()), |(w, h)| (w.to_string(), h.to_string()));
+            csv.push_str(&format!("{},{},{births},{deaths},{width},{height}\n", sample.generation, sample.population));
+        }
+        csv
+    }
+}
@@ -0,0 +1,42 @@
+//! SVG pictures of the cells, vector art that stays crisp at any size. The cells of each color
+//! are merged into one path of horizontal runs, which keeps files small and avoids the hairline
+//! gaps that viewers draw between separate squares.
+
+use std::collections::BTreeMap;
+
+use eframe::egui::Color32;
+
+/// An SVG of the area, given as (left, top, width, height) in cells and shown `scale` pixels
+/// per cell unless resized, with the cells in their colors on a background.
+pub fn write(cells: impl Iterator<Item = ((i64, i64), Color32)>, (left, top, width, height): (i64, i64, usize, usize), scale: usize, background: Color32) -> String {
+    let mut rows: BTreeMap<[u8; 3], BTreeMap<i64, Vec<i64>>> = BTreeMap::new();
+    for ((x, y), color) in cells.filter(|&(_, color)| color != background) {
+        rows.entry([color.r(), color.g(), color.b()]).or_default().entry(y - top).or_default().push(x - left);
+    }
+
+    let (pixel_width, pixel_height) = (width * scale, height * scale);
+    let mut svg = format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{pixel_width}" height="{pixel_height}" viewBox="0 0 {width} {height}" shape-rendering="crispEdges">"#);
+    svg.push_str(&format!("\n<rect width=\"{width}\" height=\"{height}\" fill=\"{}\"/>\n", hex([background.r(), background.g(), background.b()])));
+    for (color, rows) in rows {
+        let mut path = String::new();
+        for (y, mut xs) in rows {
+            xs.sort_unstable();
+            xs.dedup();
+            let mut xs = xs.into_iter().peekable();
+            while let Some(start) = xs.next() {
+                let mut end = start + 1;
+                while xs.next_if_eq(&end).is_some() {
+                    end += 1;
+                }
+                path.push_str(&format!("M{start} {y}h{}v1h-{}z", end - start, end - start));
+            }
+        }
+        svg.push_str(&format!("<path fill=\"{}\" d=\"{path}\"/>\n", hex(color)));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
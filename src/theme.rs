@@ -0,0 +1,155 @@
+//! Colors used to draw the grid, editable from the side panel and saved between runs, and
+//! whether the UI around it is dark or light.
+
+use eframe::egui::{Color32, Visuals};
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub alive: Color32,
+    pub dead: Color32,
+    pub grid_line: Color32,
+    pub background: Color32, // Around a finite grid
+    pub newborn: Color32,    // Age gradient, from cells born this generation...
+    pub old: Color32,        // ...to the oldest shade
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::for_dark_mode(true)
+    }
+}
+
+impl Theme {
+    /// Key the theme is stored under in eframe's storage.
+    pub const STORAGE_KEY: &'static str = "theme";
+
+    pub const CLASSIC: Theme = Theme {
+        alive: Color32::RED,
+        dead: Color32::BLACK,
+        grid_line: Color32::WHITE,
+        background: Color32::from_gray(27),
+        newborn: Color32::YELLOW,
+        old: Color32::from_rgb(40, 80, 255),
+    };
+
+    /// Blue and orange from the Okabe-Ito palette, which stay apart for red-green colorblindness.
+    pub const RED_GREEN_SAFE: Theme = Theme {
+        alive: Color32::from_rgb(230, 159, 0),
+        dead: Color32::BLACK,
+        grid_line: Color32::from_gray(110),
+        background: Color32::from_gray(27),
+        newborn: Color32::from_rgb(240, 228, 66),
+        old: Color32::from_rgb(0, 114, 178),
+    };
+
+    /// Vermillion and bluish green, which stay apart for blue-yellow colorblindness.
+    pub const BLUE_YELLOW_SAFE: Theme = Theme {
+        alive: Color32::from_rgb(213, 94, 0),
+        dead: Color32::BLACK,
+        grid_line: Color32::from_gray(110),
+        background: Color32::from_gray(27),
+        newborn: Color32::from_rgb(204, 121, 167),
+        old: Color32::from_rgb(0, 158, 115),
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        alive: Color32::WHITE,
+        dead: Color32::BLACK,
+        grid_line: Color32::from_gray(90),
+        background: Color32::from_rgb(0, 0, 90),
+        newborn: Color32::WHITE,
+        old: Color32::from_rgb(0, 200, 255),
+    };
+
+    pub const PRESETS: [(&'static str, Theme); 4] = [
+        ("Classic", Theme::CLASSIC),
+        ("Deuteranopia/protanopia safe", Theme::RED_GREEN_SAFE),
+        ("Tritanopia safe", Theme::BLUE_YELLOW_SAFE),
+        ("High contrast", Theme::HIGH_CONTRAST),
+    ];
+
+    /// Grid colors that go with egui's dark or light visuals: cells as dark as the darkest
+    /// widget background (or as light as the lightest), lines like separators, and the panel
+    /// color around the grid.
+    pub fn for_dark_mode(dark: bool) -> Theme {
+        let visuals = if dark { Visuals::dark() } else { Visuals::light() };
+        Theme {
+            alive: if dark { Color32::RED } else { Color32::from_rgb(200, 0, 0) },
+            dead: visuals.extreme_bg_color,
+            grid_line: visuals.widgets.noninteractive.bg_stroke.color,
+            background: visuals.panel_fill,
+            newborn: if dark { Color32::YELLOW } else { Color32::from_rgb(230, 150, 0) },
+            old: if dark { Color32::from_rgb(40, 80, 255) } else { Color32::from_rgb(0, 50, 200) },
+        }
+    }
+
+    /// Name of the matching preset, if any.
+    pub fn name(&self) -> &'static str {
+        Self::PRESETS.iter().find(|(_, t)| t == self).map_or("Custom", |(name, _)| name)
+    }
+
+    /// Between the dead and alive colors by `t` in 0..=1.
+    pub fn blend(&self, t: f32) -> Color32 {
+        lerp(self.dead, self.alive, t)
+    }
+
+    /// Color of a live cell: the alive color, or when its age is known, its place on the
+    /// newborn to old gradient, which reaches the old color at `span` generations.
+    pub fn cell_color(&self, age: Option<u32>, span: u32) -> Color32 {
+        match age {
+            Some(age) => lerp(self.newborn, self.old, age.min(span) as f32 / span.max(1) as f32),
+            None => self.alive,
+        }
+    }
+
+    /// Color of a cell that died `since` generations ago, fading from the alive to the dead
+    /// color over a trail of `trail` generations.
+    pub fn trail_color(&self, since: u32, trail: u32) -> Color32 {
+        self.blend(0.6 * (1.0 - since as f32 / (trail + 1) as f32))
+    }
+
+    /// Color of a dying cell under a rule with decay, fading from the old to the dead color as
+    /// its state goes from 2 up to `states - 1`.
+    pub fn decay_color(&self, state: u8, states: u8) -> Color32 {
+        lerp(self.old, self.dead, (state - 1) as f32 / (states - 1) as f32)
+    }
+}
+
+/// Linear interpolation between two opaque colors.
+fn lerp(a: Color32, b: Color32, t: f32) -> Color32 {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color32::from_rgb(mix(a.r(), b.r()), mix(a.g(), b.g()), mix(a.b(), b.b()))
+}
+
+/// Whether the UI is dark or light.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Appearance {
+    System, // Follow the OS or browser preference
+    Dark,
+    Light,
+}
+
+impl Appearance {
+    pub const ALL: [Appearance; 3] = [Appearance::System, Appearance::Dark, Appearance::Light];
+
+    /// Key the appearance is stored under in eframe's storage.
+    pub const STORAGE_KEY: &'static str = "appearance";
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Appearance::System => "System",
+            Appearance::Dark => "Dark",
+            Appearance::Light => "Light",
+        }
+    }
+
+    /// Whether to use dark visuals, given the system preference if known.
+    pub fn is_dark(self, system: Option<eframe::Theme>) -> bool {
+        match self {
+            Appearance::System => system != Some(eframe::Theme::Light),
+            Appearance::Dark => true,
+            Appearance::Light => false,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! How the edges of a finite grid are glued together, and neighbor addressing across them.
+
+/// What happens when stepping off one of a pair of opposite edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Edge {
+    Dead,   // cells beyond the edge are always dead
+    Wrap,   // continue from the opposite edge
+    Mirror, // cells beyond the edge reflect the cells just inside it
+    Twist,  // continue from the opposite edge, flipped along the other axis
+}
+
+impl Edge {
+    pub const ALL: [Edge; 4] = [Edge::Dead, Edge::Wrap, Edge::Mirror, Edge::Twist];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Edge::Dead => "Dead",
+            Edge::Wrap => "Wrap",
+            Edge::Mirror => "Mirror",
+            Edge::Twist => "Twisted wrap",
+        }
+    }
+}
+
+/// The edge behavior for the left/right pair and the top/bottom pair of edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Topology {
+    pub horizontal: Edge, // left and right edges
+    pub vertical: Edge,   // top and bottom edges
+}
+
+impl Topology {
+    pub const PLANE: Topology = Topology { horizontal: Edge::Dead, vertical: Edge::Dead };
+    pub const TORUS: Topology = Topology { horizontal: Edge::Wrap, vertical: Edge::Wrap };
+    pub const KLEIN_BOTTLE: Topology = Topology { horizontal: Edge::Wrap, vertical: Edge::Twist };
+    pub const CROSS_SURFACE: Topology = Topology { horizontal: Edge::Twist, vertical: Edge::Twist };
+    pub const MIRROR_BOX: Topology = Topology { horizontal: Edge::Mirror, vertical: Edge::Mirror };
+
+    pub const PRESETS: [(&'static str, Topology); 5] = [
+        ("Plane (dead edges)", Topology::PLANE),
+        ("Torus", Topology::TORUS),
+        ("Klein bottle", Topology::KLEIN_BOTTLE),
+        ("Cross-surface", Topology::CROSS_SURFACE),
+        ("Mirror box", Topology::MIRROR_BOX),
+    ];
+
+    /// Name of the matching preset, if any.
+    pub fn name(self) -> &'static str {
+        Self::PRESETS.iter().find(|(_, t)| *t == self).map_or("Custom", |(name, _)| name)
+    }
+
+    /// Reads a preset's name, such as `torus` or `klein-bottle`, an edge for all four sides,
+    /// such as `mirror`, or the left/right and top/bottom edges apart, as in `wrap,twist`.
+    /// Case, spaces and dashes don't matter.
+    pub fn parse(text: &str) -> Result<Topology, String> {
+        let simplify = |name: &str| -> String { name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect() };
+        let edge = |name: &str| Edge::ALL.into_iter().find(|edge| simplify(edge.name()).starts_with(&simplify(name)) && !name.trim().is_empty());
+        if let Some((horizontal, vertical)) = text.split_once(',') {
+            return match (edge(horizontal), edge(vertical)) {
+                (Some(horizontal), Some(vertical)) => Ok(Topology { horizontal, vertical }),
+                _ => Err(format!("Unknown edges {text}: each of the two is one of dead, wrap, mirror or twist")),
+            };
+        }
+        let preset = Self::PRESETS.iter().find(|(name, _)| simplify(name.split(" (").next().unwrap_or(name)) == simplify(text));
+        match (preset, edge(text)) {
+            (Some(&(_, topology)), _) => Ok(topology),
+            (None, Some(edge)) => Ok(Topology { horizontal: edge, vertical: edge }),
+            (None, None) => Err(format!("Unknown boundary {text}: try plane, torus, klein-bottle, cross-surface, mirror-box, or an edge such as wrap")),
+        }
+    }
+
+    /// Maps a possibly out-of-range coordinate on a `width` x `height` grid to the cell it
+    /// refers to, or `None` if it lies beyond a dead edge.
+    pub fn resolve(self, x: isize, y: isize, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (w, h) = (width as isize, height as isize);
+        let (mut x, mut y) = (x, y);
+
+        if x < 0 || x >= w {
+            match self.horizontal {
+                Edge::Dead => return None,
+                Edge::Wrap => x = x.rem_euclid(w),
+                Edge::Mirror => x = reflect(x, w),
+                Edge::Twist => {
+                    x = x.rem_euclid(w);
+                    y = h - 1 - y;
+                }
+            }
+        }
+        if y < 0 || y >= h {
+            match self.vertical {
+                Edge::Dead => return None,
+                Edge::Wrap => y = y.rem_euclid(h),
+                Edge::Mirror => y = reflect(y, h),
+                Edge::Twist => {
+                    y = y.rem_euclid(h);
+                    x = w - 1 - x;
+                }
+            }
+        }
+
+        // A twist on one axis can push the other coordinate out of range at the corners
+        if x < 0 || x >= w || y < 0 || y >= h {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+}
+
+/// Reflects a coordinate back into `0..len` as if the edges were mirrors, e.g. -1 -> 0.
+fn reflect(x: isize, len: isize) -> isize {
+    let period = 2 * len;
+    let x = x.rem_euclid(period);
+    if x < len { x } else { period - 1 - x }
+}
@@ -0,0 +1,181 @@
+//! Following objects from one generation to the next to pick out the spaceships. Each cluster
+//! of live cells is matched to the nearest one of the generation before, and a cluster that
+//! keeps moving is run on its own once to confirm that it is a spaceship.
+
+use std::collections::VecDeque;
+
+use crate::rule::Rule;
+use crate::soup::{self, Kind, Object, ObjectNames};
+use crate::universe::Universe;
+
+const MAX_TRACKED_CELLS: usize = 50_000; // More live cells than this are too slow to split up every generation
+const MAX_SHIP_CELLS: usize = 500; // Larger clusters aren't run to see if they are spaceships
+const MAX_STEP: u64 = 16; // Longest jump between generations that objects can be followed across
+const MIN_TRAVEL: f64 = 1.0; // Cells a cluster has to move before it is checked
+const RECHECK: u64 = 32; // Generations before a moving cluster that wasn't a spaceship is checked again
+const HISTORY: usize = 64; // Positions kept per track for its velocity
+
+/// A cluster followed across generations.
+#[derive(Clone, Debug)]
+pub struct Track {
+    pub id: u64,
+    pub bounds: (i64, i64, i64, i64), // left, top, right and bottom live cells
+    pub center: (f64, f64),          // mean position of the live cells, in cells
+    pub ship: Option<Object>,        // the spaceship it was found to be, if it is one
+    pub name: Option<&'static str>,  // the library's name for the spaceship
+    cells: usize,
+    positions: VecDeque<(u64, (f64, f64))>, // (generation, center), oldest first
+    checked: Option<u64>, // generation it was last run to see if it is a spaceship
+}
+
+impl Track {
+    /// Cells moved a generation, averaged over the generations it has been followed.
+    pub fn velocity(&self) -> (f64, f64) {
+        let (Some(&(first, (x0, y0))), Some(&(last, (x1, y1)))) = (self.positions.front(), self.positions.back()) else { return (0.0, 0.0) };
+        let generations = last.saturating_sub(first).max(1) as f64;
+        ((x1 - x0) / generations, (y1 - y0) / generations)
+    }
+
+    /// How far it has moved since it was first seen, in cells.
+    fn travel(&self) -> f64 {
+        let (Some(&(_, (x0, y0))), Some(&(_, (x1, y1)))) = (self.positions.front(), self.positions.back()) else { return 0.0 };
+        ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt()
+    }
+}
+
+/// The clusters of the last generation seen, matched up with those of the ones before.
+#[derive(Default)]
+pub struct Tracker {
+    tracks: Vec<Track>,
+    generation: Option<u64>,
+    rule: Option<Rule>,
+    names: Option<ObjectNames>,
+    next_id: u64,
+}
+
+impl Tracker {
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// The tracks confirmed as spaceships.
+    pub fn ships(&self) -> impl Iterator<Item = &Track> {
+        self.tracks.iter().filter(|track| track.ship.is_some())
+    }
+
+    pub fn find(&self, id: u64) -> Option<&Track> {
+        self.tracks.iter().find(|track| track.id == id)
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+        self.generation = None;
+    }
+
+    /// Follows the clusters into the universe's generation. Going back in time, jumping too
+    /// far ahead or a change of rule starts over, as do universes too large to split up; the
+    /// result says why nothing is being tracked, if it isn't.
+    pub fn update(&mut self, universe: &Universe) -> Result<(), String> {
+        let (generation, rule) = (universe.generation(), universe.rule());
+        if self.generation == Some(generation) && self.rule == Some(rule) {
+            return Ok(());
+        }
+        let unusable = match (universe.automaton(), universe.ants()) {
+            (None, None) => soup::check_rule(rule).err(),
+            _ => Some("Spaceships can only be tracked under a rule, not an automaton or ants".to_string()),
+        };
+        let too_big = (universe.population() > MAX_TRACKED_CELLS).then(|| format!("Too many live cells to track, over {MAX_TRACKED_CELLS}"));
+        if let Some(e) = unusable.or(too_big) {
+            self.clear();
+            return Err(e);
+        }
+        let step = match self.generation {
+            Some(last) if self.rule == Some(rule) && last < generation && generation - last <= MAX_STEP => generation - last,
+            _ => {
+                self.tracks.clear();
+                0
+            }
+        };
+        if self.rule != Some(rule) {
+            self.names = Some(ObjectNames::new(rule));
+        }
+        self.generation = Some(generation);
+        self.rule = Some(rule);
+
+        // Nearest pairs first, each cluster and track used once. Nothing goes faster than a cell a
+        // generation, and the center can shift by a little more as the shape changes.
+        let clusters = soup::clusters(universe.live_cells());
+        let summaries: Vec<_> = clusters.iter().map(|cells| summarize(cells)).collect();
+        let reach = (2 * step + 2) as f64;
+        let mut pairs = Vec::new();
+        for (t, track) in self.tracks.iter().enumerate() {
+            for (c, &(_, (x, y))) in summaries.iter().enumerate() {
+                let distance = ((x - track.center.0).powi(2) + (y - track.center.1).powi(2)).sqrt();
+                if distance <= reach {
+                    pairs.push((distance, t, c));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (mut matched_tracks, mut matched_clusters) = (vec![None; self.tracks.len()], vec![false; clusters.len()]);
+        for (_, t, c) in pairs {
+            if matched_tracks[t].is_none() && !matched_clusters[c] {
+                matched_tracks[t] = Some(c);
+                matched_clusters[c] = true;
+            }
+        }
+
+        let mut tracks = Vec::new();
+        for (track, cluster) in std::mem::take(&mut self.tracks).into_iter().zip(matched_tracks) {
+            let Some(c) = cluster else { continue };
+            let mut track = track;
+            (track.bounds, track.center) = summaries[c];
+            track.positions.push_back((generation, track.center));
+            if track.positions.len() > HISTORY {
+                track.positions.pop_front();
+            }
+            // A spaceship that gains or loses cells has run into something
+            if track.ship.as_ref().is_some_and(|ship| clusters[c].len().abs_diff(ship.shape.cells.len()) > ship.shape.cells.len() / 2) {
+                (track.ship, track.name, track.checked) = (None, None, None);
+            }
+            track.cells = clusters[c].len();
+            self.check(&mut track, &clusters[c], generation, rule);
+            tracks.push(track);
+        }
+        for (c, cells) in clusters.iter().enumerate().filter(|&(c, _)| !matched_clusters[c]) {
+            let (bounds, center) = summaries[c];
+            self.next_id += 1;
+            let positions = VecDeque::from([(generation, center)]);
+            tracks.push(Track { id: self.next_id, bounds, center, ship: None, name: None, cells: cells.len(), positions, checked: None });
+        }
+        self.tracks = tracks;
+        Ok(())
+    }
+
+    /// Runs a moving cluster on its own to see if it is a spaceship, unless that was done lately.
+    fn check(&self, track: &mut Track, cells: &[(i64, i64)], generation: u64, rule: Rule) {
+        let due = track.checked.is_none_or(|checked| generation >= checked + RECHECK);
+        if track.ship.is_some() || !due || track.cells > MAX_SHIP_CELLS || track.travel() < MIN_TRAVEL {
+            return;
+        }
+        track.checked = Some(generation);
+        let object = Object::classify(cells, rule);
+        if matches!(object.kind, Kind::Spaceship(_)) {
+            track.name = self.names.as_ref().and_then(|names| names.get(&object));
+            track.ship = Some(object);
+        }
+    }
+}
+
+/// The bounds and mean position of a cluster's cells.
+fn summarize(cells: &[(i64, i64)]) -> ((i64, i64, i64, i64), (f64, f64)) {
+    let mut bounds = (i64::MAX, i64::MAX, i64::MIN, i64::MIN);
+    let (mut sum_x, mut sum_y) = (0.0, 0.0);
+    for &(x, y) in cells {
+        bounds = (bounds.0.min(x), bounds.1.min(y), bounds.2.max(x), bounds.3.max(y));
+        sum_x += x as f64;
+        sum_y += y as f64;
+    }
+    let count = cells.len().max(1) as f64;
+    (bounds, (sum_x / count, sum_y / count))
+}
@@ -0,0 +1,186 @@
+//! A terminal frontend, `game_of_life tui`, on the same engine as the app. The grid is drawn
+//! with half-block characters, two cells to a character, so that it runs over SSH and without a
+//! window system.
+
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect, Size};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::rng::Rng;
+use crate::rule::Rule;
+use crate::topology::Topology;
+use crate::universe::Universe;
+
+/// Runs in the terminal. Without a pattern the terminal fills with a random soup that wraps
+/// around its edges.
+#[derive(Args, Debug)]
+pub struct TuiArgs {
+    /// Pattern file to start from
+    #[arg(long, value_name = "FILE")]
+    pattern: Option<String>,
+    /// Rule to run, in B/S or another notation the app reads; by default the one the pattern
+    /// file names, or Conway's Life
+    #[arg(long, value_parser = Rule::parse)]
+    rule: Option<Rule>,
+}
+
+/// Generations a second that the speed keys step through.
+const SPEEDS: [u32; 10] = [1, 2, 5, 10, 20, 30, 60, 120, 250, 500];
+const FRAME: Duration = Duration::from_millis(16); // Shortest time between redraws
+const SOUP_DENSITY: f64 = 0.3;
+const HELP: &str = "Space play/pause  n step  +/- speed  arrows pan  r new soup  c clear  q quit";
+
+struct Tui {
+    universe: Universe,
+    rule: Rule,
+    soup: bool, // Whether the universe is a random soup the size of the terminal
+    left: i64,  // Cell shown in the top left corner
+    top: i64,
+    playing: bool,
+    speed: usize, // Index into SPEEDS
+    seed: u64,
+}
+
+/// Runs the terminal frontend as the `tui` subcommand asks, until it is quit.
+pub fn run(args: TuiArgs) -> Result<String, String> {
+    let TuiArgs { pattern, rule } = args;
+    let pattern = pattern.map(|path| crate::cli::load(&path, rule)).transpose()?;
+
+    let mut terminal = ratatui::init();
+    let rule = pattern.as_ref().map_or(rule.unwrap_or_default(), Universe::rule);
+    let mut tui = Tui { universe: Universe::new(1, 1), rule, soup: pattern.is_none(), left: 0, top: 0, playing: false, speed: 3, seed: 1 };
+    let started = match pattern {
+        Some(universe) => {
+            tui.universe = universe;
+            tui.center(grid_area(terminal.size().map_err(|e| e.to_string())?));
+            Ok(())
+        }
+        None => terminal.size().map_err(|e| e.to_string()).and_then(|size| tui.new_soup(grid_area(size))),
+    };
+    let ran = started.and_then(|()| tui.run(&mut terminal).map_err(|e| e.to_string()));
+    ratatui::restore();
+    ran?;
+    Ok(format!("Stopped at generation {} with {} cells", tui.universe.generation(), tui.universe.population()))
+}
+
+/// Where the grid goes on the screen: all but the border and the two status lines.
+fn grid_area(screen: Size) -> Rect {
+    let [grid, _] = Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(Rect::new(0, 0, screen.width, screen.height));
+    Block::bordered().inner(grid)
+}
+
+impl Tui {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        let mut next_step = Instant::now();
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            // Fast speeds step several generations a frame rather than redrawing for each
+            let speed = SPEEDS[self.speed];
+            let interval = Duration::from_secs_f64(1.0 / speed as f64).max(FRAME);
+            let wait = if self.playing { next_step.saturating_duration_since(Instant::now()) } else { Duration::from_secs(60) };
+            if event::poll(wait)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    let grid = grid_area(terminal.size()?);
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char(' ') => {
+                            self.playing = !self.playing;
+                            next_step = Instant::now();
+                        }
+                        KeyCode::Char('n') => self.universe.advance(1),
+                        KeyCode::Char('+') | KeyCode::Char('=') => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+                        KeyCode::Char('-') => self.speed = self.speed.saturating_sub(1),
+                        KeyCode::Left => self.left -= grid.width as i64 / 4,
+                        KeyCode::Right => self.left += grid.width as i64 / 4,
+                        KeyCode::Up => self.top -= grid.height as i64 / 2,
+                        KeyCode::Down => self.top += grid.height as i64 / 2,
+                        KeyCode::Char('r') => {
+                            self.seed += 1;
+                            let _ = self.new_soup(grid);
+                        }
+                        KeyCode::Char('c') => self.universe.clear(),
+                        _ => {}
+                    }
+                }
+            } else if self.playing && Instant::now() >= next_step {
+                let generations = ((speed as f64 * interval.as_secs_f64()).round() as u64).max(1);
+                self.universe.advance(generations);
+                next_step += interval;
+                // After falling behind, carry on from now instead of catching up in a burst
+                next_step = next_step.max(Instant::now());
+            }
+        }
+    }
+
+    /// Replaces the universe with a random soup filling the grid, on a torus.
+    fn new_soup(&mut self, grid: Rect) -> Result<(), String> {
+        let (width, height) = (grid.width.max(1) as usize, grid.height.max(1) as usize * 2);
+        let mut universe = Universe::new(width, height);
+        universe.set_topology(Topology::TORUS);
+        universe.set_rule(self.rule)?;
+        let mut rng = Rng::new(self.seed);
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                if rng.chance(SOUP_DENSITY) {
+                    universe.set(x, y, true);
+                }
+            }
+        }
+        self.universe = universe;
+        self.soup = true;
+        (self.left, self.top) = (0, 0);
+        Ok(())
+    }
+
+    /// Puts the middle of the live cells in the middle of the grid.
+    fn center(&mut self, grid: Rect) {
+        let cells: Vec<(i64, i64)> = self.universe.live_cells().collect();
+        let middle = |values: Vec<i64>| (values.iter().min().unwrap_or(&0) + values.iter().max().unwrap_or(&0)) / 2;
+        let (x, y) = (middle(cells.iter().map(|c| c.0).collect()), middle(cells.iter().map(|c| c.1).collect()));
+        self.left = x - grid.width as i64 / 2;
+        self.top = y - grid.height as i64;
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [grid, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+        let block = Block::bordered().title(format!(" {} ", self.rule));
+        let inner = block.inner(grid);
+        let alive = |x: i64, y: i64| self.universe.state(x, y) != 0;
+        let lines: Vec<Line> = (0..inner.height as i64)
+            .map(|row| {
+                let y = self.top + 2 * row;
+                let text: String = (0..inner.width as i64)
+                    .map(|column| match (alive(self.left + column, y), alive(self.left + column, y + 1)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect();
+                Line::from(text)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(block), grid);
+
+        let state = if self.playing { "Playing" } else { "Paused" };
+        let kind = if self.soup { "soup" } else { "pattern" };
+        let summary = format!(
+            "Generation {}  Population {}  {} gen/s  {state}  ({kind}, view at {}, {})",
+            self.universe.generation(),
+            self.universe.population(),
+            SPEEDS[self.speed],
+            self.left,
+            self.top
+        );
+        frame.render_widget(Paragraph::new(vec![Line::from(summary), Line::from(HELP)]), status);
+    }
+}
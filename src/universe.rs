@@ -0,0 +1,1340 @@
+//! The cell grid and the rules that advance it.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::ages::Ages;
+use crate::ants::{Ant, Turmite};
+use crate::automaton::Automaton;
+use crate::bitgrid::BitGrid;
+use crate::hashlife::HashLife;
+use crate::noise::Noise;
+use crate::pattern::Pattern;
+use crate::rainbow::Rainbow;
+use crate::rule::{self, neighbor_bit, LargerThanLife, Margolus, Neighborhood, Rule, NEIGHBORS};
+use crate::selection::Selection;
+use crate::stats::Changes;
+use crate::topology::Topology;
+
+/// The algorithm used to advance the universe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Engine {
+    Naive,     // visit every cell (or every live cell's neighborhood when infinite)
+    BitPacked, // 64 cells per word with bitwise neighbor counting; finite universes only
+    Simd,      // the bit-packed engine with a SIMD kernel (see the `simd` feature); finite only
+    Gpu,       // compute shader (see the `gpu` feature); finite only
+    HashLife,  // memoized quadtree; infinite universes only
+}
+
+impl Engine {
+    pub const ALL: [Engine; 5] = [Engine::Naive, Engine::BitPacked, Engine::Simd, Engine::Gpu, Engine::HashLife];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Naive => "Naive",
+            Engine::BitPacked => "Bit-packed",
+            Engine::Simd => "SIMD",
+            Engine::Gpu => "GPU",
+            Engine::HashLife => "HashLife",
+        }
+    }
+
+    pub fn supports_finite(self) -> bool {
+        self != Engine::HashLife
+    }
+
+    pub fn supports_infinite(self) -> bool {
+        !matches!(self, Engine::BitPacked | Engine::Simd | Engine::Gpu)
+    }
+
+    /// Whether the engine can simulate the rule: HashLife's blocks only see neighbors next to a
+    /// cell, not wider neighborhoods, and assume every cell follows the same rule every
+    /// generation, which Margolus blocks and the rows of elementary rules don't. Nor do they
+    /// have room for the colors of colored rules.
+    pub fn supports_rule(self, rule: Rule) -> bool {
+        let uniform = rule.margolus.is_none() && rule.elementary.is_none() && rule.colors == 1;
+        self != Engine::HashLife || (rule.larger_than_life.is_none() && uniform && rule.neighborhood.is_adjacent())
+    }
+
+    /// Whether the engine can run in this build: the GPU engine needs the `gpu` feature and a
+    /// GPU with compute shaders.
+    pub fn is_available(self) -> bool {
+        match self {
+            #[cfg(all(feature = "gpu", not(target_arch = "wasm32")))]
+            Engine::Gpu => crate::gpu::is_available(),
+            #[cfg(not(all(feature = "gpu", not(target_arch = "wasm32"))))]
+            Engine::Gpu => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Cells {
+    Dense(BitGrid),
+    Sparse(HashSet<(i64, i64)>), // coordinates of the live cells of an unbounded universe
+    HashLife(HashLife),
+}
+
+impl Cells {
+    /// Whether the cell is alive, for a cell inside a finite grid.
+    fn get(&self, x: i64, y: i64) -> bool {
+        match self {
+            Cells::Dense(cells) => cells.get(x as usize, y as usize),
+            Cells::Sparse(cells) => cells.contains(&(x, y)),
+            Cells::HashLife(hashlife) => hashlife.get(x, y),
+        }
+    }
+
+    fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        match self {
+            Cells::Dense(cells) => Box::new(cells.live_cells().map(|(x, y)| (x as i64, y as i64))),
+            Cells::Sparse(cells) => Box::new(cells.iter().copied()),
+            Cells::HashLife(hashlife) => Box::new(hashlife.live_cells().into_iter()),
+        }
+    }
+}
+
+/// A grid of cells addressed by `(x, y)`. A finite universe covers `0 <= x < width` and
+/// `0 <= y < height`, and what lies beyond its edges is decided by the topology. An infinite
+/// universe only stores its live cells and has no edges at all.
+#[derive(Clone)]
+pub struct Universe {
+    width: usize, // size of the finite grid; kept while infinite so it can be switched back
+    height: usize,
+    cells: Cells,
+    topology: Topology,
+    rule: Rule,
+    engine: Engine,
+    threads: usize, // worker threads used by the bit-packed engines
+    generation: u64, // generations advanced since the universe was last cleared
+    ages: Option<Ages>, // cell ages and trails of recently dead cells, while they are tracked
+    rainbow: Option<Rainbow>, // colors of the live cells for Rainbow Life, while they are tracked
+    higher: HashMap<(i64, i64), u8>, // cells in states above 1: dying under a rule with decay, or any state of an automaton
+    automaton: Option<Arc<dyn Automaton>>, // a rule file or other automaton, which replaces the rule while set
+    ants: Option<Vec<Ant>>, // turmites, which replace the rule and automaton while set
+    turmite: Turmite, // the table the ants follow
+    noise: Noise, // randomness added after each generation, if any
+}
+
+impl Universe {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: Cells::Dense(BitGrid::new(width, height)),
+            topology: Topology::PLANE,
+            rule: Rule::CONWAY,
+            engine: Engine::BitPacked,
+            threads: 1,
+            generation: 0,
+            ages: None,
+            rainbow: None,
+            higher: HashMap::new(),
+            automaton: None,
+            ants: None,
+            turmite: Turmite::langtons_ant(),
+            noise: Noise::default(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_infinite(&self) -> bool {
+        !matches!(self.cells, Cells::Dense(_))
+    }
+
+    /// Switches between finite and infinite storage, keeping every live cell that still fits.
+    /// Stays finite under a rule with births from nothing.
+    pub fn set_infinite(&mut self, infinite: bool) {
+        if infinite == self.is_infinite() || (infinite && self.births_from_nothing()) {
+            return;
+        }
+        let engine = match (infinite, self.engine) {
+            (true, engine) if engine.supports_infinite() => engine,
+            (false, engine) if engine.supports_finite() => engine,
+            (true, _) => Engine::Naive,
+            (false, _) => Engine::BitPacked,
+        };
+        self.convert(infinite, engine);
+    }
+
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    /// Switches the stepping algorithm. HashLife needs an unbounded universe and the bit-packed
+    /// engine a finite one, so choosing either may also switch between finite and infinite.
+    /// Engines that can't run the rule or automaton in use, or only unbounded when it brings
+    /// empty space to life, are refused and the engine stays as it was.
+    pub fn set_engine(&mut self, engine: Engine) -> Result<(), String> {
+        if engine == self.engine {
+            return Ok(());
+        }
+        let infinite = match self.is_infinite() {
+            true => engine.supports_infinite(),
+            false => !engine.supports_finite(),
+        };
+        if engine == Engine::HashLife && self.automaton.is_some() {
+            return Err("HashLife can't simulate rule files or other automata".to_string());
+        }
+        if !engine.supports_rule(self.rule) {
+            return Err(format!("{} can't simulate {}", engine.name(), self.rule));
+        }
+        if infinite && self.births_from_nothing() {
+            return Err(format!("{} needs an unbounded universe, and the rule in use brings empty space to life", engine.name()));
+        }
+        self.convert(infinite, engine);
+        Ok(())
+    }
+
+    fn convert(&mut self, infinite: bool, engine: Engine) {
+        let live: Vec<(i64, i64)> = self.live_cells().collect();
+        self.engine = engine;
+        self.cells = match (infinite, engine) {
+            (false, _) => Cells::Dense(BitGrid::new(self.width, self.height)),
+            (true, Engine::HashLife) => Cells::HashLife(HashLife::new()),
+            (true, _) => Cells::Sparse(HashSet::new()),
+        };
+        // Setting the cells would give them all the brush color
+        let rainbow = self.rainbow.take();
+        for (x, y) in live {
+            self.set(x, y, true);
+        }
+        self.rainbow = rainbow.map(|rainbow| rainbow.shifted(0, 0, |x, y| self.get(x, y)));
+        let (width, height) = (self.width as i64, self.height as i64);
+        if let Some(ants) = &mut self.ants {
+            ants.retain(|ant| infinite || ((0..width).contains(&ant.x) && (0..height).contains(&ant.y)));
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Sets how many threads the bit-packed engines split each generation across. Ignored on
+    /// the web, where everything runs on one thread.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// The trail length while ages are tracked, see `set_tracking`.
+    pub fn tracking(&self) -> Option<u32> {
+        self.ages.as_ref().map(Ages::trail)
+    }
+
+    /// Starts or stops keeping the age of every live cell and, for `trail` generations after
+    /// a cell dies, how long ago it died. Tracking costs a hash map update per live cell and
+    /// generation, so it is off by default; cells alive when it starts are counted as newborn.
+    pub fn set_tracking(&mut self, tracking: Option<u32>) {
+        match (&mut self.ages, tracking) {
+            (_, None) => self.ages = None,
+            (Some(ages), Some(trail)) => ages.set_trail(trail),
+            (None, Some(trail)) => self.ages = Some(Ages::new(self.live_cells(), trail)),
+        }
+    }
+
+    /// Generations the cell has been continuously alive, or `None` if it is dead or ages are
+    /// not being tracked.
+    pub fn age(&self, x: i64, y: i64) -> Option<u32> {
+        self.ages.as_ref()?.get(x, y)
+    }
+
+    /// Generations since the cell died, if it did within the tracked trail length.
+    pub fn since_death(&self, x: i64, y: i64) -> Option<u32> {
+        self.ages.as_ref()?.since_death(x, y)
+    }
+
+    /// The color cells drawn in get while Rainbow Life colors are tracked, see `set_rainbow`.
+    pub fn rainbow_brush(&self) -> Option<[u8; 3]> {
+        self.rainbow.as_ref().map(Rainbow::brush)
+    }
+
+    /// Starts or stops giving live cells colors that newborns inherit, averaged over their
+    /// parents, or changes the color cells drawn in get. Like ages, colors cost a hash map
+    /// update per live cell and generation; cells alive when it starts get the brush color.
+    pub fn set_rainbow(&mut self, brush: Option<[u8; 3]>) {
+        match (&mut self.rainbow, brush) {
+            (_, None) => self.rainbow = None,
+            (Some(rainbow), Some(brush)) => rainbow.set_brush(brush),
+            (None, Some(brush)) => self.rainbow = Some(Rainbow::new(self.live_cells(), brush)),
+        }
+    }
+
+    /// The Rainbow Life color of a live cell, if colors are tracked.
+    pub fn rainbow_color(&self, x: i64, y: i64) -> Option<[u8; 3]> {
+        self.rainbow.as_ref()?.get(x, y)
+    }
+
+    /// Recolors a live cell while Rainbow Life colors are tracked.
+    pub fn recolor(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if let Some(rainbow) = &mut self.rainbow {
+            rainbow.recolor(x, y, color);
+        }
+    }
+
+    /// The cells that died within the tracked trail length, with the generations since.
+    pub fn recent_deaths(&self) -> Box<dyn Iterator<Item = ((i64, i64), u32)> + '_> {
+        match &self.ages {
+            Some(ages) => Box::new(ages.deaths()),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    /// The cells that differ from `other` as rectangles `[x, y, width, height]`, or `None` when
+    /// the two can't be compared cell for cell because they aren't finite grids of the same size.
+    pub fn changed_regions(&self, other: &Universe) -> Option<Vec<[usize; 4]>> {
+        match (&self.cells, &other.cells) {
+            (Cells::Dense(a), Cells::Dense(b)) if self.width == other.width && self.height == other.height => {
+                let same_colors = self.rainbow == other.rainbow;
+                if self.higher == other.higher && same_colors {
+                    return Some(a.changed_regions(b, 32));
+                }
+                // Cells that changed between higher states, or between one and being alive or
+                // dead, or that changed color, are marked as changed by flipping them in a copy
+                // of the other grid
+                let mut marked = b.clone();
+                for &(x, y) in self.higher.keys().chain(other.higher.keys()) {
+                    if self.higher.get(&(x, y)) != other.higher.get(&(x, y)) {
+                        marked.set(x as usize, y as usize, !a.get(x as usize, y as usize));
+                    }
+                }
+                if !same_colors {
+                    let colors = |universe: &Universe| universe.rainbow.as_ref().map(|rainbow| rainbow.colors().collect::<Vec<_>>()).unwrap_or_default();
+                    for ((x, y), _) in colors(self).into_iter().chain(colors(other)) {
+                        if self.rainbow_color(x, y) != other.rainbow_color(x, y) {
+                            marked.set(x as usize, y as usize, !a.get(x as usize, y as usize));
+                        }
+                    }
+                }
+                Some(a.changed_regions(&marked, 32))
+            }
+            _ => None,
+        }
+    }
+
+    /// A hash of the cells in every state, equal for universes in the same state. Only meant to
+    /// be compared within one run of the program.
+    pub fn state_hash(&self) -> u64 {
+        fn hash(value: impl Hash) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+        // Summed so the order the cells come in doesn't matter
+        let higher = self.higher.iter().fold(0, |sum: u64, cell| sum.wrapping_add(hash(cell)));
+        let live = match &self.cells {
+            Cells::Dense(cells) => hash(cells),
+            _ => self.live_cells().fold(0, |sum: u64, cell| sum.wrapping_add(hash(cell))),
+        };
+        live ^ higher ^ hash(&self.ants)
+    }
+
+    /// Counts the cells alive here but not in `previous` and the other way around.
+    pub fn changes_since(&self, previous: &Universe) -> Changes {
+        match (&self.cells, &previous.cells) {
+            (Cells::Dense(a), Cells::Dense(b)) if self.width == previous.width && self.height == previous.height => {
+                a.changes_since(b)
+            }
+            _ => {
+                let before: HashSet<(i64, i64)> = previous.live_cells().collect();
+                let mut changes = Changes { births: 0, deaths: before.len() };
+                for cell in self.live_cells() {
+                    if before.contains(&cell) {
+                        changes.deaths -= 1;
+                    } else {
+                        changes.births += 1;
+                    }
+                }
+                changes
+            }
+        }
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Changes the rule the universe evolves by. Rules with births from nothing would fill an
+    /// unbounded universe, so they are refused unless it is finite, as are rules the engine
+    /// can't simulate.
+    pub fn set_rule(&mut self, rule: Rule) -> Result<(), String> {
+        if rule.births_from_nothing() && self.is_infinite() {
+            return Err(format!("{rule} brings empty space to life, so it needs a finite universe"));
+        }
+        if !self.engine.supports_rule(rule) {
+            return Err(match (rule.margolus, rule.elementary) {
+                (Some(_), _) => format!("{} can't simulate block rules such as {rule}", self.engine.name()),
+                (_, Some(_)) => format!("{} can't simulate one-dimensional rules such as {rule}", self.engine.name()),
+                _ if rule.colors > 1 => format!("{} can't keep track of the colors of {rule}", self.engine.name()),
+                _ => format!("{rule} reaches past the adjacent cells, which {} can't simulate", self.engine.name()),
+            });
+        }
+        self.rule = rule;
+        self.drop_unknown_states();
+        Ok(())
+    }
+
+    pub fn automaton(&self) -> Option<&dyn Automaton> {
+        self.automaton.as_deref()
+    }
+
+    /// Makes an automaton, such as a rule file, take the place of the rule, or with `None` goes
+    /// back to the rule. Like rules, automata with births from nothing need a finite universe,
+    /// and HashLife can't run them at all.
+    pub fn set_automaton(&mut self, automaton: Option<Arc<dyn Automaton>>) -> Result<(), String> {
+        if let Some(automaton) = &automaton {
+            if automaton.births_from_nothing() && self.is_infinite() {
+                return Err(format!("{} brings empty space to life, so it needs a finite universe", automaton.name()));
+            }
+            if self.engine == Engine::HashLife {
+                return Err(format!("HashLife can't run rule files or other automata such as {}", automaton.name()));
+            }
+        }
+        self.automaton = automaton;
+        self.drop_unknown_states();
+        Ok(())
+    }
+
+    /// The number of cell states of the ants' colors, the automaton or the rule, whichever is
+    /// in use.
+    pub fn states(&self) -> u8 {
+        match &self.automaton {
+            _ if self.ants.is_some() => self.turmite.colors,
+            Some(automaton) => automaton.states(),
+            None => self.rule.cell_states(),
+        }
+    }
+
+    /// Kills cells in higher states than what is in use has, and gives live cells back the
+    /// first color when the rule in use has no colors.
+    fn drop_unknown_states(&mut self) {
+        let states = self.states();
+        self.higher.retain(|_, state| *state < states);
+        if !self.has_colors() {
+            let colored: Vec<(i64, i64)> = self.higher.keys().copied().filter(|&(x, y)| self.get(x, y)).collect();
+            for cell in colored {
+                self.higher.remove(&cell);
+            }
+        }
+    }
+
+    /// Whether cells follow a colored rule, whose live cells can be in states above 1.
+    fn has_colors(&self) -> bool {
+        self.rule.colors > 1 && self.automaton.is_none() && self.ants.is_none()
+    }
+
+    /// Whether the rule or automaton in use brings empty space to life. Ants never do.
+    pub fn births_from_nothing(&self) -> bool {
+        match &self.automaton {
+            _ if self.ants.is_some() => false,
+            Some(automaton) => automaton.births_from_nothing(),
+            None => self.rule.births_from_nothing(),
+        }
+    }
+
+    /// The ants walking the grid, or `None` when the cells follow the rule.
+    pub fn ants(&self) -> Option<&[Ant]> {
+        self.ants.as_deref()
+    }
+
+    /// Lets ants take the place of the rule, starting with none, or with `false` removes them
+    /// all and goes back to the rule.
+    pub fn set_ant_mode(&mut self, on: bool) {
+        match on {
+            true => {
+                self.ants.get_or_insert_with(Vec::new);
+            }
+            false => self.ants = None,
+        }
+        self.drop_unknown_states();
+    }
+
+    pub fn turmite(&self) -> &Turmite {
+        &self.turmite
+    }
+
+    /// Changes the table the ants follow. Ants in states it doesn't have go back to state 0.
+    pub fn set_turmite(&mut self, turmite: Turmite) {
+        if turmite == self.turmite {
+            return;
+        }
+        if let Some(ants) = &mut self.ants {
+            for ant in ants.iter_mut().filter(|ant| ant.state >= turmite.states) {
+                ant.state = 0;
+            }
+        }
+        self.turmite = turmite;
+        self.drop_unknown_states();
+    }
+
+    /// Puts an ant facing north on the cell, or picks up the one already there. Only while ants
+    /// replace the rule, and only inside a finite grid.
+    pub fn toggle_ant(&mut self, x: i64, y: i64) {
+        if !self.contains(x, y) {
+            return;
+        }
+        if let Some(ants) = &mut self.ants {
+            match ants.iter().position(|ant| (ant.x, ant.y) == (x, y)) {
+                Some(i) => {
+                    ants.remove(i);
+                }
+                None => ants.push(Ant::new(x, y)),
+            }
+        }
+    }
+
+    pub fn noise(&self) -> Noise {
+        self.noise
+    }
+
+    /// Adds randomness to every generation from now on, or with the default `Noise` none.
+    pub fn set_noise(&mut self, noise: Noise) {
+        self.noise = noise;
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        self.is_infinite() || (x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height)
+    }
+
+    /// Whether the cell is alive; cells outside a finite grid are reported dead.
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        self.contains(x, y) && self.cells.get(x, y)
+    }
+
+    /// The cell's state under the rule: 0 if dead, 1 if alive, and from 2 up while dying or
+    /// in one of the other states of an automaton.
+    pub fn state(&self, x: i64, y: i64) -> u8 {
+        match self.higher.get(&(x, y)) {
+            Some(&state) => state,
+            None => self.get(x, y) as u8,
+        }
+    }
+
+    /// The cells in states above 1, with their states.
+    pub fn higher_states(&self) -> impl Iterator<Item = ((i64, i64), u8)> + '_ {
+        self.higher.iter().map(|(&cell, &state)| (cell, state))
+    }
+
+    /// Sets a cell's state; cells outside a finite grid are ignored. A cell in a higher state
+    /// set either way leaves it.
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if !self.contains(x, y) {
+            return;
+        }
+        self.set_alive(x, y, alive);
+        self.higher.remove(&(x, y));
+        if let Some(ages) = &mut self.ages {
+            ages.set(x, y, alive);
+        }
+        if let Some(rainbow) = &mut self.rainbow {
+            rainbow.set(x, y, alive);
+        }
+    }
+
+    /// Puts a cell in any state, for rules with more than two. Under a colored rule the states
+    /// up to the number of colors are alive.
+    pub fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        self.set(x, y, state == 1 || (self.has_colors() && state != 0 && state <= self.rule.colors));
+        if state > 1 && self.contains(x, y) {
+            self.higher.insert((x, y), state);
+        }
+    }
+
+    /// Sets just the stored cell, for stepping.
+    fn set_alive(&mut self, x: i64, y: i64, alive: bool) {
+        match &mut self.cells {
+            Cells::Dense(cells) => cells.set(x as usize, y as usize, alive),
+            Cells::Sparse(cells) => {
+                if alive {
+                    cells.insert((x, y));
+                } else {
+                    cells.remove(&(x, y));
+                }
+            }
+            Cells::HashLife(hashlife) => hashlife.set(x, y, alive),
+        }
+    }
+
+    pub fn toggle(&mut self, x: i64, y: i64) {
+        self.set(x, y, !self.get(x, y));
+    }
+
+    /// Empties the universe and restarts the generation count.
+    pub fn clear(&mut self) {
+        self.generation = 0;
+        self.higher.clear();
+        if let Some(ants) = &mut self.ants {
+            ants.clear();
+        }
+        if let Some(ages) = &mut self.ages {
+            ages.clear();
+        }
+        if let Some(rainbow) = &mut self.rainbow {
+            rainbow.clear();
+        }
+        match &mut self.cells {
+            Cells::Dense(cells) => cells.clear(),
+            Cells::Sparse(cells) => cells.clear(),
+            Cells::HashLife(hashlife) => hashlife.clear(),
+        }
+    }
+
+    /// Iterates over the coordinates of all live cells.
+    pub fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        self.cells.live_cells()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> usize {
+        match &self.cells {
+            Cells::Dense(cells) => cells.population(),
+            Cells::Sparse(cells) => cells.len(),
+            Cells::HashLife(hashlife) => hashlife.population() as usize,
+        }
+    }
+
+    /// Live cells of each color under a colored rule, first color first, or nothing under a
+    /// rule without colors.
+    pub fn color_populations(&self) -> Vec<usize> {
+        if !self.has_colors() {
+            return Vec::new();
+        }
+        let mut counts = vec![0; self.rule.colors as usize];
+        for (&(x, y), &color) in &self.higher {
+            if color <= self.rule.colors && self.get(x, y) {
+                counts[color as usize - 1] += 1;
+            }
+        }
+        counts[0] = self.population() - counts.iter().sum::<usize>();
+        counts
+    }
+
+    /// Rough number of bytes this universe takes up. HashLife nodes are shared between clones,
+    /// so they are left out.
+    pub fn memory_size(&self) -> usize {
+        let cells = match &self.cells {
+            Cells::Dense(cells) => cells.memory_size(),
+            Cells::Sparse(cells) => cells.capacity() * std::mem::size_of::<(i64, i64)>() * 2,
+            Cells::HashLife(_) => 0,
+        };
+        let higher = self.higher.capacity() * std::mem::size_of::<((i64, i64), u8)>();
+        let tracked = self.ages.as_ref().map_or(0, Ages::memory_size) + self.rainbow.as_ref().map_or(0, Rainbow::memory_size);
+        std::mem::size_of::<Self>() + cells + higher + tracked
+    }
+
+    /// The live cells, cropped to their bounding box.
+    pub fn to_pattern(&self) -> Pattern {
+        Pattern::from_cells(self.live_cells().collect())
+    }
+
+    /// The live cells inside the selection, as a pattern the size of the selection.
+    pub fn copy_region(&self, selection: Selection) -> Pattern {
+        let mut cells: Vec<(usize, usize)> = self
+            .live_cells()
+            .filter(|&(x, y)| selection.contains(x, y))
+            .map(|(x, y)| ((x - selection.left) as usize, (y - selection.top) as usize))
+            .collect();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        Pattern { width: selection.width(), height: selection.height(), cells }
+    }
+
+    /// Kills every cell inside the selection, whatever its state.
+    pub fn clear_region(&mut self, selection: Selection) {
+        let live: Vec<(i64, i64)> = self.live_cells().filter(|&(x, y)| selection.contains(x, y)).collect();
+        for (x, y) in live {
+            self.set(x, y, false);
+        }
+        self.higher.retain(|&(x, y), _| !selection.contains(x, y));
+    }
+
+    /// Returns a copy at a new size with the existing cells kept centered, plus the (x, y)
+    /// shift that was applied to them.
+    pub fn resized(&self, width: usize, height: usize) -> (Self, (i64, i64)) {
+        let mut resized = Self::new(width, height);
+        resized.topology = self.topology;
+        resized.rule = self.rule;
+        resized.automaton = self.automaton.clone();
+        resized.turmite = self.turmite.clone();
+        resized.engine = self.engine;
+        resized.threads = self.threads;
+        resized.noise = self.noise;
+        resized.generation = self.generation;
+        let shift_x = (width as i64 - self.width as i64) / 2;
+        let shift_y = (height as i64 - self.height as i64) / 2;
+        for (x, y) in self.live_cells() {
+            resized.set(x + shift_x, y + shift_y, true);
+        }
+        for (&(x, y), &state) in &self.higher {
+            if resized.contains(x + shift_x, y + shift_y) {
+                resized.higher.insert((x + shift_x, y + shift_y), state);
+            }
+        }
+        let ages = self.ages.as_ref().map(|ages| ages.shifted(shift_x, shift_y, |x, y| resized.contains(x, y)));
+        resized.ages = ages;
+        resized.rainbow = self.rainbow.as_ref().map(|rainbow| rainbow.shifted(shift_x, shift_y, |x, y| resized.contains(x, y)));
+        resized.ants = self.ants.as_ref().map(|ants| {
+            let shifted = ants.iter().map(|&ant| Ant { x: ant.x + shift_x, y: ant.y + shift_y, ..ant });
+            shifted.filter(|ant| resized.contains(ant.x, ant.y)).collect()
+        });
+        (resized, (shift_x, shift_y))
+    }
+
+    /// Sets the pattern's live cells with its top-left corner at (left, top), clipping
+    /// anything that falls outside the grid.
+    pub fn place(&mut self, pattern: &Pattern, left: i64, top: i64) {
+        for &(px, py) in &pattern.cells {
+            self.set(left + px as i64, top + py as i64, true);
+        }
+    }
+
+    /// Advances the universe by one generation.
+    pub fn step(&mut self) {
+        self.advance(1);
+    }
+
+    /// Advances the universe by any number of generations. HashLife jumps there directly;
+    /// the other engines step one generation at a time. Tracked ages are only approximate after a
+    /// jump over several generations at once. Rules with decay or colors always go one
+    /// generation at a time, on the CPU, and the bit-packed and GPU engines step non-totalistic
+    /// rules cell by cell. Margolus rules also go one generation at a time, as the blocks shift with the
+    /// generation count, and so do ants and elementary rules, which add a row per generation.
+    /// Noise is added after each generation, so with noise everything goes one at a time.
+    pub fn advance(&mut self, generations: u64) {
+        profile_scope!();
+        if !self.noise.is_active() {
+            self.advance_rule(generations);
+            return;
+        }
+        for _ in 0..generations {
+            let before = self.cells.clone();
+            self.advance_rule(1);
+            self.add_noise(&before);
+        }
+    }
+
+    /// The universe after each further generation in turn, leaving this one as it is, to drive
+    /// it without the app: `universe.generations().take(100).map(|u| u.population())`. The
+    /// iterator never ends. Every item is a copy, so to jump far ahead `advance` is faster.
+    pub fn generations(&self) -> Generations {
+        Generations { universe: self.clone() }
+    }
+
+    /// Advances by the rule, automaton or ants alone.
+    fn advance_rule(&mut self, generations: u64) {
+        self.generation += generations;
+        if self.ants.is_some() {
+            for _ in 0..generations {
+                self.step_ants();
+                self.age_cells(1);
+            }
+            return;
+        }
+        if let Some(automaton) = self.automaton.clone() {
+            for _ in 0..generations {
+                self.step_automaton(automaton.as_ref());
+                self.age_cells(1);
+            }
+            return;
+        }
+        if let Some(code) = self.rule.elementary {
+            for generation in self.generation - generations..self.generation {
+                self.cells = match &self.cells {
+                    Cells::Dense(cells) => Cells::Dense(cells.step_elementary(self.topology, code, generation)),
+                    Cells::Sparse(cells) => Cells::Sparse(step_sparse_elementary(cells, code, generation as i64)),
+                    Cells::HashLife(_) => unreachable!("HashLife doesn't support one-dimensional rules"),
+                };
+                self.age_cells(1);
+            }
+            return;
+        }
+        if let Some(blocks) = self.rule.margolus {
+            for generation in self.generation - generations..self.generation {
+                self.step_blocks(blocks, Margolus::offset(generation));
+                self.age_cells(1);
+            }
+            return;
+        }
+        if self.rule.has_decay() {
+            for _ in 0..generations {
+                self.step_with_decay();
+                self.age_cells(1);
+            }
+            return;
+        }
+        if self.rule.colors > 1 {
+            for _ in 0..generations {
+                self.step_with_colors();
+                self.age_cells(1);
+            }
+            return;
+        }
+        if let Cells::HashLife(hashlife) = &mut self.cells {
+            hashlife.advance(generations, self.rule);
+            self.age_cells(generations);
+            return;
+        }
+        #[cfg(all(feature = "gpu", not(target_arch = "wasm32")))]
+        if let (Engine::Gpu, Cells::Dense(cells), true) = (self.engine, &self.cells, self.rule.is_totalistic()) {
+            if let Some(next) = crate::gpu::advance(cells, self.topology, self.rule, generations) {
+                self.cells = Cells::Dense(next);
+                self.age_cells(generations);
+                return;
+            }
+        }
+        for _ in 0..generations {
+            self.step_once();
+            self.age_cells(1);
+        }
+    }
+
+    /// Brings the tracked ages and colors up to date after the cells moved on by `generations`.
+    fn age_cells(&mut self, generations: u64) {
+        profile_scope!();
+        if let Some(mut ages) = self.ages.take() {
+            ages.advance(self.live_cells(), generations);
+            self.ages = Some(ages);
+        }
+        if let Some(mut rainbow) = self.rainbow.take() {
+            rainbow.advance(self.live_cells(), |(x, y)| self.neighbors(x, y));
+            self.rainbow = Some(rainbow);
+        }
+    }
+
+    /// Flips cells at random after a step from `before`: with probability `temperature` each
+    /// cell that was alive or next to a live one, as if the rule had said the opposite, and in
+    /// a finite grid a fraction `flips` of all cells. Cells are flipped as if by hand, so they
+    /// leave any higher state.
+    fn add_noise(&mut self, before: &Cells) {
+        let Noise { temperature, flips, .. } = self.noise;
+        let mut rng = self.noise.rng(self.generation);
+        if self.is_infinite() {
+            // Sorted, as the random numbers must go to the same cells every time
+            let near: HashSet<(i64, i64)> = before.live_cells().flat_map(|(x, y)| self.neighbors(x, y).into_iter().chain([(x, y)])).collect();
+            let mut near: Vec<(i64, i64)> = near.into_iter().collect();
+            near.sort_unstable();
+            for (x, y) in near {
+                if rng.chance(temperature) {
+                    self.toggle(x, y);
+                }
+            }
+            return;
+        }
+
+        // Skip from one picked cell to the next rather than roll for each
+        let (width, cells) = (self.width as u64, (self.width * self.height) as u64);
+        for (probability, near_only) in [(temperature, true), (flips, false)] {
+            if probability <= 0.0 {
+                continue;
+            }
+            let mut i = rng.gap(probability);
+            while i < cells {
+                let (x, y) = ((i % width) as i64, (i / width) as i64);
+                if !near_only || before.get(x, y) || self.neighbors(x, y).into_iter().any(|(x, y)| before.get(x, y)) {
+                    self.toggle(x, y);
+                }
+                i = i.saturating_add(1 + rng.gap(probability));
+            }
+        }
+    }
+
+    /// The cells the rule counts as neighbors of (x, y), where the topology puts them.
+    fn neighbors(&self, x: i64, y: i64) -> Vec<(i64, i64)> {
+        let neighborhood = self.rule.larger_than_life.map_or(self.rule.neighborhood, |range| range.neighborhood);
+        let offsets = neighborhood.offsets(rule::is_upward(x, y));
+        offsets.into_iter().filter_map(|(dx, dy)| self.resolve(x + dx, y + dy)).collect()
+    }
+
+    /// One generation of a rule with decay. The live cells step as under the rule without decay,
+    /// except that dying cells can't be born, and cells that didn't survive start dying.
+    fn step_with_decay(&mut self) {
+        let live: Vec<(i64, i64)> = self.live_cells().collect();
+        match &mut self.cells {
+            Cells::HashLife(hashlife) => hashlife.advance(1, self.rule),
+            _ => self.step_once(),
+        }
+        let blocked: Vec<(i64, i64)> = self.higher.keys().copied().filter(|&(x, y)| self.get(x, y)).collect();
+        for (x, y) in blocked {
+            self.set_alive(x, y, false);
+        }
+
+        let states = self.rule.states;
+        self.higher.retain(|_, state| {
+            *state += 1;
+            *state < states
+        });
+        for (x, y) in live {
+            if !self.get(x, y) {
+                self.higher.insert((x, y), 2);
+            }
+        }
+    }
+
+    /// One generation of a colored rule. The live cells step as under the rule without colors,
+    /// then survivors keep their colors and newborns take theirs from their parents.
+    fn step_with_colors(&mut self) {
+        let (before, colors) = (self.cells.clone(), std::mem::take(&mut self.higher));
+        self.step_once();
+        let was_alive = |(x, y): (i64, i64)| before.get(x, y);
+        let color = |cell: (i64, i64)| colors.get(&cell).copied().unwrap_or(1);
+
+        let live: Vec<(i64, i64)> = self.live_cells().collect();
+        for (x, y) in live {
+            let color = if was_alive((x, y)) {
+                color((x, y))
+            } else {
+                let mut parents = vec![0; self.rule.colors as usize];
+                for (dx, dy) in self.rule.neighborhood.offsets(rule::is_upward(x, y)) {
+                    if let Some(parent) = self.resolve(x + dx, y + dy).filter(|&cell| was_alive(cell)) {
+                        parents[color(parent) as usize - 1] += 1;
+                    }
+                }
+                self.rule.newborn_color(&parents)
+            };
+            if color > 1 {
+                self.higher.insert((x, y), color);
+            }
+        }
+    }
+
+    /// The cell at (x, y), or where the topology puts it if that is off a finite grid, or
+    /// `None` past a dead edge.
+    fn resolve(&self, x: i64, y: i64) -> Option<(i64, i64)> {
+        match self.contains(x, y) {
+            true => Some((x, y)),
+            false => self.topology.resolve(x as isize, y as isize, self.width, self.height).map(|(x, y)| (x as i64, y as i64)),
+        }
+    }
+
+    /// One generation under an automaton. Every cell of a finite grid is looked up in it,
+    /// but in an infinite universe only cells that aren't dead and their neighbors, as empty
+    /// space stays empty. Neighborhoods repeat a lot, so each is looked up only once.
+    fn step_automaton(&mut self, automaton: &dyn Automaton) {
+        let candidates: Vec<(i64, i64)> = if self.is_infinite() {
+            let occupied = self.live_cells().chain(self.higher.keys().copied());
+            let near: HashSet<(i64, i64)> =
+                occupied.flat_map(|(x, y)| NEIGHBORS.iter().map(move |(dx, dy)| (x + dx, y + dy)).chain([(x, y)])).collect();
+            near.into_iter().collect()
+        } else {
+            let (width, height) = (self.width as i64, self.height as i64);
+            (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).collect()
+        };
+        let state_at = |x: i64, y: i64| self.resolve(x, y).map_or(0, |(x, y)| self.state(x, y));
+
+        let mut known: HashMap<(u8, [u8; 8]), u8> = HashMap::new();
+        let next: Vec<((i64, i64), u8)> = candidates
+            .into_iter()
+            .map(|(x, y)| {
+                let center = self.state(x, y);
+                let neighbors = NEIGHBORS.map(|(dx, dy)| state_at(x + dx, y + dy));
+                ((x, y), *known.entry((center, neighbors)).or_insert_with(|| automaton.next(center, neighbors)))
+            })
+            .filter(|&(_, state)| state != 0)
+            .collect();
+
+        self.cells = match &self.cells {
+            Cells::Dense(_) => Cells::Dense(BitGrid::new(self.width, self.height)),
+            _ => Cells::Sparse(HashSet::new()),
+        };
+        self.higher.clear();
+        for ((x, y), state) in next {
+            match state {
+                1 => self.set_alive(x, y, true),
+                _ => {
+                    self.higher.insert((x, y), state);
+                }
+            }
+        }
+    }
+
+    /// Moves each ant in turn. Ants that walk off a finite grid come back where the topology
+    /// puts them, still facing the same way, or are gone past a dead edge.
+    fn step_ants(&mut self) {
+        let (mut ants, turmite) = (self.ants.take().unwrap_or_default(), std::mem::take(&mut self.turmite));
+        ants.retain_mut(|ant| {
+            let color = ant.turn(&turmite, self.state(ant.x, ant.y));
+            self.set_alive(ant.x, ant.y, color == 1);
+            match color {
+                0 | 1 => self.higher.remove(&(ant.x, ant.y)),
+                _ => self.higher.insert((ant.x, ant.y), color),
+            };
+            let (dx, dy) = ant.heading.offset();
+            match self.resolve(ant.x + dx, ant.y + dy) {
+                Some((x, y)) => {
+                    (ant.x, ant.y) = (x, y);
+                    true
+                }
+                None => false,
+            }
+        });
+        (self.ants, self.turmite) = (Some(ants), turmite);
+    }
+
+    /// One generation of a Margolus rule, with the blocks starting `offset` cells from the origin.
+    fn step_blocks(&mut self, blocks: Margolus, offset: i64) {
+        self.cells = match &self.cells {
+            Cells::Dense(cells) => Cells::Dense(cells.step_blocks(self.topology, blocks, offset)),
+            Cells::Sparse(cells) => Cells::Sparse(step_sparse_blocks(cells, blocks, offset)),
+            Cells::HashLife(_) => unreachable!("HashLife doesn't support block rules"),
+        };
+    }
+
+    fn step_once(&mut self) {
+        profile_scope!();
+        match &self.cells {
+            Cells::Dense(cells) => {
+                let new_cells = match (self.engine, self.rule.larger_than_life) {
+                    (_, Some(range)) => cells.step_range(self.topology, range, self.threads),
+                    (Engine::Naive, _) => self.step_dense(cells),
+                    // Bit-sliced neighbor counts cover all 8 adjacent cells and lose where they are
+                    _ if self.rule.isotropic.is_none() && self.rule.neighborhood != Neighborhood::MOORE => {
+                        cells.step_wide(self.topology, self.rule, self.threads)
+                    }
+                    _ if !self.rule.is_totalistic() => self.step_dense(cells),
+                    // The GPU engine only gets here when no GPU could be used
+                    (Engine::BitPacked | Engine::Gpu, _) => cells.step(self.topology, self.rule, self.threads),
+                    (Engine::Simd, _) => cells.step_simd(self.topology, self.rule, self.threads),
+                    _ => self.step_dense(cells),
+                };
+                self.cells = Cells::Dense(new_cells);
+            }
+            Cells::Sparse(cells) => {
+                let new_cells = match self.rule.larger_than_life {
+                    Some(range) => step_sparse_range(cells, range),
+                    None => step_sparse(cells, self.rule),
+                };
+                self.cells = Cells::Sparse(new_cells);
+            }
+            Cells::HashLife(_) => unreachable!("HashLife advances in advance()"),
+        }
+    }
+
+    /// One generation computed cell by cell.
+    fn step_dense(&self, cells: &BitGrid) -> BitGrid {
+        let mut new_cells = BitGrid::new(self.width, self.height);
+        // Only triangular grids have different neighborhoods for different cells
+        let offsets = [false, true].map(|upward| self.rule.neighborhood.offsets(upward));
+
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let offsets = &offsets[rule::is_upward(x as i64, y as i64) as usize];
+                let (neighborhood, count) = self.alive_neighbors(cells, x, y, offsets);
+                let alive = cells.get(x, y);
+                let next = if self.rule.neighborhood.is_adjacent() {
+                    self.rule.next(alive, neighborhood)
+                } else {
+                    self.rule.next_count(alive, count)
+                };
+                new_cells.set(x, y, next);
+            }
+        }
+
+        new_cells
+    }
+
+    /// The live neighbors of a cell among the given offsets: those next to it as bits set by
+    /// `rule::neighbor_bit`, and how many there are in all.
+    fn alive_neighbors(&self, cells: &BitGrid, x: usize, y: usize, offsets: &[(i64, i64)]) -> (u8, u32) {
+        let (mut neighborhood, mut count) = (0, 0);
+        // Cells away from the edges can index their neighbors directly
+        let (reach, r) = (self.rule.neighborhood.reach() as usize, self.rule.neighborhood.radius as usize);
+        let interior = x >= reach && y >= r && x + reach < self.width && y + r < self.height;
+
+        for &(dx, dy) in offsets {
+            let nx = x as isize + dx as isize;
+            let ny = y as isize + dy as isize;
+
+            let alive = if interior {
+                cells.get(nx as usize, ny as usize)
+            } else {
+                self.topology
+                    .resolve(nx, ny, self.width, self.height)
+                    .is_some_and(|(rx, ry)| cells.get(rx, ry))
+            };
+            if alive {
+                neighborhood |= neighbor_bit(dx, dy);
+                count += 1;
+            }
+        }
+
+        (neighborhood, count)
+    }
+}
+
+/// A universe as saved with the `serde` feature: its grid, topology, rule and generation, with
+/// the live cells as RLE from the top-left one. Engine, rule file, ants, ages, colors and noise
+/// aren't kept.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Saved {
+    width: usize,
+    height: usize,
+    infinite: bool,
+    topology: Topology,
+    rule: Rule,
+    generation: u64,
+    origin: (i64, i64), // where the RLE's top-left corner goes
+    cells: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    higher: Vec<(i64, i64, u8)>, // cells in states above 1, as (x, y, state)
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Universe {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let live: Vec<(i64, i64)> = self.live_cells().collect();
+        let origin = (live.iter().map(|&(x, _)| x).min().unwrap_or(0), live.iter().map(|&(_, y)| y).min().unwrap_or(0));
+        let cells = crate::formats::rle::write_cells(&Pattern::from_cells(live)).trim_end().to_string();
+        let mut higher: Vec<(i64, i64, u8)> = self.higher_states().map(|((x, y), state)| (x, y, state)).collect();
+        higher.sort_unstable();
+        let saved = Saved {
+            width: self.width,
+            height: self.height,
+            infinite: self.is_infinite(),
+            topology: self.topology,
+            rule: self.rule,
+            generation: self.generation,
+            origin,
+            cells,
+            higher,
+        };
+        saved.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Universe {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let saved = Saved::deserialize(deserializer)?;
+        let mut universe = Universe::new(saved.width, saved.height);
+        universe.set_topology(saved.topology);
+        universe.set_rule(saved.rule).map_err(D::Error::custom)?;
+        universe.set_infinite(saved.infinite);
+        if universe.is_infinite() != saved.infinite {
+            return Err(D::Error::custom(format!("{} brings empty space to life, so it needs a finite universe", saved.rule)));
+        }
+        let pattern = crate::formats::rle::parse(&saved.cells).map_err(D::Error::custom)?;
+        universe.place(&pattern, saved.origin.0, saved.origin.1);
+        for (x, y, state) in saved.higher {
+            universe.set_state(x, y, state);
+        }
+        universe.generation = saved.generation;
+        Ok(universe)
+    }
+}
+
+/// Successive generations of a universe, from `Universe::generations`.
+#[derive(Clone)]
+pub struct Generations {
+    universe: Universe,
+}
+
+impl Iterator for Generations {
+    type Item = Universe;
+
+    fn next(&mut self) -> Option<Universe> {
+        self.universe.advance(1);
+        Some(self.universe.clone())
+    }
+}
+
+/// One generation of an unbounded universe: only live cells and their neighbors can change,
+/// as long as the rule has no births from nothing.
+fn step_sparse(cells: &HashSet<(i64, i64)>, rule: Rule) -> HashSet<(i64, i64)> {
+    profile_scope!();
+    // Each live cell is a neighbor of its neighbors, in the opposite direction
+    let offsets = [false, true].map(|upward| rule.neighborhood.offsets(upward));
+    let mut neighborhoods: HashMap<(i64, i64), (u8, u32)> = HashMap::new();
+    for &(x, y) in cells {
+        for &(dx, dy) in &offsets[rule::is_upward(x, y) as usize] {
+            let (neighborhood, count) = neighborhoods.entry((x + dx, y + dy)).or_default();
+            *neighborhood |= neighbor_bit(-dx, -dy);
+            *count += 1;
+        }
+    }
+
+    // Live cells without live neighbors aren't counted above but may survive
+    for &cell in cells {
+        neighborhoods.entry(cell).or_default();
+    }
+
+    neighborhoods
+        .into_iter()
+        .filter(|&(cell, (neighborhood, count))| {
+            let alive = cells.contains(&cell);
+            if rule.neighborhood.is_adjacent() {
+                rule.next(alive, neighborhood)
+            } else {
+                rule.next_count(alive, count)
+            }
+        })
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+/// One generation of an unbounded universe under a Margolus rule: only blocks with live cells can
+/// change, as long as empty blocks stay empty.
+fn step_sparse_blocks(cells: &HashSet<(i64, i64)>, blocks: Margolus, offset: i64) -> HashSet<(i64, i64)> {
+    let corners: HashSet<(i64, i64)> = cells
+        .iter()
+        .map(|&(x, y)| (x - (x - offset).rem_euclid(2), y - (y - offset).rem_euclid(2)))
+        .collect();
+    let mut next = HashSet::new();
+    for (left, top) in corners {
+        let block = Margolus::CELLS
+            .iter()
+            .enumerate()
+            .fold(0, |block, (bit, &(dx, dy))| block | (cells.contains(&(left + dx, top + dy)) as usize) << bit);
+        let block = blocks.table[block];
+        let alive = Margolus::CELLS.iter().enumerate().filter(|&(bit, _)| block & (1 << bit) != 0);
+        next.extend(alive.map(|(_, &(dx, dy))| (left + dx, top + dy)));
+    }
+    next
+}
+
+/// One generation of an unbounded universe under an elementary rule: the row at y = `row` gets a
+/// new row below it, which can only reach one cell past its live cells on either side.
+fn step_sparse_elementary(cells: &HashSet<(i64, i64)>, code: u8, row: i64) -> HashSet<(i64, i64)> {
+    let mut next: HashSet<(i64, i64)> = cells.iter().copied().filter(|&(_, y)| y != row + 1).collect();
+    let live = cells.iter().filter(|&&(_, y)| y == row);
+    let candidates: HashSet<i64> = live.flat_map(|&(x, _)| [x - 1, x, x + 1]).collect();
+    let alive = |x: i64| cells.contains(&(x, row)) as u8;
+    let born = candidates.into_iter().filter(|&x| code >> (alive(x - 1) << 2 | alive(x) << 1 | alive(x + 1)) & 1 != 0);
+    next.extend(born.map(|x| (x, row + 1)));
+    next
+}
+
+/// One generation of an unbounded universe under a Larger than Life rule, counting neighbors
+/// only around live cells as `step_sparse` does, so far-apart cells cost no more than close ones.
+fn step_sparse_range(cells: &HashSet<(i64, i64)>, range: LargerThanLife) -> HashSet<(i64, i64)> {
+    profile_scope!();
+    let offsets = [false, true].map(|upward| range.neighborhood.offsets(upward));
+    let mut counts: HashMap<(i64, i64), u32> = HashMap::new();
+    for &(x, y) in cells {
+        for &(dx, dy) in &offsets[rule::is_upward(x, y) as usize] {
+            *counts.entry((x + dx, y + dy)).or_default() += 1;
+        }
+    }
+
+    // Live cells without live neighbors aren't counted above but may survive
+    for &cell in cells {
+        counts.entry(cell).or_default();
+    }
+
+    counts
+        .into_iter()
+        .filter(|&(cell, count)| {
+            let alive = cells.contains(&cell);
+            let count = count + (alive && range.middle) as u32;
+            let (min, max) = if alive { range.survival } else { range.birth };
+            (min..=max).contains(&count)
+        })
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    const RULES: [&str; 3] = ["B3/S23", "B36/S23", "B2-a3/S12ce"];
+
+    /// A soup filling a third of the grid, the same for the same seed.
+    fn soup(width: usize, height: usize, seed: u64) -> Universe {
+        let mut universe = Universe::new(width, height);
+        let mut rng = Rng::new(seed);
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                universe.set(x, y, rng.chance(0.35));
+            }
+        }
+        universe
+    }
+
+    fn sorted_cells(universe: &Universe) -> Vec<(i64, i64)> {
+        let mut cells: Vec<_> = universe.live_cells().collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    /// Steps both universes and checks they hold the same cells after every generation.
+    fn assert_agree(mut expected: Universe, mut actual: Universe, generations: u64, what: &str) {
+        for generation in 1..=generations {
+            expected.step();
+            actual.step();
+            assert_eq!(sorted_cells(&actual), sorted_cells(&expected), "{what}, generation {generation}");
+        }
+    }
+
+    #[test]
+    fn word_parallel_engines_match_naive_under_every_topology() {
+        for (name, topology) in Topology::PRESETS {
+            for rule in RULES {
+                // 70 columns puts an edge partway through the second word of each row
+                let mut naive = soup(70, 45, 7);
+                naive.set_topology(topology);
+                naive.set_rule(Rule::parse(rule).unwrap()).unwrap();
+                naive.set_engine(Engine::Naive).unwrap();
+                for engine in [Engine::BitPacked, Engine::Simd] {
+                    let mut universe = naive.clone();
+                    universe.set_engine(engine).unwrap();
+                    assert_agree(naive.clone(), universe, 60, &format!("{} on {name} under {rule}", engine.name()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hashlife_matches_naive() {
+        for rule in RULES {
+            let mut naive = soup(48, 40, 11);
+            naive.set_rule(Rule::parse(rule).unwrap()).unwrap();
+            naive.set_engine(Engine::Naive).unwrap();
+            naive.set_infinite(true);
+            let mut hashlife = naive.clone();
+            hashlife.set_engine(Engine::HashLife).unwrap();
+            assert_agree(naive.clone(), hashlife.clone(), 60, &format!("HashLife under {rule}"));
+
+            // Jumps land where stepping one generation at a time does
+            naive.advance(200);
+            hashlife.advance(200);
+            assert_eq!(sorted_cells(&hashlife), sorted_cells(&naive), "HashLife jumping under {rule}");
+        }
+    }
+
+    #[test]
+    fn unbounded_larger_than_life_matches_a_grid_it_never_reaches_the_edges_of() {
+        for (name, rule) in [("Bosco's Rule", "R5,C0,M1,S34..58,B34..45,NM"), ("Majority", "R4,C0,M1,S41..81,B41..81,NM")] {
+            let rule = Rule::parse(rule).unwrap();
+            // Cells spread at most a radius a generation, so 20 generations stay off the edges
+            let mut finite = Universe::new(260, 260);
+            finite.set_rule(rule).unwrap();
+            finite.place(&soup(30, 30, 3).to_pattern(), 115, 115);
+            let mut unbounded = finite.clone();
+            unbounded.set_infinite(true);
+            assert_agree(finite, unbounded, 20, name);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! WebM and MP4 videos, encoded by an `ffmpeg` found on the PATH: frames are piped to it raw,
+//! one at a time, so even long runs never have to fit in memory.
+
+use std::io::Write;
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use eframe::egui;
+
+use crate::animation::AnimationFormat;
+
+/// An ffmpeg process making a video file out of the frames written to it.
+pub struct VideoWriter {
+    child: Child,
+    stdin: Option<ChildStdin>, // taken to tell ffmpeg the frames are done
+    size: [usize; 2],
+}
+
+impl VideoWriter {
+    /// Starts ffmpeg on a video of frames of the given size, each shown `delay` milliseconds.
+    pub fn new(path: &str, format: AnimationFormat, [width, height]: [usize; 2], delay: u16) -> Result<Self, String> {
+        let codec: &[&str] = match format {
+            AnimationFormat::WebM => &["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "32"],
+            AnimationFormat::Mp4 => &["-c:v", "libx264", "-crf", "20", "-movflags", "+faststart"],
+            _ => return Err(format!("{} isn't a video format", format.name())),
+        };
+        let input = ["-f", "rawvideo", "-pix_fmt", "rgb24", "-s", &format!("{width}x{height}"), "-framerate", &format!("1000/{delay}"), "-i", "-"];
+        let mut child = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(input)
+            // Players want 4:2:0 color, which needs even sides
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p"])
+            .args(codec)
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Couldn't run ffmpeg: {e}"))?;
+        let stdin = child.stdin.take();
+        Ok(Self { child, stdin, size: [width, height] })
+    }
+
+    pub fn write(&mut self, frame: &egui::ColorImage) -> Result<(), String> {
+        if frame.size != self.size {
+            return Err("The frames of a video must all be the same size".to_string());
+        }
+        let data: Vec<u8> = frame.pixels.iter().flat_map(|color| [color.r(), color.g(), color.b()]).collect();
+        let stdin = self.stdin.as_mut().ok_or("The video is already finished")?;
+        stdin.write_all(&data).map_err(|e| format!("ffmpeg stopped taking frames: {e}"))
+    }
+
+    /// Ends the video and waits for ffmpeg to finish the file, returning what it complained
+    /// about if it failed.
+    pub fn finish(mut self) -> Result<(), String> {
+        drop(self.stdin.take());
+        let output = self.child.wait_with_output().map_err(|e| e.to_string())?;
+        match output.status.success() {
+            true => Ok(()),
+            false => Err(format!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim())),
+        }
+    }
+}
+
+/// Writes the frames, all of the given size and each shown `delay` milliseconds, to a video.
+pub fn save(path: &str, format: AnimationFormat, size: [usize; 2], frames: impl Iterator<Item = egui::ColorImage>, delay: u16) -> Result<(), String> {
+    let mut writer = VideoWriter::new(path, format, size, delay)?;
+    for frame in frames {
+        if let Err(e) = writer.write(&frame) {
+            // ffmpeg's own complaint says more than the broken pipe
+            return Err(writer.finish().err().unwrap_or(e));
+        }
+    }
+    writer.finish()
+}
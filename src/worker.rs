@@ -0,0 +1,298 @@
+//! Runs the simulation on a dedicated thread so that heavy stepping never blocks painting or
+//! input. The UI sends a snapshot of the universe and gets the advanced universe back. On the
+//! web that thread is a Web Worker, see `web`.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+#[cfg(not(target_arch = "wasm32"))]
+use eframe::egui;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::universe::Universe;
+
+#[cfg(target_arch = "wasm32")]
+pub use web::Worker;
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Job {
+    id: u64,
+    universe: Universe,
+    generations: u64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct Worker {
+    jobs: Sender<Job>,
+    results: Receiver<(u64, Universe, f64)>,
+    pending: Option<u64>, // id of the job whose result is still wanted
+    next_id: u64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Worker {
+    /// Spawns the simulation thread, which wakes up `ctx` whenever a result is ready. It exits
+    /// once the worker is dropped.
+    pub fn new(ctx: egui::Context) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        thread::Builder::new()
+            .name("simulation".to_string())
+            .spawn(move || {
+                for mut job in job_receiver {
+                    let started = Instant::now();
+                    job.universe.advance(job.generations);
+                    if result_sender.send((job.id, job.universe, started.elapsed().as_secs_f64())).is_err() {
+                        break;
+                    }
+                    ctx.request_repaint();
+                }
+            })
+            .expect("failed to spawn the simulation thread");
+        Self { jobs, results, pending: None, next_id: 0 }
+    }
+
+    /// Whether a job has been submitted and its result not collected yet.
+    pub fn is_busy(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Starts advancing a snapshot of the universe in the background.
+    pub fn submit(&mut self, universe: Universe, generations: u64) {
+        self.next_id += 1;
+        self.pending = Some(self.next_id);
+        self.jobs.send(Job { id: self.next_id, universe, generations }).expect("simulation thread stopped");
+    }
+
+    /// Forgets the job in flight, e.g. because the universe was edited in the meantime.
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    /// Returns the result of the pending job if it has finished, with the seconds the thread
+    /// spent advancing it. The thread can't fail, unlike the Web Worker.
+    pub fn poll(&mut self) -> Option<Result<(Universe, f64), String>> {
+        while let Ok((id, universe, seconds)) = self.results.try_recv() {
+            if Some(id) == self.pending {
+                self.pending = None;
+                return Some(Ok((universe, seconds)));
+            }
+        }
+        None
+    }
+}
+
+/// The page can't wait on a thread, so a Web Worker running this same module from `worker.js`,
+/// next to `index.html`, does the stepping, and universes go back and forth as RON. That format
+/// leaves out automata, ants, ages and colors, and HashLife would lose its memo, so universes
+/// with those step on the page as before. So do steps quicker than sending the cells over.
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::sync::mpsc::{self, Receiver};
+
+    use eframe::egui;
+    use wasm_bindgen::prelude::*;
+
+    use crate::universe::{Engine, Universe};
+
+    const SCRIPT: &str = "./worker.js";
+    const OFFLOAD_AFTER: f64 = 0.008; // Seconds a step takes on the page before the next ones go to the worker
+    const RETURN_UNDER: f64 = 0.002; // Seconds a step takes in the worker before the next ones come back
+
+    /// A job, with the settings the RON format leaves out.
+    #[cfg(feature = "serde")]
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Job<U> {
+        id: u64,
+        universe: U,
+        generations: u64,
+        engine: Engine,
+        noise: crate::noise::Noise,
+    }
+
+    #[cfg(feature = "serde")]
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Done {
+        id: u64,
+        universe: Universe,
+        seconds: f64,
+    }
+
+    pub struct Worker {
+        remote: Option<Remote>, // the Web Worker, until it fails
+        messages: Receiver<Result<String, String>>, // finished jobs as RON, or why the worker failed
+        pending: Option<u64>, // id of the job whose result is still wanted
+        sent: Option<(Universe, u64)>, // the job in the worker, to step here should it fail
+        done: Option<(u64, Universe, f64)>, // a job stepped here
+        offload: bool, // whether steps are slow enough to go to the worker
+        next_id: u64,
+        ctx: egui::Context,
+    }
+
+    impl Worker {
+        /// Starts the Web Worker, which wakes up `ctx` whenever a result is ready. Without it,
+        /// everything steps on the page.
+        pub fn new(ctx: egui::Context) -> Self {
+            let (sender, messages) = mpsc::channel();
+            let remote = Remote::spawn(sender, ctx.clone()).map_err(|e| log::warn!("Stepping on the page, as the simulation worker didn't start: {e:?}")).ok();
+            Self { remote, messages, pending: None, sent: None, done: None, offload: false, next_id: 0, ctx }
+        }
+
+        /// Whether a job has been submitted and its result not collected yet.
+        pub fn is_busy(&self) -> bool {
+            self.pending.is_some()
+        }
+
+        /// Advances a snapshot of the universe, in the worker if steps have been slow.
+        pub fn submit(&mut self, universe: Universe, generations: u64) {
+            self.next_id += 1;
+            self.pending = Some(self.next_id);
+            if let Some(remote) = self.remote.as_ref().filter(|_| self.offload && offloadable(&universe)) {
+                match remote.post(self.next_id, &universe, generations) {
+                    Ok(()) => {
+                        self.sent = Some((universe, generations));
+                        return;
+                    }
+                    Err(e) => log::warn!("Couldn't send a step to the simulation worker: {e:?}"),
+                }
+            }
+            self.step_here(self.next_id, universe, generations);
+        }
+
+        /// Forgets the job in flight, e.g. because the universe was edited in the meantime.
+        pub fn cancel(&mut self) {
+            self.pending = None;
+            self.sent = None;
+        }
+
+        /// Returns the result of the pending job if it has finished, with the seconds spent
+        /// advancing it, or why the result can't be used.
+        pub fn poll(&mut self) -> Option<Result<(Universe, f64), String>> {
+            while let Ok(message) = self.messages.try_recv() {
+                match message.and_then(|text| decode(&text)) {
+                    Ok((id, mut next, seconds)) if Some(id) == self.pending => {
+                        self.pending = None;
+                        self.offload = seconds > RETURN_UNDER;
+                        if let Some((universe, _)) = self.sent.take() {
+                            if let Err(e) = next.set_engine(universe.engine()) {
+                                return Some(Err(format!("The step from the simulation worker can't go back on {}: {e}", universe.engine().name())));
+                            }
+                            next.set_threads(universe.threads());
+                            next.set_noise(universe.noise());
+                        }
+                        return Some(Ok((next, seconds)));
+                    }
+                    Ok(_) => {} // a job cancelled since
+                    Err(e) => {
+                        log::warn!("Stepping on the page from now on, as the simulation worker failed: {e}");
+                        self.remote = None;
+                        if let (Some(id), Some((universe, generations))) = (self.pending, self.sent.take()) {
+                            self.step_here(id, universe, generations);
+                        }
+                    }
+                }
+            }
+            match self.done.take() {
+                Some((id, next, seconds)) if Some(id) == self.pending => {
+                    self.pending = None;
+                    Some(Ok((next, seconds)))
+                }
+                _ => None,
+            }
+        }
+
+        fn step_here(&mut self, id: u64, mut universe: Universe, generations: u64) {
+            let started = js_sys::Date::now();
+            universe.advance(generations);
+            let seconds = (js_sys::Date::now() - started) / 1000.0;
+            self.offload = seconds > OFFLOAD_AFTER;
+            self.done = Some((id, universe, seconds));
+            self.ctx.request_repaint();
+        }
+    }
+
+    /// Whether the universe comes back from the worker just as it would have stepped here.
+    fn offloadable(universe: &Universe) -> bool {
+        let plain = universe.automaton().is_none() && universe.ants().is_none() && universe.engine() != Engine::HashLife;
+        cfg!(feature = "serde") && plain && universe.tracking().is_none() && universe.rainbow_brush().is_none()
+    }
+
+    /// The Web Worker and the handlers it calls back, which live as long as it does.
+    struct Remote {
+        worker: web_sys::Worker,
+        _handlers: [Closure<dyn FnMut(web_sys::Event)>; 2],
+    }
+
+    impl Remote {
+        fn spawn(sender: mpsc::Sender<Result<String, String>>, ctx: egui::Context) -> Result<Self, JsValue> {
+            use wasm_bindgen::JsCast;
+            let options = web_sys::WorkerOptions::new();
+            options.set_type(web_sys::WorkerType::Module);
+            let worker = web_sys::Worker::new_with_options(SCRIPT, &options)?;
+            let send = move |message| {
+                let _ = sender.send(message);
+                ctx.request_repaint();
+            };
+            let failed = send.clone();
+            let on_message = Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
+                let text = event.dyn_ref::<web_sys::MessageEvent>().and_then(|event| event.data().as_string());
+                send(text.ok_or_else(|| "it sent something other than a job".to_string()));
+            });
+            // Scripts that fail to load give a plain event, and errors thrown in them an ErrorEvent
+            let on_error = Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
+                let message = event.dyn_ref::<web_sys::ErrorEvent>().map_or_else(|| format!("couldn't load {SCRIPT}"), |event| event.message());
+                failed(Err(message));
+            });
+            worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+            Ok(Self { worker, _handlers: [on_message, on_error] })
+        }
+
+        #[cfg(feature = "serde")]
+        fn post(&self, id: u64, universe: &Universe, generations: u64) -> Result<(), JsValue> {
+            let job = Job { id, universe, generations, engine: universe.engine(), noise: universe.noise() };
+            let text = ron::to_string(&job).map_err(|e| e.to_string())?;
+            self.worker.post_message(&JsValue::from_str(&text))
+        }
+
+        #[cfg(not(feature = "serde"))]
+        fn post(&self, _id: u64, _universe: &Universe, _generations: u64) -> Result<(), JsValue> {
+            Err("universes can only be sent with the serde feature".into())
+        }
+    }
+
+    impl Drop for Remote {
+        fn drop(&mut self) {
+            self.worker.terminate();
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn decode(text: &str) -> Result<(u64, Universe, f64), String> {
+        let done: Done = ron::from_str(text).map_err(|e| e.to_string())?;
+        Ok((done.id, done.universe, done.seconds))
+    }
+
+    #[cfg(not(feature = "serde"))]
+    fn decode(_text: &str) -> Result<(u64, Universe, f64), String> {
+        Err("universes can only be received with the serde feature".to_string())
+    }
+
+    /// Steps a job from the page, inside the Web Worker; `worker.js` calls it for each message.
+    #[cfg(feature = "serde")]
+    #[wasm_bindgen]
+    pub fn simulate(job: &str) -> Result<String, JsValue> {
+        let job: Job<Universe> = ron::from_str(job).map_err(|e| e.to_string())?;
+        let mut universe = job.universe;
+        universe.set_engine(job.engine)?;
+        universe.set_noise(job.noise);
+        let started = js_sys::Date::now();
+        universe.advance(job.generations);
+        let seconds = (js_sys::Date::now() - started) / 1000.0;
+        Ok(ron::to_string(&Done { id: job.id, universe, seconds }).map_err(|e| e.to_string())?)
+    }
+}
@@ -0,0 +1,9 @@
+// Steps universes for the app off the page's main thread, see src/worker.rs
+import init, { simulate } from './pkg/game_of_life.js';
+
+const ready = init(); // Jobs that come in before the module is loaded wait for it
+
+self.onmessage = async (event) => {
+    await ready;
+    self.postMessage(simulate(event.data));
+};